@@ -0,0 +1,114 @@
+//! Ticks a flat world full of falling and walking entities, with and without a thread pool.
+//! Run with `cargo bench --bench entities`.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rayon::ThreadPoolBuilder;
+
+use rsa_core::api::Api;
+use rsa_core::error::{Result, WrapErr};
+use rsa_core::logging::LevelFilter;
+use rsa_core::math::{rect, vec2};
+use rsa_core::reload;
+use rsa_core::settings::CHUNK_SIZE;
+use rsa_core::ty::{ChunkPos, ChunkSubPos, Tag};
+use rustaria::chunk::layer::tile::TilePrototype;
+use rustaria::chunk::layer::ChunkLayer;
+use rustaria::chunk::{Chunk, ChunkSystem};
+use rustaria::entity::component::gravity::GravityComp;
+use rustaria::entity::component::hitbox::HitboxComp;
+use rustaria::entity::component::humanoid::{HumanoidComp, HumanoidSettings};
+use rustaria::entity::component::physics::PhysicsComp;
+use rustaria::entity::component::pos::PositionComp;
+use rustaria::entity::EntitySystem;
+
+/// How many chunks wide the floor is.
+const WIDTH: i32 = 32;
+
+fn chunks() -> Result<ChunkSystem> {
+	rsa_core::initialize(LevelFilter::Off)?;
+	let mut api = Api::new_test();
+	api.load_simple_plugin(
+		r#"
+		reload.registry["tile"]:insert {
+			["r:air"] = {},
+			["r:dirt"] = { collision = true },
+		}
+		"#,
+	);
+	reload!((TilePrototype) => api);
+
+	let carrier = api.get_carrier();
+	let tiles = carrier.get::<TilePrototype>();
+	let air = tiles.create_from_tag(&Tag::rsa("air"))?;
+	let dirt = tiles.create_from_tag(&Tag::rsa("dirt"))?;
+
+	let mut chunks = ChunkSystem::new();
+	for x in 0..WIDTH {
+		let mut chunk = Chunk {
+			tiles: ChunkLayer::new([[air; CHUNK_SIZE]; CHUNK_SIZE]),
+		};
+		for x in 0..CHUNK_SIZE {
+			for y in 0..4 {
+				chunk.tiles[ChunkSubPos::new(x as u8, y as u8)] = dirt;
+			}
+		}
+		chunks.put_chunk(ChunkPos { x: x as u32, y: 0 }, chunk);
+	}
+	Ok(chunks)
+}
+
+fn entities(amount: usize) -> EntitySystem {
+	let mut entities = EntitySystem::new();
+	let width = (WIDTH as usize * CHUNK_SIZE) as f32;
+	for i in 0..amount {
+		entities.push((
+			PositionComp {
+				position: vec2((i as f32 * 1.7) % width, 5.0 + (i % 8) as f32),
+			},
+			PhysicsComp::default(),
+			HitboxComp {
+				hitbox: rect(0.0, 0.0, 1.0, 2.0),
+				touches_ground: false,
+				drop_through: false,
+			},
+			GravityComp { speed: 1.0 },
+			HumanoidComp {
+				settings: HumanoidSettings {
+					jump_frames: 15,
+					jump_speed: 20.0,
+					run_acceleration: 4.8 * 60.0,
+					run_slowdown: 1.2 * 60.0,
+					run_max_speed: 12.0 * 60.0,
+				},
+				dir: vec2(if i % 2 == 0 { 1.0 } else { -1.0 }, 0.0),
+				jumping: i % 3 == 0,
+				jump_frames_remaining: 0.0,
+			},
+		));
+	}
+	entities
+}
+
+fn tick(c: &mut Criterion) {
+	let chunks = chunks().unwrap();
+	let threads = std::thread::available_parallelism().map_or(4, |threads| threads.get());
+	let mut group = c.benchmark_group("entity_tick");
+	for amount in [1000, 4000, 16000] {
+		group.bench_with_input(BenchmarkId::new("single", amount), &amount, |b, &amount| {
+			let mut entities = entities(amount);
+			b.iter(|| entities.tick(&chunks).unwrap());
+		});
+
+		group.bench_with_input(BenchmarkId::new(format!("pool_{threads}"), amount), &amount, |b, &amount| {
+			let mut entities = entities(amount);
+			entities.set_thread_pool(Arc::new(ThreadPoolBuilder::new().num_threads(threads).build().unwrap()));
+			b.iter(|| entities.tick(&chunks).unwrap());
+		});
+	}
+	group.finish();
+}
+
+criterion_group!(benches, tick);
+criterion_main!(benches);
@@ -238,6 +238,13 @@ mod tests {
 			api.get_carrier().get::<FrogePrototype>().entries[0],
 			FrogePrototype { cool: true }
 		);
+
+		let carrier = api.get_carrier();
+		let registry = carrier.get::<FrogePrototype>();
+		// SAFETY: Only used for lookups, which is what is being tested.
+		let (frog, missing) = unsafe { (RawId::new(0), RawId::new(1)) };
+		assert_eq!(registry.get(frog), Some(&FrogePrototype { cool: true }));
+		assert_eq!(registry.get(missing), None);
 		Ok(())
 	}
 
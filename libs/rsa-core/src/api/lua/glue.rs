@@ -63,6 +63,13 @@ impl<V: UserData> LuaGlue<V> {
 			value
 		}
 	}
+
+	/// Gets the value this glue is stuck to.
+	/// # Safety
+	/// The value needs to still be alive, which is only the case while the call the glue got passed to is running.
+	pub unsafe fn get_mut(&mut self) -> &mut V {
+		&mut *self.value
+	}
 }
 
 impl<V: UserData> Clone for LuaGlue<V> {
@@ -28,6 +28,15 @@ impl<P: Prototype> Registry<P> {
 		self.entries.iter()
 	}
 
+	/// Iterates the prototypes together with their ids.
+	pub fn iter_ids(&self) -> impl Iterator<Item = (RawId, &P)> {
+		self.entries
+			.iter()
+			.enumerate()
+			// SAFETY: The ids are the indexes of the entries.
+			.map(|(id, prototype)| (unsafe { RawId::new(id as u32) }, prototype))
+	}
+
 	pub fn id_from_tag(&self, tag: &Tag) -> Result<RawId, RegistryError> {
 		match self.tag_to_id.get(tag).copied() {
 			None => {
@@ -61,6 +70,11 @@ impl<P: Prototype> Registry<P> {
 			.expect("Could not find RawId in registry, this heavily violates the RawId policy.")
 	}
 
+	/// Like [`Registry::prototype_from_id`] but for ids which came from outside, like packets.
+	pub fn get(&self, id: RawId) -> Option<&P> {
+		self.entries.get(id.index())
+	}
+
 	#[inline(always)]
 	pub fn create_from_id(&self, id: RawId) -> P::Item {
 		self.prototype_from_id(id).create(id)
@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use rsa_core::api::lua::{Lua, LuaError, LuaFromLua, LuaResult, LuaToLua, LuaValue};
+use rsa_core::ty::Tag;
+
+/// How many more uses the item has.
+pub const DURABILITY: &str = "durability";
+/// The prefix or modifier the item rolled.
+pub const PREFIX: &str = "prefix";
+/// A name given by the player, shown instead of the item name.
+pub const NAME: &str = "name";
+/// How deep tables may be nested, this also stops tables that contain themselves.
+pub const MAX_DEPTH: usize = 16;
+
+/// A single value on a stack.
+/// Values coming from Lua never hold a NaN or infinite number and their tables are sorted by key.
+#[derive(Clone, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum ItemValue {
+	Boolean(bool),
+	Integer(i64),
+	Number(f64),
+	String(String),
+	Tag(Tag),
+	/// A Lua table, for whatever plugins want to keep on an item.
+	Table(Vec<(ItemValue, ItemValue)>),
+}
+
+/// The data that makes one stack different from another stack of the same item.
+/// Stacks only stack if their data is the same.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct ItemData {
+	values: BTreeMap<String, ItemValue>,
+}
+
+impl ItemData {
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	pub fn get(&self, key: &str) -> Option<&ItemValue> {
+		self.values.get(key)
+	}
+
+	/// Sets a value, `None` removes it.
+	pub fn set(&mut self, key: &str, value: Option<ItemValue>) {
+		match value {
+			Some(value) => {
+				self.values.insert(key.to_string(), value);
+			}
+			None => {
+				self.values.remove(key);
+			}
+		}
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&String, &ItemValue)> {
+		self.values.iter()
+	}
+
+	pub fn durability(&self) -> Option<u32> {
+		match self.get(DURABILITY)? {
+			ItemValue::Integer(value) => u32::try_from(*value).ok(),
+			_ => None,
+		}
+	}
+
+	pub fn set_durability(&mut self, durability: Option<u32>) {
+		self.set(DURABILITY, durability.map(|value| ItemValue::Integer(value as i64)));
+	}
+
+	pub fn prefix(&self) -> Option<&Tag> {
+		match self.get(PREFIX)? {
+			ItemValue::Tag(tag) => Some(tag),
+			_ => None,
+		}
+	}
+
+	pub fn set_prefix(&mut self, prefix: Option<Tag>) {
+		self.set(PREFIX, prefix.map(ItemValue::Tag));
+	}
+
+	pub fn name(&self) -> Option<&str> {
+		match self.get(NAME)? {
+			ItemValue::String(name) => Some(name),
+			_ => None,
+		}
+	}
+
+	pub fn set_name(&mut self, name: Option<String>) {
+		self.set(NAME, name.map(ItemValue::String));
+	}
+}
+
+impl ItemValue {
+	fn from_lua_nested(lua_value: LuaValue, lua: &Lua, depth: usize) -> LuaResult<Self> {
+		Ok(match lua_value {
+			LuaValue::Boolean(value) => ItemValue::Boolean(value),
+			LuaValue::Integer(value) => ItemValue::Integer(value),
+			LuaValue::Number(value) if value.is_finite() => ItemValue::Number(value),
+			LuaValue::Number(value) => {
+				return Err(LuaError::RuntimeError(format!("{value} can not be stored on an item")))
+			}
+			LuaValue::String(value) => ItemValue::String(value.to_str()?.to_string()),
+			LuaValue::Table(_) if depth >= MAX_DEPTH => {
+				return Err(LuaError::RuntimeError(format!(
+					"Tables nested deeper than {MAX_DEPTH} can not be stored on an item"
+				)))
+			}
+			LuaValue::Table(table) => {
+				let mut entries = Vec::new();
+				for pair in table.pairs::<LuaValue, LuaValue>() {
+					let (key, value) = pair?;
+					entries.push((
+						ItemValue::from_lua_nested(key, lua, depth + 1)?,
+						ItemValue::from_lua_nested(value, lua, depth + 1)?,
+					));
+				}
+				// pairs() has no order, sorting makes equal tables compare equal so their stacks stack.
+				entries.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+				ItemValue::Table(entries)
+			}
+			value => {
+				return Err(LuaError::RuntimeError(format!(
+					"{} can not be stored on an item",
+					value.type_name()
+				)))
+			}
+		})
+	}
+}
+
+impl LuaFromLua for ItemValue {
+	fn from_lua(lua_value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+		ItemValue::from_lua_nested(lua_value, lua, 0)
+	}
+}
+
+impl LuaToLua for ItemValue {
+	fn to_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+		Ok(match self {
+			ItemValue::Boolean(value) => LuaValue::Boolean(value),
+			ItemValue::Integer(value) => LuaValue::Integer(value),
+			ItemValue::Number(value) => LuaValue::Number(value),
+			ItemValue::String(value) => LuaValue::String(lua.create_string(&value)?),
+			ItemValue::Tag(tag) => LuaValue::String(lua.create_string(&tag.to_string())?),
+			ItemValue::Table(entries) => {
+				let table = lua.create_table()?;
+				for (key, value) in entries {
+					table.raw_set(key, value)?;
+				}
+				LuaValue::Table(table)
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::api::lua::{Lua, LuaAnyUserData, LuaFromLua, LuaResult, LuaValue};
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+
+	use crate::data::{ItemData, ItemValue};
+	use crate::item::ItemPrototype;
+	use crate::stack::ItemStack;
+
+	fn value(lua: &Lua, code: &str) -> LuaResult<ItemValue> {
+		let value: LuaValue = lua.load(code).eval()?;
+		ItemValue::from_lua(value, lua)
+	}
+
+	#[test]
+	fn data_setters() -> Result<()> {
+		let mut data = ItemData::default();
+		assert!(data.is_empty());
+
+		data.set_durability(Some(20));
+		data.set_prefix(Some(Tag::new("hello:sharp")?));
+		data.set_name(Some("Pointy".to_string()));
+		assert_eq!(data.durability(), Some(20));
+		assert_eq!(data.prefix(), Some(&Tag::new("hello:sharp")?));
+		assert_eq!(data.name(), Some("Pointy"));
+
+		data.set_durability(None);
+		assert_eq!(data.durability(), None);
+		assert_eq!(data.iter().count(), 2);
+
+		// Values of the wrong kind are not read as the well known keys.
+		data.set(super::DURABILITY, Some(ItemValue::Integer(-1)));
+		assert_eq!(data.durability(), None);
+		Ok(())
+	}
+
+	#[test]
+	fn tables_are_sorted() -> LuaResult<()> {
+		let lua = Lua::new();
+		let a = value(&lua, "local t = {} t.b = 2 t.a = 1 t[3] = true return t")?;
+		let b = value(&lua, "local t = {} t[3] = true t.a = 1 t.b = 2 return t")?;
+		assert_eq!(a, b);
+		assert_eq!(
+			a,
+			ItemValue::Table(vec![
+				(ItemValue::Integer(3), ItemValue::Boolean(true)),
+				(ItemValue::String("a".to_string()), ItemValue::Integer(1)),
+				(ItemValue::String("b".to_string()), ItemValue::Integer(2)),
+			])
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn rejects_bad_values() {
+		let lua = Lua::new();
+		assert!(value(&lua, "return 0/0").is_err());
+		assert!(value(&lua, "return math.huge").is_err());
+		assert!(value(&lua, "return { x = 0/0 }").is_err());
+		assert!(value(&lua, "return function() end").is_err());
+		assert!(value(&lua, "local t = {} t.t = t return t").is_err());
+
+		let mut deep = String::from("return ");
+		deep.push_str(&"{ ".repeat(super::MAX_DEPTH + 1));
+		deep.push_str(&"} ".repeat(super::MAX_DEPTH + 1));
+		assert!(value(&lua, &deep).is_err());
+	}
+
+	#[test]
+	fn lua_round_trip() -> LuaResult<()> {
+		let lua = Lua::new();
+		let table = value(&lua, "return { name = \"bag\", 4, { 1.5, false } }")?;
+		lua.globals().set("value", table.clone())?;
+		assert_eq!(value(&lua, "return value")?, table);
+		Ok(())
+	}
+
+	#[test]
+	fn stack_data_from_lua() -> Result<()> {
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["stick"] = { max_stack = 10 },
+			}
+			"#,
+		);
+		reload!((ItemPrototype) => api);
+		let carrier = api.get_carrier();
+		let stick = carrier.get::<ItemPrototype>().create_from_tag(&Tag::new("hello:stick")?)?;
+
+		let lua = Lua::new();
+		lua.globals().set("first", stick.to_stack(None).unwrap())?;
+		lua.globals().set("second", stick.to_stack(None).unwrap())?;
+		lua.load(
+			r#"
+			first:set_data("color", { r = 1, g = 2 })
+			second:set_data("color", { g = 2, r = 1 })
+			assert(first:get_data("color").g == 2)
+			first:set_data("gone", 1)
+			first:set_data("gone", nil)
+			"#,
+		)
+		.exec()?;
+
+		let first = lua.globals().get::<_, LuaAnyUserData>("first")?;
+		let second = lua.globals().get::<_, LuaAnyUserData>("second")?;
+		let first = first.borrow::<ItemStack>()?;
+		let second = second.borrow::<ItemStack>()?;
+		assert!(first.stacks_with(&second));
+		assert!(first.data().get("gone").is_none());
+		Ok(())
+	}
+}
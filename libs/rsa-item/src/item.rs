@@ -1,13 +1,90 @@
 use crate::stack::ItemStack;
+use crate::stats::StatModifiers;
 use apollo::*;
 use rsa_core::api::lua::FromLua;
 use rsa_core::api::lua::{get_meta, Lua, LuaResult};
 #[allow(unused_imports)]
-use rsa_core::ty::{Prototype, RawId};
+use rsa_core::ty::{Prototype, RawId, Tag};
 
+/// ```lua
+/// reload.registry.item:insert {
+///     ["pickaxe"] = {
+///         max_stack = 1,
+///         use_style = "Swing",
+///         use_time = 15,
+///         tool_power = 2,
+///         damage = 4,
+///     },
+///     ["dirt"] = { max_stack = 999, use_style = "PlaceTile", tile = "dirt" },
+///     ["iron_helmet"] = { max_stack = 1, equipment = "Head", modifiers = { defense = { add = 2 } } },
+/// }
+/// ```
 #[derive(Clone, Debug, serde::Deserialize, FromLua)]
 pub struct ItemPrototype {
 	pub max_stack: u32,
+	/// What happens when the item gets used, it can not be used if this is not set.
+	pub use_style: Option<UseStyle>,
+	/// How many ticks a use takes before the item can be used again.
+	pub use_time: Option<u32>,
+	/// How hard the tiles this can mine can be, it does not mine if this is not set.
+	pub tool_power: Option<u32>,
+	/// What `Swing` and `Thrust` deal to the entities they hit.
+	pub damage: Option<f32>,
+	/// The tile `PlaceTile` places.
+	pub tile: Option<Tag>,
+	/// The projectile entity `Shoot` shoots.
+	pub projectile: Option<Tag>,
+	/// The status effect `Consume` applies to the user.
+	pub effect: Option<Tag>,
+	/// If every use takes one item off the stack. Placing tiles and consuming always do.
+	pub consumable: Option<bool>,
+	/// The equipment slot this can be put in, it can not be equipped if this is not set.
+	pub equipment: Option<EquipmentSlot>,
+	/// What this changes on whoever has it equipped.
+	#[serde(default)]
+	pub modifiers: StatModifiers,
+}
+
+impl ItemPrototype {
+	/// How many ticks a use takes if `use_time` is not set.
+	pub const DEFAULT_USE_TIME: u32 = 20;
+
+	pub fn use_time(&self) -> u32 {
+		self.use_time.unwrap_or(Self::DEFAULT_USE_TIME)
+	}
+
+	/// If a use takes an item off the stack.
+	pub fn consumes(&self) -> bool {
+		match self.use_style {
+			Some(UseStyle::PlaceTile) | Some(UseStyle::Consume) => true,
+			_ => self.consumable.unwrap_or(false),
+		}
+	}
+}
+
+/// How an item gets used.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, serde::Deserialize, FromLua)]
+pub enum UseStyle {
+	/// Hits everything around the target, and mines it if the item is a tool.
+	Swing,
+	/// Like `Swing`, but reaches further and only hits right at the target.
+	Thrust,
+	/// Shoots the `projectile` towards the target.
+	Shoot,
+	/// Places the `tile` on the target if there is nothing there.
+	PlaceTile,
+	/// Applies the `effect` to whoever used it.
+	Consume,
+}
+
+/// The kind of equipment slot an item goes in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, serde::Deserialize, FromLua)]
+pub enum EquipmentSlot {
+	Head,
+	Chest,
+	Legs,
+	/// Any of the accessory slots.
+	Accessory,
 }
 
 impl Prototype for ItemPrototype {
@@ -22,7 +99,8 @@ impl Prototype for ItemPrototype {
 	}
 }
 
-#[derive(Clone, PartialOrd, PartialEq)]
+/// Serializes by raw id, which is only valid for the same registry. Saves should use [`crate::save`].
+#[derive(Clone, Debug, PartialOrd, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Item {
 	pub ty: RawId,
 }
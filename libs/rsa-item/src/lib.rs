@@ -5,7 +5,11 @@ use rsa_core::api::carrier::Carrier;
 use rsa_core::api::lua::{LuaUserData, LuaUserDataMethods};
 use rsa_core::ty::{Prototype, Tag};
 
+pub mod data;
+pub mod recipe;
+pub mod save;
 pub mod stack;
+pub mod stats;
 pub mod storage;
 pub mod item;
 mod testing;
@@ -0,0 +1,221 @@
+use rsa_core::api::carrier::Carrier;
+use rsa_core::api::lua::FromLua;
+use rsa_core::error::{bail, Result, WrapErr};
+use rsa_core::ty::{Prototype, RawId, Tag};
+
+use crate::item::{Item, ItemPrototype};
+use crate::stack::ItemStack;
+use crate::storage::ItemStorage;
+
+/// Turns some items into other items, sometimes only next to a crafting station.
+/// ```lua
+/// reload.registry.recipe:insert {
+///     ["torch"] = {
+///         ingredients = { { item = "wood" }, { item = "gel", amount = 2 } },
+///         output = { { item = "torch", amount = 3 } },
+///         stations = { "workbench" },
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, serde::Deserialize, FromLua)]
+pub struct RecipePrototype {
+	pub ingredients: Vec<RecipeItem>,
+	pub output: Vec<RecipeItem>,
+	/// Tiles which all need to be close to the player, none are needed if this is not set.
+	pub stations: Option<Vec<Tag>>,
+}
+
+impl Prototype for RecipePrototype {
+	type Item = RecipePrototype;
+
+	fn create(&self, _: RawId) -> Self::Item {
+		self.clone()
+	}
+
+	fn lua_registry_name() -> &'static str {
+		"recipe"
+	}
+}
+
+#[derive(Clone, Debug, serde::Deserialize, FromLua)]
+pub struct RecipeItem {
+	pub item: Tag,
+	/// Defaults to 1.
+	pub amount: Option<u32>,
+}
+
+impl RecipeItem {
+	pub fn amount(&self) -> u32 {
+		self.amount.unwrap_or(1)
+	}
+
+	fn resolve(&self, carrier: &Carrier) -> Result<(Item, u32)> {
+		let item = carrier
+			.get::<ItemPrototype>()
+			.create_from_tag(&self.item)
+			.wrap_err_with(|| format!("Recipe uses unknown item {}", self.item))?;
+		Ok((item, self.amount()))
+	}
+}
+
+impl RecipePrototype {
+	pub fn stations(&self) -> &[Tag] {
+		self.stations.as_deref().unwrap_or(&[])
+	}
+
+	/// Checks if `storages` together hold every ingredient. Recipes with unknown items are never craftable.
+	/// Stacks with data (durability, prefixes, names) never count as ingredients.
+	pub fn has_ingredients(&self, carrier: &Carrier, storages: &[&ItemStorage]) -> bool {
+		self.ingredients.iter().all(|ingredient| match ingredient.resolve(carrier) {
+			Ok((item, amount)) => storages.iter().map(|storage| storage.count_plain(&item)).sum::<u32>() >= amount,
+			Err(_) => false,
+		})
+	}
+
+	/// Takes the ingredients out of the storages and puts the output in, filling `storage` first.
+	/// If anything is missing or the output does not fit, neither storage changes.
+	/// Stacks with data are never used up.
+	pub fn craft(&self, carrier: &Carrier, storage: &mut ItemStorage, other: &mut ItemStorage) -> Result<()> {
+		let ingredients = self
+			.ingredients
+			.iter()
+			.map(|ingredient| ingredient.resolve(carrier))
+			.collect::<Result<Vec<_>>>()?;
+		let output = self
+			.output
+			.iter()
+			.map(|output| output.resolve(carrier))
+			.collect::<Result<Vec<_>>>()?;
+
+		storage.transaction_with(other, |storage, other| {
+			for (item, amount) in ingredients {
+				let taken = storage.count_plain(&item).min(amount);
+				storage.remove_plain(&item, taken)?;
+				other.remove_plain(&item, amount - taken)?;
+			}
+
+			for (item, amount) in output {
+				let leftover = match storage.insert(carrier, ItemStack::new(item, Some(amount))) {
+					Some(leftover) => other.insert(carrier, leftover),
+					None => None,
+				};
+				if let Some(leftover) = leftover {
+					bail!("No room for {} more items", leftover.amount())
+				}
+			}
+			Ok(())
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::api::carrier::Carrier;
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+
+	use crate::data::ItemData;
+	use crate::item::{Item, ItemPrototype};
+	use crate::recipe::RecipePrototype;
+	use crate::storage::ItemStorage;
+
+	fn carrier() -> Result<Carrier> {
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["wood"] = { max_stack = 99 },
+				["torch"] = { max_stack = 5 },
+			}
+			reload.registry["recipe"]:insert {
+				["torch"] = {
+					ingredients = { { item = "wood", amount = 3 } },
+					output = { { item = "torch", amount = 4 } },
+				},
+			}
+			"#,
+		);
+		reload!((ItemPrototype, RecipePrototype) => api);
+		Ok(api.get_carrier())
+	}
+
+	fn item(carrier: &Carrier, name: &str) -> Item {
+		carrier
+			.get::<ItemPrototype>()
+			.create_from_tag(&Tag::new(format!("hello:{name}")).unwrap())
+			.unwrap()
+	}
+
+	fn recipe(carrier: &Carrier) -> RecipePrototype {
+		carrier
+			.get::<RecipePrototype>()
+			.create_from_tag(&Tag::new("hello:torch").unwrap())
+			.unwrap()
+	}
+
+	#[test]
+	fn crafts_across_storages() -> Result<()> {
+		let carrier = carrier()?;
+		let (wood, torch) = (item(&carrier, "wood"), item(&carrier, "torch"));
+		let recipe = recipe(&carrier);
+
+		let mut hotbar = ItemStorage::new(2);
+		let mut main = ItemStorage::new(2);
+		hotbar.insert(&carrier, wood.to_stack(Some(1)).unwrap());
+		main.insert(&carrier, wood.to_stack(Some(4)).unwrap());
+
+		assert!(recipe.has_ingredients(&carrier, &[&hotbar, &main]));
+		recipe.craft(&carrier, &mut hotbar, &mut main)?;
+		assert_eq!(hotbar.count(&wood) + main.count(&wood), 2);
+		assert_eq!(hotbar.count(&torch), 4);
+
+		assert!(!recipe.has_ingredients(&carrier, &[&hotbar, &main]));
+		assert!(recipe.craft(&carrier, &mut hotbar, &mut main).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn full_inventory_changes_nothing() -> Result<()> {
+		let carrier = carrier()?;
+		let (wood, torch) = (item(&carrier, "wood"), item(&carrier, "torch"));
+		let recipe = recipe(&carrier);
+
+		let mut hotbar = ItemStorage::new(1);
+		let mut main = ItemStorage::new(1);
+		hotbar.insert(&carrier, wood.to_stack(Some(10)).unwrap());
+		main.insert(&carrier, torch.to_stack(Some(3)).unwrap());
+
+		let before = (hotbar.clone(), main.clone());
+		assert!(recipe.craft(&carrier, &mut hotbar, &mut main).is_err());
+		assert_eq!((hotbar, main), before);
+		Ok(())
+	}
+
+	#[test]
+	fn stacks_with_data_are_not_ingredients() -> Result<()> {
+		let carrier = carrier()?;
+		let (wood, torch) = (item(&carrier, "wood"), item(&carrier, "torch"));
+		let recipe = recipe(&carrier);
+
+		let mut data = ItemData::default();
+		data.set_name(Some("Heirloom".to_string()));
+		let named = wood.to_stack(Some(5)).unwrap().with_data(data);
+
+		let mut hotbar = ItemStorage::new(2);
+		let mut main = ItemStorage::new(2);
+		hotbar.insert(&carrier, named.clone());
+		main.insert(&carrier, wood.to_stack(Some(2)).unwrap());
+
+		assert!(!recipe.has_ingredients(&carrier, &[&hotbar, &main]));
+		assert!(recipe.craft(&carrier, &mut hotbar, &mut main).is_err());
+
+		main.insert(&carrier, wood.to_stack(Some(1)).unwrap());
+		recipe.craft(&carrier, &mut hotbar, &mut main)?;
+		assert_eq!(hotbar.get(0), Some(&named));
+		assert_eq!(main.count(&wood), 0);
+		assert_eq!(hotbar.count(&torch), 4);
+		Ok(())
+	}
+}
@@ -0,0 +1,201 @@
+//! The format items get saved in. Raw ids change whenever the loaded plugins change, so saves
+//! store items by tag and get checked against the registry again when they are loaded.
+//! Packets just use the serde impls on [`ItemStack`] and [`ItemStorage`] which go by raw id.
+use serde::{Deserialize, Serialize};
+
+use rsa_core::api::carrier::Carrier;
+use rsa_core::ty::Tag;
+
+use crate::data::ItemData;
+use crate::item::{Item, ItemPrototype};
+use crate::stack::ItemStack;
+use crate::storage::ItemStorage;
+
+/// Something that was wrong with a save and got fixed while loading it.
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+pub enum LoadIssue {
+	#[error("Item {0} does not exist anymore and got removed")]
+	UnknownItem(Tag),
+	#[error("Stack of {item} had {amount} items but only stacks up to {max_stack}")]
+	Clamped { item: Tag, amount: u32, max_stack: u32 },
+	#[error("Empty stack of {0} got removed")]
+	Empty(Tag),
+	#[error("Stack of {0} was outside of the storage and got removed")]
+	OutOfBounds(Tag),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedStack {
+	pub item: Tag,
+	pub amount: u32,
+	#[serde(default, skip_serializing_if = "ItemData::is_empty")]
+	pub data: ItemData,
+}
+
+impl SavedStack {
+	pub fn save(carrier: &Carrier, stack: &ItemStack) -> SavedStack {
+		SavedStack {
+			item: carrier.get::<ItemPrototype>().tag_from_id(stack.item().ty).clone(),
+			amount: stack.amount(),
+			data: stack.data().clone(),
+		}
+	}
+
+	/// Turns this back into a stack. Unknown items give `None`, amounts get clamped to `max_stack`.
+	/// Everything that had to be fixed ends up in `issues`.
+	pub fn load(&self, carrier: &Carrier, issues: &mut Vec<LoadIssue>) -> Option<ItemStack> {
+		let registry = carrier.get::<ItemPrototype>();
+		let ty = match registry.id_from_tag(&self.item) {
+			Ok(ty) => ty,
+			Err(_) => {
+				issues.push(LoadIssue::UnknownItem(self.item.clone()));
+				return None;
+			}
+		};
+
+		if self.amount == 0 {
+			issues.push(LoadIssue::Empty(self.item.clone()));
+			return None;
+		}
+
+		let max_stack = registry.prototype_from_id(ty).max_stack;
+		if self.amount > max_stack {
+			issues.push(LoadIssue::Clamped {
+				item: self.item.clone(),
+				amount: self.amount,
+				max_stack,
+			});
+		}
+
+		Some(ItemStack::new(Item { ty }, Some(self.amount.min(max_stack))).with_data(self.data.clone()))
+	}
+}
+
+/// The slots of an [`ItemStorage`], saved as a plain list.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SavedStorage {
+	pub slots: Vec<Option<SavedStack>>,
+}
+
+impl SavedStorage {
+	pub fn save(carrier: &Carrier, storage: &ItemStorage) -> SavedStorage {
+		SavedStorage {
+			slots: (0..storage.len())
+				.map(|pos| storage.get(pos).map(|stack| SavedStack::save(carrier, stack)))
+				.collect(),
+		}
+	}
+
+	/// Loads the slots into a storage of `size`. Missing slots are empty, stacks past the end get removed.
+	pub fn load(&self, carrier: &Carrier, size: u32, issues: &mut Vec<LoadIssue>) -> ItemStorage {
+		let mut slots = Vec::with_capacity(size as usize);
+		for (pos, saved) in self.slots.iter().enumerate() {
+			let saved = match saved {
+				Some(saved) => saved,
+				None => {
+					if pos < size as usize {
+						slots.push(None);
+					}
+					continue;
+				}
+			};
+
+			if pos >= size as usize {
+				issues.push(LoadIssue::OutOfBounds(saved.item.clone()));
+				continue;
+			}
+			slots.push(saved.load(carrier, issues));
+		}
+
+		slots.resize(size as usize, None);
+		ItemStorage::from_slots(slots)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::api::carrier::Carrier;
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+
+	use crate::item::ItemPrototype;
+	use crate::save::{LoadIssue, SavedStack, SavedStorage};
+	use crate::storage::ItemStorage;
+
+	fn carrier() -> Result<Carrier> {
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["stick"] = { max_stack = 10 },
+			}
+			"#,
+		);
+		reload!((ItemPrototype) => api);
+		Ok(api.get_carrier())
+	}
+
+	fn saved(item: &str, amount: u32) -> Option<SavedStack> {
+		Some(SavedStack {
+			item: Tag::new(item).unwrap(),
+			amount,
+			data: Default::default(),
+		})
+	}
+
+	#[test]
+	fn round_trip() -> Result<()> {
+		let carrier = carrier()?;
+		let stick = carrier.get::<ItemPrototype>().create_from_tag(&Tag::new("hello:stick")?)?;
+
+		let mut storage = ItemStorage::new(3);
+		let mut stack = stick.to_stack(Some(7)).unwrap();
+		stack.data_mut().set_durability(Some(12));
+		storage.set(1, Some(stack))?;
+
+		let mut issues = vec![];
+		let loaded = SavedStorage::save(&carrier, &storage).load(&carrier, 3, &mut issues);
+		assert_eq!(loaded, storage);
+		assert!(issues.is_empty());
+		Ok(())
+	}
+
+	#[test]
+	fn fixes_broken_saves() -> Result<()> {
+		let carrier = carrier()?;
+		let save = SavedStorage {
+			slots: vec![
+				saved("hello:stick", 40),
+				saved("hello:gone", 1),
+				saved("hello:stick", 0),
+				saved("hello:stick", 1),
+			],
+		};
+
+		let mut issues = vec![];
+		let storage = save.load(&carrier, 3, &mut issues);
+		assert_eq!(storage.len(), 3);
+		assert_eq!(storage.get(0).map(|stack| stack.amount()), Some(10));
+		assert!(storage.get(1).is_none());
+		assert!(storage.get(2).is_none());
+
+		let stick = Tag::new("hello:stick")?;
+		assert_eq!(
+			issues,
+			vec![
+				LoadIssue::Clamped {
+					item: stick.clone(),
+					amount: 40,
+					max_stack: 10
+				},
+				LoadIssue::UnknownItem(Tag::new("hello:gone")?),
+				LoadIssue::Empty(stick.clone()),
+				LoadIssue::OutOfBounds(stick),
+			]
+		);
+		Ok(())
+	}
+}
@@ -1,24 +1,87 @@
 use rsa_core::api::carrier::Carrier;
 #[allow(unused_imports)]
 use rsa_core::api::lua::{Lua, LuaMetaMethod, LuaResult, LuaUserData, LuaUserDataMethods};
+use crate::data::{ItemData, ItemValue};
 use crate::item::Item;
 use apollo::{lua_method, lua_impl};
 use rsa_core::api::lua::get_meta;
+use rsa_core::ty::Tag;
 use crate::ItemPrototype;
 
 
 /// An ItemStack is an item or more.
-#[derive(Clone, PartialOrd, PartialEq)]
+///
+/// Serializes by raw id for packets, saves should go through [`crate::save::SavedStack`].
+#[derive(Clone, Debug, PartialOrd, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ItemStack {
 	/// The item this is.
 	item: Item,
 	/// The amount of items that are in this "slot"
 	amount: u32,
+	/// What makes this stack special, like durability or a name.
+	#[serde(default)]
+	data: ItemData,
 }
 
 impl ItemStack {
 	pub fn new(item: Item, amount: Option<u32>) -> ItemStack {
-		ItemStack { item, amount: amount.unwrap_or(1) }
+		ItemStack {
+			item,
+			amount: amount.unwrap_or(1),
+			data: ItemData::default(),
+		}
+	}
+
+	pub fn with_data(mut self, data: ItemData) -> ItemStack {
+		self.data = data;
+		self
+	}
+
+	/// The item this stack is made of.
+	pub fn item(&self) -> &Item {
+		&self.item
+	}
+
+	/// How many items are in this stack.
+	pub fn amount(&self) -> u32 {
+		self.amount
+	}
+
+	pub fn data(&self) -> &ItemData {
+		&self.data
+	}
+
+	pub fn data_mut(&mut self) -> &mut ItemData {
+		&mut self.data
+	}
+
+	/// Checks if `other` is the same item with the same data and could be merged into this stack.
+	pub fn stacks_with(&self, other: &ItemStack) -> bool {
+		self.item == other.item && self.data == other.data
+	}
+
+	/// Moves as much of `other` into this stack as `max_stack` allows and returns what did not fit.
+	pub fn merge(&mut self, carrier: &Carrier, other: ItemStack) -> Option<ItemStack> {
+		debug_assert!(self.stacks_with(&other), "Merging different items");
+		self.increase(carrier, other.amount).map(|amount| ItemStack {
+			item: other.item,
+			amount,
+			data: other.data,
+		})
+	}
+
+	/// Takes `amount` items off into their own stack. Returns `None` if that would leave this stack empty or `amount` is 0.
+	pub fn split(&mut self, amount: u32) -> Option<ItemStack> {
+		if amount == 0 || amount >= self.amount {
+			return None;
+		}
+
+		self.amount -= amount;
+		Some(ItemStack {
+			item: self.item.clone(),
+			amount,
+			data: self.data.clone(),
+		})
 	}
 
 	pub fn increase(&mut self, carrier: &Carrier, amount: u32) -> Option<u32> {
@@ -41,6 +104,50 @@ impl ItemStack {
 		Ok(self.increase(&get_meta(lua).api.get_carrier(), amount))
 	}
 
+	#[lua_method]
+	pub fn get_data(&self, key: String) -> LuaResult<Option<ItemValue>> {
+		Ok(self.data.get(&key).cloned())
+	}
+
+	#[lua_method]
+	pub fn set_data(&mut self, key: String, value: Option<ItemValue>) -> LuaResult<()> {
+		self.data.set(&key, value);
+		Ok(())
+	}
+
+	#[lua_method]
+	pub fn durability(&self) -> LuaResult<Option<u32>> {
+		Ok(self.data.durability())
+	}
+
+	#[lua_method]
+	pub fn set_durability(&mut self, durability: Option<u32>) -> LuaResult<()> {
+		self.data.set_durability(durability);
+		Ok(())
+	}
+
+	#[lua_method]
+	pub fn prefix(&self) -> LuaResult<Option<Tag>> {
+		Ok(self.data.prefix().cloned())
+	}
+
+	#[lua_method]
+	pub fn set_prefix(&mut self, prefix: Option<Tag>) -> LuaResult<()> {
+		self.data.set_prefix(prefix);
+		Ok(())
+	}
+
+	#[lua_method]
+	pub fn name(&self) -> LuaResult<Option<String>> {
+		Ok(self.data.name().map(str::to_string))
+	}
+
+	#[lua_method]
+	pub fn set_name(&mut self, name: Option<String>) -> LuaResult<()> {
+		self.data.set_name(name);
+		Ok(())
+	}
+
 	#[lua_method]
 	pub fn __tostring(&self, lua: &Lua) -> LuaResult<String> {
 		Ok(format!("(item: {}, amount: {})", self.item.__tostring(lua)?, self.amount))
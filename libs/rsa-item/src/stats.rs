@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use rsa_core::api::lua::{Lua, LuaError, LuaFromLua, LuaResult, LuaToLua, LuaValue};
+
+/// Changes a stat by first adding to it and then multiplying it.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Modifier {
+	pub add: f32,
+	pub multiply: f32,
+}
+
+impl Modifier {
+	pub fn apply(&self, value: f32) -> f32 {
+		(value + self.add) * self.multiply
+	}
+
+	/// Stacks another modifier on top of this one.
+	pub fn combine(&mut self, other: &Modifier) {
+		self.add += other.add;
+		self.multiply *= other.multiply;
+	}
+}
+
+impl Default for Modifier {
+	fn default() -> Self {
+		Modifier {
+			add: 0.0,
+			multiply: 1.0,
+		}
+	}
+}
+
+impl LuaFromLua for Modifier {
+	fn from_lua(lua_value: LuaValue, _: &Lua) -> LuaResult<Self> {
+		match lua_value {
+			LuaValue::Nil => Ok(Modifier::default()),
+			LuaValue::Table(table) => Ok(Modifier {
+				add: table.get::<_, Option<f32>>("add")?.unwrap_or(0.0),
+				multiply: table.get::<_, Option<f32>>("multiply")?.unwrap_or(1.0),
+			}),
+			_ => Err(LuaError::UserDataTypeMismatch),
+		}
+	}
+}
+
+impl LuaToLua for Modifier {
+	fn to_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+		let table = lua.create_table()?;
+		table.raw_set("add", self.add)?;
+		table.raw_set("multiply", self.multiply)?;
+		Ok(LuaValue::Table(table))
+	}
+}
+
+/// Every stat that can get changed by things like status effects and equipment.
+/// ```lua
+/// modifiers = {
+///     run_speed = { multiply = 1.5 },
+///     damage_taken = { add = 2 },
+///     defense = { add = 3 },
+/// }
+/// ```
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct StatModifiers {
+	pub run_speed: Modifier,
+	pub run_acceleration: Modifier,
+	pub jump_speed: Modifier,
+	pub gravity: Modifier,
+	pub damage_taken: Modifier,
+	/// Gets taken off the damage after `damage_taken`, starting from 0.
+	pub defense: Modifier,
+	pub max_health: Modifier,
+}
+
+impl StatModifiers {
+	pub fn combine(&mut self, other: &StatModifiers) {
+		self.run_speed.combine(&other.run_speed);
+		self.run_acceleration.combine(&other.run_acceleration);
+		self.jump_speed.combine(&other.jump_speed);
+		self.gravity.combine(&other.gravity);
+		self.damage_taken.combine(&other.damage_taken);
+		self.defense.combine(&other.defense);
+		self.max_health.combine(&other.max_health);
+	}
+
+	/// How much of `amount` damage actually gets taken, which is never negative.
+	pub fn damage(&self, amount: f32) -> f32 {
+		(self.damage_taken.apply(amount) - self.defense.apply(0.0)).max(0.0)
+	}
+}
+
+impl LuaFromLua for StatModifiers {
+	fn from_lua(lua_value: LuaValue, _: &Lua) -> LuaResult<Self> {
+		match lua_value {
+			LuaValue::Nil => Ok(StatModifiers::default()),
+			LuaValue::Table(table) => Ok(StatModifiers {
+				run_speed: table.get("run_speed")?,
+				run_acceleration: table.get("run_acceleration")?,
+				jump_speed: table.get("jump_speed")?,
+				gravity: table.get("gravity")?,
+				damage_taken: table.get("damage_taken")?,
+				defense: table.get("defense")?,
+				max_health: table.get("max_health")?,
+			}),
+			_ => Err(LuaError::UserDataTypeMismatch),
+		}
+	}
+}
+
+impl LuaToLua for StatModifiers {
+	fn to_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+		let table = lua.create_table()?;
+		table.raw_set("run_speed", self.run_speed)?;
+		table.raw_set("run_acceleration", self.run_acceleration)?;
+		table.raw_set("jump_speed", self.jump_speed)?;
+		table.raw_set("gravity", self.gravity)?;
+		table.raw_set("damage_taken", self.damage_taken)?;
+		table.raw_set("defense", self.defense)?;
+		table.raw_set("max_health", self.max_health)?;
+		Ok(LuaValue::Table(table))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::stats::{Modifier, StatModifiers};
+
+	#[test]
+	fn defense_after_damage_taken() {
+		let mut modifiers = StatModifiers {
+			damage_taken: Modifier { add: 0.0, multiply: 2.0 },
+			..StatModifiers::default()
+		};
+		modifiers.combine(&StatModifiers {
+			defense: Modifier { add: 3.0, multiply: 1.0 },
+			..StatModifiers::default()
+		});
+		modifiers.combine(&StatModifiers {
+			defense: Modifier { add: 2.0, multiply: 1.0 },
+			..StatModifiers::default()
+		});
+
+		assert_eq!(modifiers.damage(10.0), 15.0);
+		assert_eq!(modifiers.damage(2.0), 0.0);
+	}
+}
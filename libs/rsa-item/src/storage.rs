@@ -1,7 +1,12 @@
+use rsa_core::api::carrier::Carrier;
 use rsa_core::error::{bail, Result};
+use crate::item::{Item, ItemPrototype};
 use crate::stack::ItemStack;
 
 /// An ItemStorage is a fixed size storage for items. It has ´len()´ amount of slots that is either `None` or `Some(ItemStack)`
+///
+/// Serializes by raw id for packets, saves should go through [`crate::save::SavedStorage`].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ItemStorage {
 	items: Vec<Option<ItemStack>>,
 }
@@ -14,6 +19,11 @@ impl ItemStorage {
 		}
 	}
 
+	/// Create an ItemStorage out of existing slots
+	pub fn from_slots(items: Vec<Option<ItemStack>>) -> ItemStorage {
+		ItemStorage { items }
+	}
+
 	/// Get the length of the ItemStorage
 	pub fn len(&self) -> u32 {
 		self.items.len() as u32
@@ -33,10 +43,442 @@ impl ItemStorage {
 	pub fn set(&mut self, pos: u32, stack: Option<ItemStack>) -> Result<()> {
 		let index = pos as usize;
 		let len = self.items.len();
-		if index > len {
+		if index >= len {
 			bail!("Out of bounds for storage size {}", len)
 		}
-		self.items.insert(index, stack);
+		self.items[index] = stack;
+		Ok(())
+	}
+
+	/// How many of `item` are in the storage.
+	pub fn count(&self, item: &Item) -> u32 {
+		self.count_where(|stack| stack.item() == item)
+	}
+
+	/// How many of `item` are in the storage without any data on them.
+	pub fn count_plain(&self, item: &Item) -> u32 {
+		self.count_where(|stack| stack.item() == item && stack.data().is_empty())
+	}
+
+	fn count_where(&self, filter: impl Fn(&ItemStack) -> bool) -> u32 {
+		self.items
+			.iter()
+			.flatten()
+			.filter(|stack| filter(stack))
+			.map(|stack| stack.amount())
+			.sum()
+	}
+
+	/// Checks if all of `stack` would fit with `insert`.
+	pub fn can_fit(&self, carrier: &Carrier, stack: &ItemStack) -> bool {
+		let max_stack = max_stack(carrier, stack.item());
+		let room: u32 = self
+			.items
+			.iter()
+			.map(|slot| match slot {
+				None => max_stack,
+				Some(slot) if slot.stacks_with(stack) => max_stack.saturating_sub(slot.amount()),
+				Some(_) => 0,
+			})
+			.sum();
+		room >= stack.amount()
+	}
+
+	/// Puts all of `stack` into the storage, or nothing if it does not fit.
+	pub fn insert_all(&mut self, carrier: &Carrier, stack: ItemStack) -> Result<()> {
+		if !self.can_fit(carrier, &stack) {
+			bail!("Not enough room for {} items", stack.amount())
+		}
+
+		let leftover = self.insert(carrier, stack);
+		debug_assert!(leftover.is_none(), "can_fit lied");
+		Ok(())
+	}
+
+	/// Takes `amount` of `item` out of the storage, starting from the last slot.
+	/// Nothing gets removed if there are not enough.
+	pub fn remove(&mut self, item: &Item, amount: u32) -> Result<()> {
+		self.remove_where(amount, |stack| stack.item() == item)
+	}
+
+	/// Like [`ItemStorage::remove`] but leaves stacks with data alone.
+	pub fn remove_plain(&mut self, item: &Item, amount: u32) -> Result<()> {
+		self.remove_where(amount, |stack| stack.item() == item && stack.data().is_empty())
+	}
+
+	fn remove_where(&mut self, amount: u32, filter: impl Fn(&ItemStack) -> bool) -> Result<()> {
+		let count = self.count_where(&filter);
+		if count < amount {
+			bail!("Only {} of {} items are in the storage", count, amount)
+		}
+
+		let mut remaining = amount;
+		for slot in self.items.iter_mut().rev() {
+			if remaining == 0 {
+				break;
+			}
+
+			let stack = match slot {
+				Some(stack) if filter(stack) => stack,
+				_ => continue,
+			};
+
+			let taken = match stack.split(remaining) {
+				Some(taken) => taken,
+				None => slot.take().unwrap(),
+			};
+			remaining -= taken.amount();
+		}
+		Ok(())
+	}
+
+	/// Swaps two slots.
+	pub fn swap(&mut self, pos: u32, other_pos: u32) -> Result<()> {
+		self.slot(pos)?;
+		self.slot(other_pos)?;
+		self.items.swap(pos as usize, other_pos as usize);
+		Ok(())
+	}
+
+	/// Swaps a slot with a slot of another storage.
+	pub fn swap_with(&mut self, pos: u32, other: &mut ItemStorage, other_pos: u32) -> Result<()> {
+		std::mem::swap(self.slot(pos)?, other.slot(other_pos)?);
+		Ok(())
+	}
+
+	/// Moves `amount` items out of a slot into a slot of another storage.
+	/// The other slot has to be empty or have the same item, and everything has to fit.
+	pub fn split_into(
+		&mut self,
+		carrier: &Carrier,
+		pos: u32,
+		amount: u32,
+		other: &mut ItemStorage,
+		other_pos: u32,
+	) -> Result<()> {
+		let source = self.slot(pos)?;
+		let target = other.slot(other_pos)?;
+		let stack = match source {
+			Some(stack) => stack,
+			None => bail!("Slot {} is empty", pos),
+		};
+
+		if amount == 0 || amount > stack.amount() {
+			bail!("Slot {} does not have {} items", pos, amount)
+		}
+
+		let held = match target.as_ref() {
+			None => 0,
+			Some(target) if target.stacks_with(stack) => target.amount(),
+			Some(_) => bail!("Slot {} has a different item", other_pos),
+		};
+
+		if held + amount > max_stack(carrier, stack.item()) {
+			bail!("{} items do not fit into slot {}", amount, other_pos)
+		}
+
+		let part = match stack.split(amount) {
+			Some(part) => part,
+			None => source.take().unwrap(),
+		};
+
+		match target.as_mut() {
+			None => *target = Some(part),
+			Some(target) => {
+				target.merge(carrier, part);
+			}
+		}
+		Ok(())
+	}
+
+	/// Moves a whole slot into a slot of another storage, see `split_into`.
+	pub fn move_into(&mut self, carrier: &Carrier, pos: u32, other: &mut ItemStorage, other_pos: u32) -> Result<()> {
+		let amount = match self.slot(pos)? {
+			Some(stack) => stack.amount(),
+			None => bail!("Slot {} is empty", pos),
+		};
+		self.split_into(carrier, pos, amount, other, other_pos)
+	}
+
+	/// Moves as much of a slot as fits into a slot of another storage which is empty or has the same item.
+	/// Returns how many items moved.
+	pub fn merge_into(&mut self, carrier: &Carrier, pos: u32, other: &mut ItemStorage, other_pos: u32) -> Result<u32> {
+		let stack = match self.slot(pos)? {
+			Some(stack) => stack.clone(),
+			None => return Ok(0),
+		};
+
+		let held = match other.slot(other_pos)? {
+			None => 0,
+			Some(target) if target.stacks_with(&stack) => target.amount(),
+			Some(_) => bail!("Slot {} has a different item", other_pos),
+		};
+
+		let amount = stack.amount().min(max_stack(carrier, stack.item()).saturating_sub(held));
+		if amount > 0 {
+			self.split_into(carrier, pos, amount, other, other_pos)?;
+		}
+		Ok(amount)
+	}
+
+	/// Runs a batch of changes. If it fails, the storage is put back to how it was before it ran.
+	pub fn transaction<R>(&mut self, func: impl FnOnce(&mut ItemStorage) -> Result<R>) -> Result<R> {
+		let backup = self.items.clone();
+		let result = func(self);
+		if result.is_err() {
+			self.items = backup;
+		}
+		result
+	}
+
+	/// Like `transaction`, but for moving things between two storages.
+	pub fn transaction_with<R>(
+		&mut self,
+		other: &mut ItemStorage,
+		func: impl FnOnce(&mut ItemStorage, &mut ItemStorage) -> Result<R>,
+	) -> Result<R> {
+		let backup = (self.items.clone(), other.items.clone());
+		let result = func(self, other);
+		if result.is_err() {
+			self.items = backup.0;
+			other.items = backup.1;
+		}
+		result
+	}
+
+	fn slot(&mut self, pos: u32) -> Result<&mut Option<ItemStack>> {
+		let len = self.items.len();
+		match self.items.get_mut(pos as usize) {
+			Some(slot) => Ok(slot),
+			None => bail!("Slot {} is out of bounds for storage size {}", pos, len),
+		}
+	}
+
+	/// Get the slot on `pos` to change it directly
+	pub fn slot_mut(&mut self, pos: u32) -> Option<&mut Option<ItemStack>> {
+		self.items.get_mut(pos as usize)
+	}
+
+	/// Takes the stack out of the slot on `pos`, leaving it empty.
+	pub fn take(&mut self, pos: u32) -> Option<ItemStack> {
+		self.items.get_mut(pos as usize)?.take()
+	}
+
+	/// Puts `stack` into the storage, first topping up the stacks of the same item and then filling empty slots.
+	/// Returns whatever did not fit. Items with a `max_stack` of 0 never fit.
+	pub fn insert(&mut self, carrier: &Carrier, mut stack: ItemStack) -> Option<ItemStack> {
+		let max_stack = max_stack(carrier, stack.item());
+		if max_stack == 0 {
+			return Some(stack);
+		}
+
+		for slot in self.items.iter_mut().flatten() {
+			if slot.stacks_with(&stack) {
+				// Returns if everything fit.
+				stack = slot.merge(carrier, stack)?;
+			}
+		}
+
+		for slot in self.items.iter_mut().filter(|slot| slot.is_none()) {
+			if stack.amount() <= max_stack {
+				*slot = Some(stack);
+				return None;
+			}
+
+			// Splitting keeps the data on both halves.
+			*slot = stack.split(max_stack);
+		}
+
+		Some(stack)
+	}
+}
+
+fn max_stack(carrier: &Carrier, item: &Item) -> u32 {
+	carrier.get::<ItemPrototype>().prototype_from_id(item.ty).max_stack
+}
+
+#[cfg(test)]
+mod tests {
+	use proptest::prelude::*;
+	use rsa_core::api::carrier::Carrier;
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+
+	use crate::item::{Item, ItemPrototype};
+	use crate::stack::ItemStack;
+	use crate::storage::ItemStorage;
+
+	const SIZE: u32 = 8;
+
+	fn carrier() -> Result<Carrier> {
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["stick"] = { max_stack = 10 },
+				["stone"] = { max_stack = 99 },
+			}
+			"#,
+		);
+		reload!((ItemPrototype) => api);
+		Ok(api.get_carrier())
+	}
+
+	fn items(carrier: &Carrier) -> [Item; 2] {
+		let registry = carrier.get::<ItemPrototype>();
+		[
+			registry.create_from_tag(&Tag::new("hello:stick").unwrap()).unwrap(),
+			registry.create_from_tag(&Tag::new("hello:stone").unwrap()).unwrap(),
+		]
+	}
+
+	#[derive(Clone, Debug)]
+	enum Op {
+		Insert { other: bool, item: usize, amount: u32 },
+		Remove { other: bool, item: usize, amount: u32 },
+		Swap { from: u32, to: u32 },
+		Split { from: u32, amount: u32, to: u32 },
+		Move { from: u32, to: u32 },
+		Merge { from: u32, to: u32 },
+	}
+
+	fn op() -> impl Strategy<Value = Op> {
+		// Slots go a bit past the end so out of bounds gets hit too.
+		let slot = 0..SIZE + 2;
+		prop_oneof![
+			(any::<bool>(), 0..2usize, 1..150u32).prop_map(|(other, item, amount)| Op::Insert { other, item, amount }),
+			(any::<bool>(), 0..2usize, 1..150u32).prop_map(|(other, item, amount)| Op::Remove { other, item, amount }),
+			(slot.clone(), slot.clone()).prop_map(|(from, to)| Op::Swap { from, to }),
+			(slot.clone(), 0..20u32, slot.clone()).prop_map(|(from, amount, to)| Op::Split { from, amount, to }),
+			(slot.clone(), slot.clone()).prop_map(|(from, to)| Op::Move { from, to }),
+			(slot.clone(), slot).prop_map(|(from, to)| Op::Merge { from, to }),
+		]
+	}
+
+	/// Runs an op and returns by how much the item count changed.
+	fn apply(carrier: &Carrier, items: &[Item; 2], a: &mut ItemStorage, b: &mut ItemStorage, op: &Op) -> Result<[i64; 2]> {
+		let mut change = [0; 2];
+		match *op {
+			Op::Insert { other, item, amount } => {
+				let storage = if other { b } else { a };
+				let leftover = storage.insert(carrier, ItemStack::new(items[item].clone(), Some(amount)));
+				change[item] = amount as i64 - leftover.map_or(0, |stack| stack.amount() as i64);
+			}
+			Op::Remove { other, item, amount } => {
+				let storage = if other { b } else { a };
+				storage.remove(&items[item], amount)?;
+				change[item] = -(amount as i64);
+			}
+			Op::Swap { from, to } => a.swap_with(from, b, to)?,
+			Op::Split { from, amount, to } => a.split_into(carrier, from, amount, b, to)?,
+			Op::Move { from, to } => a.move_into(carrier, from, b, to)?,
+			Op::Merge { from, to } => {
+				b.merge_into(carrier, from, a, to)?;
+			}
+		}
+		Ok(change)
+	}
+
+	fn check(carrier: &Carrier, storage: &ItemStorage) {
+		assert_eq!(storage.len(), SIZE);
+		for slot in 0..storage.len() {
+			if let Some(stack) = storage.get(slot) {
+				let max_stack = carrier.get::<ItemPrototype>().prototype_from_id(stack.item().ty).max_stack;
+				assert!(stack.amount() > 0, "Empty stack in slot {slot}");
+				assert!(stack.amount() <= max_stack, "Slot {slot} is over max_stack");
+			}
+		}
+	}
+
+	#[test]
+	fn zero_max_stack_never_fits() -> Result<()> {
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["ghost"] = { max_stack = 0 },
+			}
+			"#,
+		);
+		reload!((ItemPrototype) => api);
+		let carrier = api.get_carrier();
+		let ghost = carrier.get::<ItemPrototype>().create_from_tag(&Tag::new("hello:ghost")?)?;
+
+		let mut storage = ItemStorage::new(SIZE);
+		let stack = ItemStack::new(ghost, Some(3));
+		assert_eq!(storage.insert(&carrier, stack.clone()), Some(stack.clone()));
+		assert!(!storage.can_fit(&carrier, &stack));
+		assert!((0..SIZE).all(|slot| storage.get(slot).is_none()));
+		Ok(())
+	}
+
+	#[test]
+	fn set_replaces() -> Result<()> {
+		let carrier = carrier()?;
+		let [stick, stone] = items(&carrier);
+		let mut storage = ItemStorage::new(SIZE);
+		storage.set(2, Some(ItemStack::new(stick, Some(3))))?;
+		storage.set(2, Some(ItemStack::new(stone.clone(), Some(4))))?;
+		assert_eq!(storage.len(), SIZE);
+		assert_eq!(storage.count(&stone), 4);
+		assert!(storage.set(SIZE, None).is_err());
 		Ok(())
 	}
+
+	proptest! {
+		#[test]
+		fn counts_are_conserved(ops in prop::collection::vec(op(), 1..64)) {
+			let carrier = carrier().unwrap();
+			let items = items(&carrier);
+			let mut a = ItemStorage::new(SIZE);
+			let mut b = ItemStorage::new(SIZE);
+			let mut expected = [0i64; 2];
+
+			for op in &ops {
+				let before = (a.clone(), b.clone());
+				match apply(&carrier, &items, &mut a, &mut b, op) {
+					Ok(change) => {
+						expected[0] += change[0];
+						expected[1] += change[1];
+					}
+					// Failing ops do not touch anything.
+					Err(_) => {
+						prop_assert_eq!(&before.0, &a);
+						prop_assert_eq!(&before.1, &b);
+					}
+				}
+
+				check(&carrier, &a);
+				check(&carrier, &b);
+				for (item, expected) in items.iter().zip(expected) {
+					prop_assert_eq!((a.count(item) + b.count(item)) as i64, expected);
+				}
+			}
+		}
+
+		#[test]
+		fn failed_batches_roll_back(ops in prop::collection::vec(op(), 1..32)) {
+			let carrier = carrier().unwrap();
+			let items = items(&carrier);
+			let mut a = ItemStorage::new(SIZE);
+			let mut b = ItemStorage::new(SIZE);
+			a.insert(&carrier, ItemStack::new(items[0].clone(), Some(25)));
+			b.insert(&carrier, ItemStack::new(items[1].clone(), Some(120)));
+			let before = (a.clone(), b.clone());
+
+			let result: Result<()> = a.transaction_with(&mut b, |a, b| {
+				for op in &ops {
+					// Errors in the middle of a batch are ignored, the batch itself always fails at the end.
+					let _ = apply(&carrier, &items, a, b, op);
+				}
+				rsa_core::error::bail!("Rolling back")
+			});
+
+			prop_assert!(result.is_err());
+			prop_assert_eq!(&before.0, &a);
+			prop_assert_eq!(&before.1, &b);
+		}
+	}
 }
@@ -1,11 +1,20 @@
 use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 
 use crossbeam::channel::{Receiver, Sender, TryRecvError};
 use laminar::{Socket, SocketEvent};
+use rsa_core::api::carrier::Carrier;
 use rsa_core::logging::trace;
+use rsa_core::settings::VERSION;
 
 use crate::packet::Packet;
-use crate::Token;
+use crate::server::remote::{self, ACCEPTED, BANNED, HELLO};
+use crate::{Error, Token};
+
+/// How long the server gets to answer each step of the handshake.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the server can stay quiet before we count it as gone.
+pub const REMOTE_TIMEOUT: Duration = Duration::from_secs(60);
 
 // the reason the enum is wrapped is because i think having an enum for client-old and a struct for server is cringe in highlighting because of my theme.
 // So ehm deal with it i guess?
@@ -35,36 +44,99 @@ impl<I: Packet, O: Packet> ClientNetwork<I, O> {
 		})
 	}
 
-	pub fn new_remote(_addr: SocketAddr) -> crate::Result<ClientNetwork<I, O>> {
-		todo!()
+	/// Connects to a server over the network, this blocks until the server accepted or rejected us.
+	/// Both sides need to run the same version with the same plugins, so `carrier` should already be reloaded.
+	pub fn new_remote(addr: SocketAddr, carrier: &Carrier) -> crate::Result<ClientNetwork<I, O>> {
+		let mut socket = Socket::bind_any_with_config(remote::config(REMOTE_TIMEOUT))?;
+		remote::send_raw(&socket, addr, vec![HELLO])?;
+
+		// The server tells us its version, gets ours and says if they match.
+		let version = Self::receive_raw(&mut socket, addr)?;
+		if version == [BANNED] {
+			return Err(Error::Banned);
+		}
+		remote::send_raw(&socket, addr, VERSION.as_bytes().to_vec())?;
+		if Self::receive_answer(&mut socket, addr)? != ACCEPTED {
+			return Err(Error::VersionMismatch {
+				client: VERSION.to_string(),
+				server: String::from_utf8_lossy(&version).into_owned(),
+			});
+		}
+
+		// Then the same for the registry hash, raw ids only mean the same thing if it matches.
+		Self::receive_raw(&mut socket, addr)?;
+		remote::send_raw(&socket, addr, carrier.get_hash().to_vec())?;
+		if Self::receive_answer(&mut socket, addr)? != ACCEPTED {
+			return Err(Error::HashMismatch);
+		}
+
+		trace!(target: "init@networking.client", "Connected to {}", addr);
+		Ok(ClientNetwork {
+			kind: ClientNetworkKind::Remote {
+				addr,
+				socket: Box::new(socket),
+			},
+		})
+	}
+
+	/// Waits for the next handshake message from `addr`.
+	fn receive_raw(socket: &mut Socket, addr: SocketAddr) -> crate::Result<Vec<u8>> {
+		let start = Instant::now();
+		while start.elapsed() < HANDSHAKE_TIMEOUT {
+			socket.manual_poll(Instant::now());
+			while let Ok(event) = socket.get_event_receiver().try_recv() {
+				if let SocketEvent::Packet(packet) = event {
+					if packet.addr() == addr {
+						return Ok(packet.payload().to_vec());
+					}
+				}
+			}
+			std::thread::sleep(Duration::from_millis(1));
+		}
+
+		Err(Error::HandshakeTimeout(HANDSHAKE_TIMEOUT))
+	}
+
+	/// Waits for the server to accept or reject what we just sent.
+	fn receive_answer(socket: &mut Socket, addr: SocketAddr) -> crate::Result<u8> {
+		match Self::receive_raw(socket, addr)?.as_slice() {
+			[answer] => Ok(*answer),
+			_ => Err(Error::InvalidHandshake),
+		}
 	}
 
 	pub fn tick(&mut self) -> crate::Result<ClientTickData<I>> {
 		let mut data = Vec::new();
-		match &self.kind {
+		match &mut self.kind {
 			ClientNetworkKind::Integrated { receive, .. } => loop {
 				match receive.try_recv() {
 					Ok(value) => data.push(value),
 					Err(TryRecvError::Empty) => break,
+					// Hand out what we got first, the server might have said why it closed.
+					Err(TryRecvError::Disconnected) if !data.is_empty() => break,
 					Err(TryRecvError::Disconnected) => {
 						return Ok(ClientTickData::Disconnected);
 					}
 				}
 			},
-			ClientNetworkKind::Remote { addr, socket } => loop {
-				match socket.get_event_receiver().try_recv() {
-					Ok(SocketEvent::Packet(packet)) => {
-						if packet.addr() == *addr {
-							data.push(bincode::deserialize(packet.payload())?);
+			ClientNetworkKind::Remote { addr, socket } => {
+				// Sends what got queued since the last tick and receives what arrived.
+				socket.manual_poll(Instant::now());
+				loop {
+					match socket.get_event_receiver().try_recv() {
+						Ok(SocketEvent::Packet(packet)) => {
+							if packet.addr() == *addr {
+								data.push(bincode::deserialize(packet.payload())?);
+							}
 						}
+						Err(TryRecvError::Disconnected) | Ok(SocketEvent::Disconnect(_)) | Ok(SocketEvent::Timeout(_)) => {
+							return Ok(ClientTickData::Disconnected);
+						}
+						Err(TryRecvError::Empty) => break,
+						_ => {}
 					}
-					Err(TryRecvError::Disconnected) | Ok(SocketEvent::Disconnect(_)) => {
-						return Ok(ClientTickData::Disconnected);
-					}
-					Err(TryRecvError::Empty) => break,
-					_ => {}
 				}
-			},
+			}
 		}
 
 		Ok(ClientTickData::Received(data))
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crossbeam::channel::SendError;
 use thiserror::Error;
 use rsa_core::ty::Uuid;
@@ -29,6 +31,21 @@ pub enum Error {
 
 	#[error("The server type does not support this given feature.")]
 	UnsupportedServerKind,
+
+	#[error("Server runs version {server} but this client runs {client}")]
+	VersionMismatch { client: String, server: String },
+
+	#[error("Server has different plugins loaded than this client")]
+	HashMismatch,
+
+	#[error("Server did not finish the handshake within {0:?}")]
+	HandshakeTimeout(Duration),
+
+	#[error("Server sent an invalid handshake message")]
+	InvalidHandshake,
+
+	#[error("Server does not let this client in right now")]
+	Banned,
 }
 
 impl<T> From<SendError<T>> for Error {
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use rsa_core::logging::trace;
 use crate::packet::Packet;
 use crate::server::integrated::Integrated;
@@ -30,6 +32,28 @@ impl<I: Packet, O: Packet> ServerNetwork<I, O> {
 		Ok(data)
 	}
 
+	/// Drops the connection of a client, it shows up in `to_disconnect` on the next tick.
+	pub fn kick(&mut self, token: Token) {
+		if let Some(integrated) = &mut self.integrated {
+			integrated.leave(token);
+		}
+
+		if let Some(remote) = &mut self.remote {
+			remote.kick(token);
+		}
+	}
+
+	/// Kicks a client and keeps its address out for `duration`, local players only get kicked.
+	pub fn ban(&mut self, token: Token, duration: Duration) {
+		if let Some(integrated) = &mut self.integrated {
+			integrated.leave(token);
+		}
+
+		if let Some(remote) = &mut self.remote {
+			remote.ban(token, duration);
+		}
+	}
+
 	pub fn send(&self, to: Token, packet: O) -> crate::Result<()> {
 		if let Some(remote) = &self.remote {
 			remote.send(to, &packet)?;
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crossbeam::channel::{Receiver, Sender};
+use crossbeam::channel::{Receiver, Sender, TryRecvError};
 
 use crate::packet::Packet;
 use crate::server::ServerTickData;
@@ -36,11 +36,25 @@ impl<I: Packet, O: Packet> Integrated<I, O> {
 			data.to_disconnect.push(token);
 		}
 
+		let mut left = Vec::new();
 		for (from, (_, receiver)) in &self.integrated_connections {
-			while let Ok(packet) = receiver.try_recv() {
-				data.received.push((*from, packet));
+			loop {
+				match receiver.try_recv() {
+					Ok(packet) => data.received.push((*from, packet)),
+					Err(TryRecvError::Empty) => break,
+					// The client dropped its end.
+					Err(TryRecvError::Disconnected) => {
+						left.push(*from);
+						break;
+					}
+				}
 			}
 		}
+
+		for token in left {
+			self.integrated_connections.remove(&token);
+			data.to_disconnect.push(token);
+		}
 		Ok(())
 	}
 
@@ -1,7 +1,7 @@
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::marker::PhantomData;
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::time::{Duration, Instant};
 
 use bimap::BiMap;
@@ -15,6 +15,26 @@ use crate::packet::Packet;
 use crate::server::ServerTickData;
 use crate::Token;
 
+/// The first thing a client sends, anything else from an address we do not know gets ignored.
+pub(crate) const HELLO: u8 = 0x69;
+/// What the server answers when the version or hash of the client matches its own.
+pub(crate) const ACCEPTED: u8 = 1;
+/// What the server answers when they do not, the client has to start over.
+pub(crate) const REJECTED: u8 = 0;
+/// What the server answers instead of its version when the address is banned.
+pub(crate) const BANNED: u8 = 2;
+/// The handshake gets its own ordered stream, so the answer and the hash after it arrive in order.
+const HANDSHAKE_STREAM: u8 = HELLO;
+
+/// The connection is the same on both sides, only how long it takes to time out differs.
+pub(crate) fn config(timeout: Duration) -> Config {
+	Config {
+		heartbeat_interval: Some(Duration::from_secs(5)),
+		idle_connection_timeout: timeout,
+		..Default::default()
+	}
+}
+
 pub struct Remote<I: Packet, O: Packet> {
 	version: Vec<u8>,
 	hash: Vec<u8>,
@@ -23,6 +43,9 @@ pub struct Remote<I: Packet, O: Packet> {
 	socket: Socket,
 	clients: BiMap<Token, SocketAddr>,
 	establishing: HashMap<SocketAddr, EstablishingStep>,
+	kicked: Vec<Token>,
+	/// Addresses that can not connect until the time runs out.
+	banned: HashMap<IpAddr, Instant>,
 
 	_p: PhantomData<(O, I)>,
 }
@@ -30,7 +53,7 @@ pub struct Remote<I: Packet, O: Packet> {
 pub struct RemoteSettings {
 	/// A forced address to bind to on the server.
 	pub address: Option<Ipv4Addr>,
-	/// The port to use on the address.
+	/// The port to use on the address, 0 lets the system pick a free one which [`Remote::local_addr`] tells.
 	/// - default: 42069
 	pub port: Option<u16>,
 	/// The timeout is the time required for the client-old to be disconnected without a response.
@@ -40,12 +63,7 @@ pub struct RemoteSettings {
 
 impl<I: Packet, O: Packet> Remote<I, O> {
 	pub fn new(settings: RemoteSettings, carrier: &Carrier) -> crate::Result<Remote<I, O>> {
-		let config = Config {
-			heartbeat_interval: Some(Duration::from_secs(5)),
-			idle_connection_timeout: settings.timeout,
-			..Default::default()
-		};
-
+		let config = config(settings.timeout);
 		let ip = settings.address.unwrap_or(Ipv4Addr::new(127, 0, 0, 1));
 		let port = settings.port.unwrap_or(42069);
 
@@ -55,12 +73,37 @@ impl<I: Packet, O: Packet> Remote<I, O> {
 			socket: { Socket::bind_with_config(SocketAddrV4::new(ip, port), config)? },
 			clients: Default::default(),
 			establishing: Default::default(),
+			kicked: Default::default(),
+			banned: Default::default(),
 			_p: Default::default(),
 		})
 	}
 
+	/// The address the socket actually got bound to.
+	pub fn local_addr(&self) -> crate::Result<SocketAddr> {
+		Ok(self.socket.local_addr()?)
+	}
+
+	/// Forgets about a client, anything it sends afterwards gets treated like a new connection.
+	pub fn kick(&mut self, token: Token) {
+		if self.clients.remove_by_left(&token).is_some() {
+			self.kicked.push(token);
+		}
+	}
+
+	/// Kicks a client and ignores new connections from its address until `duration` passed.
+	pub fn ban(&mut self, token: Token, duration: Duration) {
+		if let Some(addr) = self.clients.get_by_left(&token) {
+			self.banned.insert(addr.ip(), Instant::now() + duration);
+		}
+		self.kick(token);
+	}
+
 	pub fn tick(&mut self, data: &mut ServerTickData<I>) -> crate::Result<()> {
-		self.socket.manual_poll(Instant::now());
+		data.to_disconnect.append(&mut self.kicked);
+		let now = Instant::now();
+		self.banned.retain(|_, until| *until > now);
+		self.socket.manual_poll(now);
 		while let Ok(event) = self.socket.get_event_receiver().try_recv() {
 			match event {
 				SocketEvent::Packet(packet) => {
@@ -72,41 +115,47 @@ impl<I: Packet, O: Packet> Remote<I, O> {
 							error!(target: "tick@networking.server", "Invalid packet from {}@{}", from, addr);
 						}
 					} else {
+						// We send our version and hash first, the client answers with theirs and we say if they match.
 						match self.establishing.entry(addr) {
 							Entry::Occupied(mut entry) => match entry.get_mut() {
-								value @ EstablishingStep::SendVersion => {
-									send_raw(&self.socket, addr, self.version.clone())?;
-									*value = EstablishingStep::SendHash;
-								}
-								value @ EstablishingStep::SendHash => {
+								value @ EstablishingStep::AwaitVersion => {
 									if packet.payload() == self.version {
-										send_raw(&self.socket, addr, vec![1])?;
+										send_raw(&self.socket, addr, vec![ACCEPTED])?;
 										send_raw(&self.socket, addr, self.hash.clone())?;
 										*value = EstablishingStep::AwaitHash;
 									} else {
-										send_raw(&self.socket, addr, vec![0])?;
+										send_raw(&self.socket, addr, vec![REJECTED])?;
 										entry.remove();
 									}
 								}
 								EstablishingStep::AwaitHash => {
 									if packet.payload() == self.hash {
-										send_raw(&self.socket, addr, vec![1])?;
-										data.to_connect.push(Token::new_v4());
+										send_raw(&self.socket, addr, vec![ACCEPTED])?;
+										let token = Token::new_v4();
+										self.clients.insert(token, addr);
+										data.to_connect.push(token);
 									} else {
-										send_raw(&self.socket, addr, vec![0])?;
+										send_raw(&self.socket, addr, vec![REJECTED])?;
 									}
 									entry.remove();
 								}
 							},
+							Entry::Vacant(_) if self.banned.contains_key(&addr.ip()) => {
+								if packet.payload() == [HELLO] {
+									send_raw(&self.socket, addr, vec![BANNED])?;
+								}
+							}
 							Entry::Vacant(entry) => {
-								if packet.payload() == [0x69] {
-									entry.insert(EstablishingStep::SendVersion);
+								if packet.payload() == [HELLO] {
+									send_raw(&self.socket, addr, self.version.clone())?;
+									entry.insert(EstablishingStep::AwaitVersion);
 								}
 							}
 						}
 					}
 				}
 				SocketEvent::Disconnect(addr) => {
+					self.establishing.remove(&addr);
 					if let Some((token, _)) = self.clients.remove_by_right(&addr) {
 						data.to_disconnect.push(token);
 					}
@@ -143,16 +192,77 @@ impl<I: Packet, O: Packet> Remote<I, O> {
 	}
 }
 
-fn send_raw(socket: &Socket, addr: SocketAddr, payload: Vec<u8>) -> crate::Result<()> {
+/// Sends a handshake message, these are plain bytes instead of packets.
+pub(crate) fn send_raw(socket: &Socket, addr: SocketAddr, payload: Vec<u8>) -> crate::Result<()> {
 	socket
 		.get_packet_sender()
-		.send(laminar::Packet::reliable_unordered(addr, payload))?;
+		.send(laminar::Packet::reliable_ordered(addr, payload, Some(HANDSHAKE_STREAM)))?;
 
 	Ok(())
 }
 
 enum EstablishingStep {
-	SendVersion,
-	SendHash,
+	AwaitVersion,
 	AwaitHash,
 }
+
+#[cfg(test)]
+mod tests {
+	use std::thread;
+	use std::time::{Duration, Instant};
+
+	use rsa_core::api::Api;
+
+	use crate::client::ClientNetwork;
+	use crate::packet::{OrderKind, Packet, PacketDesc, ReliableKind};
+	use crate::server::remote::{Remote, RemoteSettings};
+	use crate::server::ServerTickData;
+	use crate::Error;
+
+	#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+	struct Nothing;
+
+	impl Packet for Nothing {
+		fn get_desc(&self) -> PacketDesc {
+			PacketDesc {
+				reliable: ReliableKind::Reliable,
+				ordered: OrderKind::None,
+			}
+		}
+	}
+
+	#[test]
+	fn rejects_other_plugins() -> crate::Result<()> {
+		let carrier = Api::new_test().get_carrier();
+		let mut remote: Remote<Nothing, Nothing> = Remote::new(
+			RemoteSettings {
+				address: None,
+				port: Some(0),
+				timeout: Duration::from_secs(10),
+			},
+			&carrier,
+		)?;
+		remote.hash = vec![1; 32];
+		let addr = remote.local_addr()?;
+
+		let server = thread::spawn(move || -> crate::Result<Vec<_>> {
+			let mut data = ServerTickData {
+				received: vec![],
+				to_disconnect: vec![],
+				to_connect: vec![],
+			};
+			let start = Instant::now();
+			// Long enough for the whole handshake, the client only gets rejected at the end of it.
+			while start.elapsed() < Duration::from_secs(2) {
+				remote.tick(&mut data)?;
+				thread::sleep(Duration::from_millis(1));
+			}
+			Ok(data.to_connect)
+		});
+
+		let result = ClientNetwork::<Nothing, Nothing>::new_remote(addr, &carrier);
+		assert!(matches!(result, Err(Error::HashMismatch)));
+		assert!(server.join().unwrap()?.is_empty());
+		Ok(())
+	}
+}
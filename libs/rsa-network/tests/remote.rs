@@ -0,0 +1,92 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use rsa_core::api::Api;
+use rsa_network::client::{ClientNetwork, ClientTickData};
+use rsa_network::packet::{OrderKind, Packet, PacketDesc, ReliableKind};
+use rsa_network::server::remote::{Remote, RemoteSettings};
+use rsa_network::server::ServerNetwork;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+enum TestPacket {
+	Ping(u32),
+	Pong(u32),
+}
+
+impl Packet for TestPacket {
+	fn get_desc(&self) -> PacketDesc {
+		PacketDesc {
+			reliable: ReliableKind::Reliable,
+			ordered: OrderKind::Ordered(None),
+		}
+	}
+}
+
+/// Connects a remote client to a server on localhost and plays ping pong over it.
+#[test]
+fn loopback() -> rsa_network::Result<()> {
+	let carrier = Api::new_test().get_carrier();
+	let mut server: ServerNetwork<TestPacket, TestPacket> = ServerNetwork {
+		integrated: None,
+		remote: Some(Remote::new(
+			RemoteSettings {
+				address: None,
+				port: Some(0),
+				timeout: Duration::from_secs(10),
+			},
+			&carrier,
+		)?),
+	};
+
+	let addr = server.remote.as_ref().unwrap().local_addr()?;
+
+	// The handshake blocks the client, so the server runs next to it.
+	let server = thread::spawn(move || -> rsa_network::Result<Vec<TestPacket>> {
+		let start = Instant::now();
+		let mut received = Vec::new();
+		while start.elapsed() < Duration::from_secs(10) {
+			let data = server.tick()?;
+			for token in data.to_connect {
+				server.send(token, TestPacket::Ping(1))?;
+			}
+			for (from, packet) in data.received {
+				if let TestPacket::Pong(number) = packet {
+					if number < 3 {
+						server.send(from, TestPacket::Ping(number + 1))?;
+					}
+				}
+				received.push(packet);
+			}
+
+			if received.len() == 3 {
+				break;
+			}
+			thread::sleep(Duration::from_millis(1));
+		}
+		Ok(received)
+	});
+
+	let mut client: ClientNetwork<TestPacket, TestPacket> = ClientNetwork::new_remote(addr, &carrier)?;
+
+	// Keep ticking until the server is done, the last pong only goes out on the next tick.
+	let start = Instant::now();
+	while !server.is_finished() && start.elapsed() < Duration::from_secs(10) {
+		match client.tick()? {
+			ClientTickData::Received(packets) => {
+				for packet in packets {
+					if let TestPacket::Ping(number) = packet {
+						client.send(TestPacket::Pong(number))?;
+					}
+				}
+			}
+			ClientTickData::Disconnected => panic!("Server disconnected"),
+		}
+		thread::sleep(Duration::from_millis(1));
+	}
+
+	let received = server.join().unwrap()?;
+	assert_eq!(received, vec![TestPacket::Pong(1), TestPacket::Pong(2), TestPacket::Pong(3)]);
+	Ok(())
+}
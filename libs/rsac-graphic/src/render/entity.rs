@@ -1,13 +1,24 @@
 mod renderer;
 
+use glium::{uniform, Surface};
+use rsa_core::api::Api;
+use rsa_core::error::{ContextCompat, Result};
+use rustaria::entity::component::animation::AnimationComp;
+use rustaria::entity::component::pos::PositionComp;
+use rustaria::entity::component::prototype::PrototypeComp;
+use rustaria::entity::prototype::EntityPrototype;
+use rustaria::entity::EntitySystem;
+
 use crate::draw::buffer::DrawBuffer;
-use crate::Drawer;
+use crate::mesh_builder::MeshBuilder;
 use crate::render::entity::renderer::EntityTypeRenderer;
 use crate::ty::PosTex;
-use rsa_core::error::Result;
+use crate::{Draw, Drawer};
 
 pub(crate) struct EntityRenderer {
 	buffer: DrawBuffer<PosTex>,
+	// Entities move every frame so the mesh gets rebuilt every frame.
+	builder: MeshBuilder<PosTex>,
 
 	// entity id to EntityTypeRenderer
 	renderers: Vec<Option<EntityTypeRenderer>>,
@@ -17,7 +28,56 @@ impl EntityRenderer {
 	pub fn new(drawer: &Drawer) -> Result<EntityRenderer> {
 		Ok(EntityRenderer {
 			buffer: DrawBuffer::new(drawer)?,
+			builder: MeshBuilder::new(),
 			renderers: vec![]
 		})
 	}
-}
\ No newline at end of file
+
+	pub fn draw(&mut self, draw: &mut Draw, entities: &EntitySystem) -> Result<()> {
+		let drawer = &draw.system.drawer;
+		self.builder.clear();
+		for (_, (prototype, position, animation)) in entities
+			.query::<(&PrototypeComp, &PositionComp, Option<&AnimationComp>)>()
+			.iter()
+		{
+			if let Some(Some(renderer)) = self.renderers.get(prototype.0.index()) {
+				let state = animation.map(|animation| (animation.state.as_str(), animation.ticks));
+				renderer.append_mesh(drawer, &mut self.builder, position.position, state);
+			}
+		}
+
+		if self.builder.index_data.is_empty() {
+			return Ok(());
+		}
+		self.buffer.submit(drawer, &self.builder)?;
+
+		let program = drawer.get_program("pos_tex")?;
+		let storage = uniform! {
+			screen_y_ratio: drawer.screen_ratio,
+			scale: drawer.camera.scale,
+			player_pos: drawer.camera.pos.to_array(),
+			tex: drawer.atlas.sampler()
+		};
+
+		// The buffer is never shrunk, so only draw what got written this frame.
+		let indices = self
+			.buffer
+			.index_buffer
+			.slice(0..self.builder.index_data.len())
+			.wrap_err("Entity mesh does not fit the index buffer")?;
+		draw.frame.draw(&self.buffer.vertex_buffer, indices, program, &storage, &glium::draw_parameters::DrawParameters {
+			blend: glium::draw_parameters::Blend::alpha_blending(),
+			..glium::draw_parameters::DrawParameters::default()
+		})?;
+
+		Ok(())
+	}
+
+	pub fn reload(&mut self, api: &Api) {
+		let carrier = api.get_carrier();
+		self.renderers.clear();
+		for prototype in carrier.get::<EntityPrototype>().iter() {
+			self.renderers.push(prototype.rendering.clone().map(|rendering| EntityTypeRenderer { rendering }));
+		}
+	}
+}
@@ -1,10 +1,37 @@
-use rsa_core::ty::Tag;
+use rsa_core::math::{rect, Vector2D, WorldSpace};
+use rustaria::api::rendering::RenderingSystem;
+use rustaria::entity::component::animation::IDLE;
+
+use crate::mesh_builder::MeshBuilder;
+use crate::ty::PosTex;
+use crate::Drawer;
 
 /// An entity renderer is responsible for rendering a single entity type.
-pub struct EntityTypeRenderer {
-	pub image: Tag,
-	pub x_offset: f32,
-	pub y_offset: f32,
-	pub width: f32,
-	pub height: f32,
-}
\ No newline at end of file
+pub(crate) struct EntityTypeRenderer {
+	pub rendering: RenderingSystem,
+}
+
+impl EntityTypeRenderer {
+	/// Draws the pane for the animation `state` the entity has been in for `ticks` ticks,
+	/// entities without an AnimationComp get drawn as idle.
+	pub fn append_mesh(
+		&self,
+		drawer: &Drawer,
+		builder: &mut MeshBuilder<PosTex>,
+		position: Vector2D<f32, WorldSpace>,
+		state: Option<(&str, u32)>,
+	) {
+		let (state, ticks) = state.unwrap_or((IDLE, 0));
+		if let Some(pane) = self.rendering.pane(state, ticks) {
+			builder.push_quad((
+				rect::<_, WorldSpace>(
+					position.x + pane.x_offset,
+					position.y + pane.y_offset,
+					pane.width,
+					pane.height,
+				),
+				drawer.atlas.get(&pane.sprite),
+			));
+		}
+	}
+}
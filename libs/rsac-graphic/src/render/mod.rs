@@ -1,4 +1,5 @@
 use crate::render::chunk::ChunkRenderer;
+use crate::render::entity::EntityRenderer;
 use crate::{Draw, GraphicSystem};
 use glium::{Program};
 use glium::program::{SourceCode};
@@ -12,6 +13,7 @@ mod entity;
 
 pub struct WorldRenderer {
 	chunk: ChunkRenderer,
+	entity: EntityRenderer,
 }
 
 impl WorldRenderer {
@@ -26,6 +28,7 @@ impl WorldRenderer {
 
 		Ok(WorldRenderer {
 			chunk: ChunkRenderer::new(&system.drawer)?,
+			entity: EntityRenderer::new(&system.drawer)?,
 		})
 	}
 
@@ -35,11 +38,13 @@ impl WorldRenderer {
 
 	pub fn draw(&mut self, draw: &mut Draw, world: &World) -> Result<()> {
 		self.chunk.draw(draw, &world.chunks)?;
+		self.entity.draw(draw, &world.entities)?;
 		Ok(())
 	}
 
 	pub fn reload(&mut self, api: &Api, graphics: &mut GraphicSystem) -> Result<()>{
 		self.chunk.reload(api, &graphics.drawer);
+		self.entity.reload(api);
 		Ok(())
 	}
 }
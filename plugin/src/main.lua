@@ -5,11 +5,74 @@ log.info "Registering tiles."
 reload.registry.tile:insert {
     ["dirt"] = {
         sprite = "sprite/tile/dirt.png",
-        collision = true
+        collision = true,
+        item = "dirt"
+    },
+    ["platform"] = {
+        sprite = "sprite/tile/dirt.png",
+        collision = true,
+        opaque = false,
+        shape = "Platform"
+    },
+    ["bed"] = {
+        sprite = "sprite/tile/dirt.png",
+        collision = false,
+        item = "bed",
+        bed = true
     },
     ["air"] = {}
 }
 
+log.info "Registering items."
+reload.registry.item:insert {
+    ["dirt"] = {
+        max_stack = 999,
+        use_style = "PlaceTile",
+        use_time = 5,
+        tile = "dirt"
+    },
+    ["bed"] = {
+        max_stack = 1,
+        use_style = "PlaceTile",
+        use_time = 15,
+        tile = "bed"
+    },
+    ["pickaxe"] = {
+        max_stack = 1,
+        use_style = "Swing",
+        use_time = 15,
+        tool_power = 1,
+        damage = 4
+    },
+    ["bow"] = {
+        max_stack = 1,
+        use_style = "Shoot",
+        use_time = 25,
+        projectile = "arrow"
+    },
+    ["slime"] = {
+        max_stack = 99,
+        use_style = "Consume",
+        effect = "slowness"
+    },
+    ["slime_helmet"] = {
+        max_stack = 1,
+        equipment = "Head",
+        modifiers = {
+            defense = { add = 1 },
+            max_health = { add = 20 },
+        }
+    },
+    ["bunne_boots"] = {
+        max_stack = 1,
+        equipment = "Accessory",
+        modifiers = {
+            run_speed = { multiply = 1.2 },
+            jump_speed = { multiply = 1.1 },
+        }
+    }
+}
+
 log.info "Registering entities."
 reload.registry.entity:insert {
     ["player"] = {
@@ -27,6 +90,9 @@ reload.registry.entity:insert {
             x = 0.0,
             y = 0.0,
         },
+        collector = {
+            range = 5
+        },
         humanoid = {
             jump_frames = 15,
             jump_speed = 20,
@@ -34,16 +100,77 @@ reload.registry.entity:insert {
             run_slowdown = 1.2 * 60,
             run_max_speed = 12.0 * 60,
         },
+        animation = {
+            state = "idle"
+        },
+        rendering = {
+            State = {
+                idle = {
+                    x_offset = 0,
+                    y_offset = 0,
+                    width = 2,
+                    height = 3,
+                    sprite = "glisco.png"
+                },
+                walk = {
+                    looping = true,
+                    frames = {
+                        { duration = 8, x_offset = 0, y_offset = 0, width = 2, height = 3, sprite = "glisco.png" },
+                        { duration = 8, x_offset = 0, y_offset = 0, width = 2, height = 3, sprite = "lab.png" },
+                    }
+                },
+            }
+        }
+    },
+    ["arrow"] = {
+        hitbox = {
+            x = 0,
+            y = 0,
+            width = 0.5,
+            height = 0.5,
+        },
+        velocity = {
+            x = 0.0,
+            y = 0.0,
+        },
+        projectile = {
+            speed = 40,
+            lifetime = 300,
+            pierce = 0,
+            gravity = 0.5,
+        },
         rendering = {
             Static = {
                 x_offset = 0,
                 y_offset = 0,
-                width = 2,
-                height = 3,
+                width = 0.5,
+                height = 0.5,
                 sprite = "glisco.png"
             }
         }
     },
+    ["item"] = {
+        hitbox = {
+            x = 0,
+            y = 0,
+            width = 0.5,
+            height = 0.5,
+        },
+        gravity = 1.0,
+        velocity = {
+            x = 0.0,
+            y = 0.0,
+        },
+        rendering = {
+            Static = {
+                x_offset = 0,
+                y_offset = 0,
+                width = 0.5,
+                height = 0.5,
+                sprite = "sprite/tile/dirt.png"
+            }
+        }
+    },
     ["bunne"] = {
         hitbox = {
             x = 0,
@@ -55,6 +182,35 @@ reload.registry.entity:insert {
             x = 3.0,
             y = 0.2,
         },
+        health = {
+            maximum = 20
+        },
+        loot_table = "bunne",
+        components = {
+            mood = { replicate = true, default = "calm" },
+            hops = { default = 0 },
+        },
+        behaviour = {
+            tick = function(entity)
+                -- Hop away from anything that gets close.
+                local nearby = entity:query_radius(8)
+                if #nearby > 0 then
+                    entity:set_component("mood", "scared")
+                    entity:set_component("hops", entity:get_component("hops") + 1)
+                    local x, y = entity:position()
+                    local other_x, _ = nearby[1]:position()
+                    local _, vel_y = entity:velocity()
+                    entity:set_velocity(x < other_x and -6 or 6, vel_y)
+                end
+            end,
+            hit = function(entity, projectile)
+                entity:damage(5)
+                entity:apply_effect("slowness")
+            end,
+            death = function(entity)
+                log.info "A bunne died :("
+            end
+        },
         rendering = {
             Static = {
                 x_offset = 0,
@@ -66,3 +222,58 @@ reload.registry.entity:insert {
         }
     }
 }
+
+reload.registry.loot_table:insert {
+    ["bunne"] = {
+        pools = {
+            {
+                rolls = { min = 1, max = 2 },
+                entries = {
+                    { item = "slime", weight = 3, amount = { min = 1, max = 3 } },
+                    { item = "dirt", weight = 1 },
+                },
+            },
+            { entries = { { item = "bow", conditions = { chance = 0.05 } } } },
+        },
+    }
+}
+
+reload.registry.biome:insert {
+    ["forest"] = { zones = { "Surface" }, x = { max = 50 } },
+    ["caves"] = { zones = { "Underground" } },
+    ["depths"] = { zones = { "Underground" }, depth = { min = 16 }, priority = 1 },
+}
+
+reload.registry.spawn_rule:insert {
+    ["bunne"] = {
+        entity = "bunne",
+        weight = 10,
+        biomes = { "forest" },
+        light = { min = 0.5 },
+        time = "Day",
+        max_per_player = 4,
+    }
+}
+
+reload.registry.status_effect:insert {
+    ["slowness"] = {
+        duration = 300,
+        stacking = "Stack",
+        max_stacks = 3,
+        modifiers = {
+            run_speed = { multiply = 0.7 },
+            jump_speed = { multiply = 0.85 },
+        },
+    },
+    ["poison"] = {
+        duration = 600,
+        stacking = "Refresh",
+        tick_interval = 60,
+        modifiers = {
+            damage_taken = { multiply = 1.25 },
+        },
+        on_tick = function(entity)
+            entity:damage(1)
+        end,
+    }
+}
@@ -1,9 +1,16 @@
 use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use rsa_core::logging::LevelFilter;
 
 pub struct ClientOptions {
 	pub mode: ClientMode,
 	pub logging: LevelFilter,
+	/// The name other players see, set with `--name=<name>`.
+	pub name: String,
+	/// Joins a server instead of starting our own, set with `--connect=<ip:port>`.
+	pub connect: Option<SocketAddr>,
+	/// Lets other clients join our world on this port, set with `--host=<port>`.
+	pub host: Option<u16>,
 }
 
 pub enum ClientMode {
@@ -38,7 +45,20 @@ impl ClientOptions {
 		let args: HashSet<String> = std::env::args().collect();
 		ClientOptions {
 			mode:  Self::get_arg(&args, MODE_ARGS),
-			logging:  Self::get_arg(&args, LOG_ARGS)
+			logging:  Self::get_arg(&args, LOG_ARGS),
+			name: args
+				.iter()
+				.find_map(|arg| arg.strip_prefix("--name="))
+				.unwrap_or("Player")
+				.to_string(),
+			connect: args
+				.iter()
+				.find_map(|arg| arg.strip_prefix("--connect="))
+				.and_then(|addr| addr.parse().ok()),
+			host: args
+				.iter()
+				.find_map(|arg| arg.strip_prefix("--host="))
+				.and_then(|port| port.parse().ok()),
 		}
 	}
 
@@ -1,17 +1,25 @@
 use std::sync::Arc;
 use rsa_core::api::Api;
 use rsa_core::error::{Context, Report, Result};
-use rsa_core::logging::{debug, info};
+use rsa_core::logging::{debug, info, warn};
 use rsa_core::reload;
 use rsa_core::settings::UPS;
+use rsa_item::item::ItemPrototype;
+use rsa_item::recipe::RecipePrototype;
+use rustaria::loot::LootTablePrototype;
 use rsac_graphic::GraphicSystem;
 use rustaria::chunk::layer::tile::TilePrototype;
 use rustaria::entity::prototype::EntityPrototype;
+use rustaria::entity::spawn_rule::SpawnRulePrototype;
+use rustaria::world::biome::BiomePrototype;
+use rustaria::entity::status_effect::StatusEffectPrototype;
 use std::time::{Duration, Instant};
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use rsa_core::math::vec2;
 use rsac_graphic::camera::Camera;
+use rustaria::player::PlayerId;
 use rustaria::CarrierUnavailable;
+use std::path::Path;
 
 use crate::args::{ClientMode, ClientOptions};
 use crate::input::InputModule;
@@ -28,7 +36,23 @@ const TICK_DURATION: Duration = Duration::from_nanos((1000000000 / UPS) as u64);
 fn main() -> Result<()> {
 	let mut client = Client::new().wrap_err("Failed to initialize core systems")?;
 	client.reload()?;
-	client.world = Some(ClientWorld::new_integrated(&client.api, &mut client.graphics,client.thread_pool.clone())?);
+	client.world = Some(match client.options.connect {
+		Some(addr) => ClientWorld::new_remote(
+			&client.api,
+			&mut client.graphics,
+			addr,
+			client.options.name.clone(),
+			client.player_id,
+		)?,
+		None => ClientWorld::new_integrated(
+			&client.api,
+			&mut client.graphics,
+			client.thread_pool.clone(),
+			client.options.host,
+			client.options.name.clone(),
+			client.player_id,
+		)?,
+	});
 
 	// If the loop fails we try to recover it. Else we nuke the client-old and go on with our day.
 	while let Err(report) = client.run_loop() {
@@ -42,6 +66,7 @@ pub struct Client {
 	thread_pool: Arc<ThreadPool>,
 	camera: Camera,
 	options: ClientOptions,
+	player_id: PlayerId,
 
 	world: Option<ClientWorld>,
 
@@ -72,9 +97,11 @@ impl Client {
 		)?;
 
 		let input = InputModule::new();
+		let player_id = Self::load_player_id(&dir)?;
 
 		Ok(Client {
 			options,
+			player_id,
 			camera: Camera {
 				pos: vec2(8.0, 8.0),
 				scale: 20.0
@@ -87,8 +114,22 @@ impl Client {
 		})
 	}
 
+	/// The id is made once and kept around so servers recognize us again.
+	fn load_player_id(dir: &Path) -> Result<PlayerId> {
+		let path = dir.join("player.id");
+		if let Ok(data) = std::fs::read_to_string(&path) {
+			if let Ok(id) = PlayerId::parse_str(data.trim()) {
+				return Ok(id);
+			}
+		}
+
+		let id = PlayerId::new_v4();
+		std::fs::write(&path, id.to_string()).wrap_err("Could not save player id")?;
+		Ok(id)
+	}
+
 	pub fn reload(&mut self) -> Result<()> {
-		reload!((TilePrototype, EntityPrototype) => &mut self.api);
+		reload!((TilePrototype, EntityPrototype, SpawnRulePrototype, BiomePrototype, StatusEffectPrototype, ItemPrototype, RecipePrototype, LootTablePrototype) => &mut self.api);
 		self.graphics
 			.reload(&self.api)
 			.wrap_err("Failed to reload Graphics System")?;
@@ -132,6 +173,10 @@ impl Client {
 	pub fn tick(&mut self) -> Result<()> {
 		if let Some(world) = &mut self.world {
 			world.tick(&mut self.input)?;
+			if let Some(reason) = world.disconnected() {
+				info!(target: "misc@rustariac", "Disconnected: {reason}");
+				self.world = None;
+			}
 		}
 		Ok(())
 	}
@@ -156,8 +201,11 @@ impl Client {
 			if report.downcast_ref::<CarrierUnavailable>().is_some() {
 				debug!("{report}");
 				return self.reload().wrap_err("Failed to reload on bail.");
-			} else {
-				//todo!("Leave server")
+			} else if self.world.is_some() {
+				// Leave the world instead of taking the whole client down.
+				warn!(target: "misc@rustariac", "Leaving world after failure: {report:?}");
+				self.world = None;
+				return Ok(());
 			}
 		}
 
@@ -1,14 +1,17 @@
 use crate::vec2;
 use rsa_core::api::Api;
 use rsa_core::error::{ContextCompat, Result};
-use rsa_core::logging::{trace, warn};
+use rsa_core::logging::{info, trace, warn};
 use rsa_core::math::{Vector2D, WorldSpace};
+use rsa_core::ty::RawId;
 use rsac_graphic::camera::Camera;
 use rustaria::entity::component::humanoid::HumanoidComp;
 use rustaria::entity::component::pos::PositionComp;
+use rustaria::entity::component::stats::{StatModifiers, StatsComp};
 use rustaria::entity::packet::ClientEntityPacket;
 use rustaria::entity::{Entity, EntitySystem};
-use rustaria::packet::player::ServerPlayerPacket;
+use rustaria::packet::player::{ClientPlayerPacket, ServerPlayerPacket};
+use rustaria::player::inventory::{InventorySlot, PlayerInventory, SlotAction};
 use rustaria::packet::ClientPacket;
 use rustaria::world::World;
 use rustaria::ClientNetwork;
@@ -32,6 +35,9 @@ pub struct PlayerModule {
 	//
 	send_dir: PlayerDir,
 	old_pos: f32,
+
+	/// Our copy of the inventory, only the server changes it.
+	inventory: PlayerInventory,
 }
 
 impl PlayerModule {
@@ -46,6 +52,7 @@ impl PlayerModule {
 			input_dir: Default::default(),
 			send_dir: Default::default(),
 			old_pos: 0.0,
+			inventory: PlayerInventory::new(),
 		}
 	}
 
@@ -85,7 +92,8 @@ impl PlayerModule {
 			tick,
 			self.send_dir,
 		)))?;
-		self.unprocessed_commands.push_front((tick, self.input_dir));
+		// Replay exactly what the server got, else the prediction drifts away from it.
+		self.unprocessed_commands.push_front((tick, self.send_dir));
 		self.send_dir = vec2(0.0, 0.0);
 		Ok(())
 	}
@@ -107,7 +115,7 @@ impl PlayerModule {
 					if let Ok(mut comp) = self.base_system.get_mut::<HumanoidComp>(*player_entity) {
 						comp.dir = speed;
 					}
-					self.base_system.tick(&world.chunks, 1.0)?;
+					self.base_system.tick(&world.chunks)?;
 
 					// If we reach the tick that we currently received,
 					// stop as the next events are the ones that the server has not yet seen.
@@ -138,8 +146,91 @@ impl PlayerModule {
 				self.prediction_system.insert(entity, builder.build());
 
 				self.player_entity = Some(entity);
+				self.update_equipment(world);
+			}
+			ServerPlayerPacket::Died { respawn_in } => {
+				info!(target: "misc@rustariac.player", "You died, respawning in {respawn_in} ticks.");
 			}
+			ServerPlayerPacket::Respawn { entity, position } => {
+				if let Ok(mut comp) = world.entities.get_mut::<PositionComp>(entity) {
+					comp.position = position;
+				}
+
+				// Everything we predicted happened before dying, start over from what the server says.
+				self.unprocessed_commands.clear();
+				let mut builder = world
+					.entities
+					.clone(entity)
+					.wrap_err("Player does not exist in the world")?;
+				self.base_system.clear();
+				self.base_system.insert(entity, builder.build());
+				self.prediction_system.clear();
+				self.prediction_system.insert(entity, builder.build());
+			}
+			ServerPlayerPacket::Slots(slots) => {
+				let mut equipment_changed = false;
+				for (slot, stack) in slots {
+					equipment_changed |= matches!(slot, InventorySlot::Equipment(_));
+					*self.inventory.slot_mut(slot)? = stack;
+				}
+				if equipment_changed {
+					self.update_equipment(world);
+				}
+			}
+			// The world handles this one.
+			ServerPlayerPacket::Disconnect { .. } => {}
+		}
+		Ok(())
+	}
+
+	/// Puts the modifiers of our equipment on our entity, the prediction gets rebuilt from the base system.
+	fn update_equipment(&mut self, world: &mut World) {
+		let entity = match self.player_entity {
+			Some(entity) => entity,
+			None => return,
+		};
+
+		let carrier = self.api.get_carrier();
+		let modifiers = self.inventory.equipment_modifiers(&carrier);
+		for entities in [&mut world.entities, &mut self.base_system] {
+			StatsComp::modify(&carrier, entities, entity, |stats| stats.set_equipment(modifiers));
 		}
+	}
+
+	/// Puts the status effect modifiers on an entity. Ours also goes into the base system,
+	/// else the prediction runs with the old stats and the server has to correct it every tick.
+	pub fn update_effects(&mut self, world: &mut World, entity: Entity, modifiers: StatModifiers) {
+		let carrier = self.api.get_carrier();
+		StatsComp::modify(&carrier, &mut world.entities, entity, |stats| stats.set_effects(modifiers));
+		if self.player_entity == Some(entity) {
+			StatsComp::modify(&carrier, &mut self.base_system, entity, |stats| stats.set_effects(modifiers));
+		}
+	}
+
+	// Nothing draws the inventory yet.
+	#[allow(dead_code)]
+	pub fn inventory(&self) -> &PlayerInventory {
+		&self.inventory
+	}
+
+	/// Asks the server to click a slot. Nothing changes until the server sends the slots back.
+	#[allow(dead_code)]
+	pub fn click_slot(&self, slot: InventorySlot, action: SlotAction, network: &ClientNetwork) -> Result<()> {
+		network.send(ClientPacket::Player(ClientPlayerPacket::ClickSlot { slot, action }))?;
+		Ok(())
+	}
+
+	/// Asks the server to use the item in a hotbar slot towards `target`.
+	#[allow(dead_code)]
+	pub fn use_item(&self, slot: u32, target: Vector2D<f32, WorldSpace>, network: &ClientNetwork) -> Result<()> {
+		network.send(ClientPacket::Player(ClientPlayerPacket::UseItem { slot, target }))?;
+		Ok(())
+	}
+
+	/// Asks the server to craft a recipe once, the result shows up in the inventory.
+	#[allow(dead_code)]
+	pub fn craft(&self, recipe: RawId, network: &ClientNetwork) -> Result<()> {
+		network.send(ClientPacket::Player(ClientPlayerPacket::Craft { recipe }))?;
 		Ok(())
 	}
 
@@ -193,7 +284,7 @@ impl PlayerModule {
 		// If reconciliation is on, we apply values that the server has not yet processed.
 		for (_, speed) in &self.unprocessed_commands {
 			self.prediction_system.get_mut::<HumanoidComp>(entity)?.dir = *speed;
-			self.prediction_system.tick(chunks, 1.0)?;
+			self.prediction_system.tick(chunks)?;
 		}
 
 		self.prediction_system.get_mut::<HumanoidComp>(entity)?.dir = self.input_dir;
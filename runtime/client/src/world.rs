@@ -3,23 +3,32 @@ use rayon::ThreadPool;
 use rsa_core::api::Api;
 use rsa_core::error::{Result, WrapErr};
 use rsa_network::client::ClientTickData;
+use rsa_network::server::remote::{Remote, RemoteSettings};
 use rsac_graphic::{Draw, GraphicSystem};
 use rustaria::chunk::{Chunk, ChunkSystem};
+use rustaria::entity::component::animation::AnimationComp;
+use rustaria::entity::component::custom::CustomComps;
+use rustaria::entity::component::effect::EffectsComp;
 use rustaria::entity::component::pos::PositionComp;
 use rustaria::entity::packet::ServerEntityPacket;
 use rustaria::entity::prototype::EntityPrototype;
+use rustaria::entity::status_effect::StatusEffectPrototype;
 use rustaria::entity::EntitySystem;
+use rustaria::packet::chunk::{ClientChunkPacket, ServerChunkPacket};
 use rustaria::packet::{ClientPacket, ServerPacket};
 use rustaria::world::World;
 use rustaria::{ClientNetwork, Server};
+use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
-use rsa_core::logging::trace;
+use std::time::Duration;
+use rsa_core::logging::{info, trace};
 use rsa_core::ty::{ChunkPos, Tag};
 use rsac_graphic::camera::Camera;
 use rsac_graphic::render::WorldRenderer;
 use rustaria::chunk::layer::ChunkLayer;
 use rustaria::chunk::layer::tile::TilePrototype;
-use rustaria::packet::player::ClientPlayerPacket;
+use rustaria::packet::player::{ClientPlayerPacket, ServerPlayerPacket};
+use rustaria::player::PlayerId;
 
 pub struct ClientWorld {
 	api: Api,
@@ -30,14 +39,40 @@ pub struct ClientWorld {
 	network: ClientNetwork,
 	player: PlayerModule,
 	tick: u32,
+	/// Set once the server closed the connection, the world should be dropped afterwards.
+	disconnected: Option<String>,
 
 	renderer: WorldRenderer,
 }
 
+/// The chunks the integrated server starts out with, which is all there is for now.
+const START_CHUNKS: [ChunkPos; 2] = [ChunkPos { x: 0, y: 0 }, ChunkPos { x: 0, y: 1 }];
+
 impl ClientWorld {
-	pub fn new_integrated(api: &Api, graphics: &mut GraphicSystem, thread_pool: Arc<ThreadPool>) -> Result<ClientWorld> {
+	/// Starts a server inside of this client, other clients can join it on `host` if it is set.
+	pub fn new_integrated(
+		api: &Api,
+		graphics: &mut GraphicSystem,
+		thread_pool: Arc<ThreadPool>,
+		host: Option<u16>,
+		name: String,
+		id: PlayerId,
+	) -> Result<ClientWorld> {
 		let mut server = Server::new_integrated(api, thread_pool)?;
 		server.reload(api);
+		if let Some(port) = host {
+			let remote = Remote::new(
+				RemoteSettings {
+					address: Some(Ipv4Addr::UNSPECIFIED),
+					port: Some(port),
+					timeout: Duration::from_secs(60),
+				},
+				&api.get_carrier(),
+			)
+			.wrap_err("Could not open the world to other players")?;
+			info!(target: "init@rustariac.world", "Hosting on {}", remote.local_addr()?);
+			server.network.remote = Some(remote);
+		}
 
 		let mut renderer = WorldRenderer::new(graphics)?;
 		renderer.reload(api, graphics)?;
@@ -45,30 +80,18 @@ impl ClientWorld {
 		let mut world = World::new();
 		let carrier = api.get_carrier();
 		let result = carrier.get::<TilePrototype>().create_from_tag(&Tag::rsa("dirt"))?;
-		let pos = ChunkPos {
-			x: 0,
-			y: 0,
-		};
-
-		let pos2 = ChunkPos {
-			x: 0,
-			y: 1,
-		};
-
 		let chunk = Chunk {
 			tiles: ChunkLayer::new_copy(result)
 		};
 
-
-		server.world.chunks.put_chunk(pos, chunk.clone());
-		server.world.chunks.put_chunk(pos2, chunk.clone());
-		world.chunks.put_chunk(pos, chunk.clone());
-		world.chunks.put_chunk(pos2, chunk);
-		renderer.notify_chunk(pos);
-		renderer.notify_chunk(pos2);
+		for pos in START_CHUNKS {
+			server.world.chunks.put_chunk(pos, chunk.clone());
+			world.chunks.put_chunk(pos, chunk.clone());
+			renderer.notify_chunk(pos);
+		}
 
 		let network = ClientNetwork::new_integrated(server.network.integrated.as_mut().unwrap())?;
-		network.send(ClientPacket::Player(ClientPlayerPacket::Join()))?;
+		network.send(ClientPacket::Player(ClientPlayerPacket::Join { name, id }))?;
 
 		Ok(ClientWorld {
 			api: api.clone(),
@@ -77,6 +100,37 @@ impl ClientWorld {
 			world: world,
 			player: PlayerModule::new(api),
 			tick: 0,
+			disconnected: None,
+			renderer
+		})
+	}
+
+	/// Joins a server over the network, this blocks until the server let us in or turned us away.
+	pub fn new_remote(
+		api: &Api,
+		graphics: &mut GraphicSystem,
+		addr: SocketAddr,
+		name: String,
+		id: PlayerId,
+	) -> Result<ClientWorld> {
+		let mut renderer = WorldRenderer::new(graphics)?;
+		renderer.reload(api, graphics)?;
+
+		info!(target: "init@rustariac.world", "Connecting to {addr}");
+		let network = ClientNetwork::new_remote(addr, &api.get_carrier())
+			.wrap_err_with(|| format!("Could not connect to {addr}"))?;
+		network.send(ClientPacket::Player(ClientPlayerPacket::Join { name, id }))?;
+		// Unlike the integrated server, nobody puts the chunks in our world for us.
+		network.send(ClientPacket::Chunk(ClientChunkPacket::Request(START_CHUNKS.to_vec())))?;
+
+		Ok(ClientWorld {
+			api: api.clone(),
+			network,
+			integrated_server: None,
+			world: World::new(),
+			player: PlayerModule::new(api),
+			tick: 0,
+			disconnected: None,
 			renderer
 		})
 	}
@@ -94,20 +148,26 @@ impl ClientWorld {
 
 		if let Some(integrated) = &mut self.integrated_server {
 			integrated.tick()?;
-			match self.network.tick()? {
-				ClientTickData::Received(data) => {
-					for packet in data {
-						self.packet(packet).wrap_err("Packet fail")?;
-					}
-				}
-				ClientTickData::Disconnected => {
-					todo!("Disconnecting")
+		}
+		match self.network.tick()? {
+			ClientTickData::Received(data) => {
+				for packet in data {
+					self.packet(packet).wrap_err("Packet fail")?;
 				}
 			}
+			ClientTickData::Disconnected => {
+				self.disconnected
+					.get_or_insert_with(|| "Lost connection to the server.".to_string());
+			}
 		}
 		Ok(())
 	}
 
+	/// Why the server closed the connection, `None` while still connected.
+	pub fn disconnected(&self) -> Option<&str> {
+		self.disconnected.as_deref()
+	}
+
 	pub fn draw(&mut self, draw: &mut Draw) -> Result<()> {
 		self.renderer.draw(draw, &self.world)?;
 		Ok(())
@@ -123,7 +183,12 @@ impl ClientWorld {
 
 	fn packet(&mut self, packet: ServerPacket) -> Result<()> {
 		match packet {
-			ServerPacket::Chunk(_) => {}
+			ServerPacket::Chunk(ServerChunkPacket::Provide(bundle)) => {
+				for (pos, chunk) in bundle.export()?.chunks {
+					self.world.chunks.put_chunk(pos, chunk);
+					self.renderer.notify_chunk(pos);
+				}
+			}
 			ServerPacket::Entity(packet) => match packet {
 				ServerEntityPacket::Pos(tick, entity, pos) => {
 					if let Ok(mut pos_comp) = self.world.entities.get_mut::<PositionComp>(entity) {
@@ -142,7 +207,33 @@ impl ClientWorld {
 							.prototype_from_id(id),
 					);
 				}
+				ServerEntityPacket::Kill(entity) => {
+					self.world.entities.kill(entity);
+				}
+				ServerEntityPacket::Component(entity, name, value) => {
+					if let Ok(mut comps) = self.world.entities.get_mut::<CustomComps>(entity) {
+						comps.set(&name, value);
+					}
+				}
+				ServerEntityPacket::Animation(entity, state) => {
+					if let Ok(mut animation) = self.world.entities.get_mut::<AnimationComp>(entity) {
+						animation.set(state);
+					}
+				}
+				ServerEntityPacket::Effects(entity, effects) => {
+					// Keep the stats in sync so our prediction moves the same as the server.
+					let comp = EffectsComp {
+						effects,
+						..EffectsComp::default()
+					};
+					let modifiers = comp.modifiers(&self.api.get_carrier().get::<StatusEffectPrototype>());
+					self.world.entities.add(entity, comp);
+					self.player.update_effects(&mut self.world, entity, modifiers);
+				}
 			},
+			ServerPacket::Player(ServerPlayerPacket::Disconnect { reason }) => {
+				self.disconnected = Some(reason);
+			}
 			ServerPacket::Player(packet) => {
 				self.player.packet(packet, &mut self.world)?;
 			}
@@ -1,6 +1,8 @@
 #[macro_use]
 pub mod prototype;
 
+pub mod data;
+
 #[cfg(feature = "client")]
 pub mod rendering;
 pub mod ty;
@@ -0,0 +1,65 @@
+use mlua::{Error, FromLua, Lua, ToLua, Value};
+use serde::{Deserialize, Serialize};
+
+/// A copy of a Lua value that can be stored on the Rust side and sent over the network.
+/// Functions, userdata and threads have no meaning outside of the Lua state so they are not allowed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LuaData {
+	Nil,
+	Boolean(bool),
+	Integer(i64),
+	Number(f64),
+	String(String),
+	Table(Vec<(LuaData, LuaData)>),
+}
+
+impl Default for LuaData {
+	fn default() -> Self {
+		LuaData::Nil
+	}
+}
+
+impl FromLua for LuaData {
+	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+		Ok(match value {
+			Value::Nil => LuaData::Nil,
+			Value::Boolean(value) => LuaData::Boolean(value),
+			Value::Integer(value) => LuaData::Integer(value),
+			Value::Number(value) => LuaData::Number(value),
+			Value::String(value) => LuaData::String(value.to_str()?.to_string()),
+			Value::Table(table) => {
+				let mut entries = Vec::new();
+				for pair in table.pairs::<Value, Value>() {
+					let (key, value) = pair?;
+					entries.push((LuaData::from_lua(key, lua)?, LuaData::from_lua(value, lua)?));
+				}
+				LuaData::Table(entries)
+			}
+			value => {
+				return Err(Error::RuntimeError(format!(
+					"{} can not be stored as data",
+					value.type_name()
+				)))
+			}
+		})
+	}
+}
+
+impl ToLua for LuaData {
+	fn to_lua(self, lua: &Lua) -> mlua::Result<Value> {
+		Ok(match self {
+			LuaData::Nil => Value::Nil,
+			LuaData::Boolean(value) => Value::Boolean(value),
+			LuaData::Integer(value) => Value::Integer(value),
+			LuaData::Number(value) => Value::Number(value),
+			LuaData::String(value) => Value::String(lua.create_string(&value)?),
+			LuaData::Table(entries) => {
+				let table = lua.create_table()?;
+				for (key, value) in entries {
+					table.raw_set(key, value)?;
+				}
+				Value::Table(table)
+			}
+		})
+	}
+}
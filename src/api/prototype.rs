@@ -11,6 +11,6 @@ macro_rules! pt {
 #[macro_export]
 macro_rules! prototypes {
     ($B:block) => {
-        $crate::pt!($crate::chunk::layer::tile::TilePrototype, $crate::entity::prototype::EntityPrototype => $B);
+        $crate::pt!($crate::chunk::layer::tile::TilePrototype, $crate::entity::prototype::EntityPrototype, $crate::entity::status_effect::StatusEffectPrototype => $B);
     };
 }
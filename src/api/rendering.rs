@@ -5,14 +5,82 @@ use serde::{Deserialize, Serialize};
 
 use rsa_core::ty::Tag;
 
+use crate::entity::component::animation::IDLE;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum RenderingSystem {
 	Static(Pane),
-	State(HashMap<String, Pane>),
+	/// Picks an animation using the state in the AnimationComp of the entity.
+	State(HashMap<String, Animation>),
 	// More implementations for dynlib lua rendering.
 	// Advanced(stuff)
 }
 
+impl RenderingSystem {
+	/// Gets the pane to draw for an entity which has been in `state` for `ticks` ticks.
+	/// Unknown states fall back to the idle animation.
+	pub fn pane(&self, state: &str, ticks: u32) -> Option<&Pane> {
+		match self {
+			RenderingSystem::Static(pane) => Some(pane),
+			RenderingSystem::State(states) => states
+				.get(state)
+				.or_else(|| states.get(IDLE))
+				.and_then(|animation| animation.frame(ticks))
+				.map(|frame| &frame.pane),
+		}
+	}
+}
+
+/// A sequence of frames which get shown one after another.
+/// A single pane is also accepted, which is an animation with one frame.
+/// ```lua
+/// walk = {
+///     looping = true,
+///     frames = {
+///         { duration = 6, x_offset = 0, y_offset = 0, width = 1, height = 2, sprite = "walk_0.png" },
+///         { duration = 6, x_offset = 0, y_offset = 0, width = 1, height = 2, sprite = "walk_1.png" },
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Animation {
+	pub frames: Vec<Frame>,
+	/// Starts over after the last frame, else it stays on the last frame.
+	pub looping: bool,
+}
+
+impl Animation {
+	pub fn frame(&self, ticks: u32) -> Option<&Frame> {
+		let total: u32 = self.frames.iter().map(|frame| frame.duration).sum();
+		if total == 0 {
+			return self.frames.last();
+		}
+
+		let mut ticks = if self.looping {
+			ticks % total
+		} else if ticks >= total {
+			return self.frames.last();
+		} else {
+			ticks
+		};
+
+		for frame in &self.frames {
+			if ticks < frame.duration {
+				return Some(frame);
+			}
+			ticks -= frame.duration;
+		}
+		self.frames.last()
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Frame {
+	pub pane: Pane,
+	/// How many ticks this frame gets shown.
+	pub duration: u32,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Pane {
 	pub x_offset: f32,
@@ -38,6 +106,40 @@ impl FromLua for Pane {
 	}
 }
 
+impl FromLua for Frame {
+	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = &value {
+			Ok(Frame {
+				duration: table.get::<_, Option<u32>>("duration")?.unwrap_or(1),
+				pane: Pane::from_lua(value, lua)?,
+			})
+		} else {
+			Err(Error::UserDataTypeMismatch)
+		}
+	}
+}
+
+impl FromLua for Animation {
+	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = &value {
+			if let Some(frames) = table.get::<_, Option<Vec<Frame>>>("frames")? {
+				return Ok(Animation {
+					frames,
+					looping: table.get::<_, Option<bool>>("looping")?.unwrap_or(true),
+				});
+			}
+		}
+
+		Ok(Animation {
+			frames: vec![Frame {
+				pane: Pane::from_lua(value, lua)?,
+				duration: 1,
+			}],
+			looping: true,
+		})
+	}
+}
+
 impl FromLua for RenderingSystem {
 	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
 		if let Value::Table(table) = value {
@@ -57,3 +159,56 @@ impl FromLua for RenderingSystem {
 		Err(Error::UserDataTypeMismatch)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use rsa_core::ty::Tag;
+
+	use crate::api::rendering::{Animation, Frame, Pane, RenderingSystem};
+	use crate::entity::component::animation::{IDLE, WALK};
+
+	fn frame(sprite: &'static str, duration: u32) -> Frame {
+		Frame {
+			pane: Pane {
+				x_offset: 0.0,
+				y_offset: 0.0,
+				width: 1.0,
+				height: 2.0,
+				sprite: Tag::rsa(sprite),
+			},
+			duration,
+		}
+	}
+
+	fn sprite(system: &RenderingSystem, state: &str, ticks: u32) -> Option<Tag> {
+		system.pane(state, ticks).map(|pane| pane.sprite.clone())
+	}
+
+	#[test]
+	fn frames_follow_ticks() {
+		let looping = Animation {
+			frames: vec![frame("walk_0", 2), frame("walk_1", 3)],
+			looping: true,
+		};
+		assert_eq!(looping.frame(1).unwrap().pane.sprite, Tag::rsa("walk_0"));
+		assert_eq!(looping.frame(2).unwrap().pane.sprite, Tag::rsa("walk_1"));
+		assert_eq!(looping.frame(5).unwrap().pane.sprite, Tag::rsa("walk_0"));
+
+		let once = Animation { looping: false, ..looping };
+		assert_eq!(once.frame(5).unwrap().pane.sprite, Tag::rsa("walk_1"));
+		assert_eq!(once.frame(100).unwrap().pane.sprite, Tag::rsa("walk_1"));
+	}
+
+	#[test]
+	fn unknown_states_are_idle() {
+		let mut states = HashMap::new();
+		states.insert(IDLE.to_string(), Animation { frames: vec![frame("idle", 1)], looping: true });
+		states.insert(WALK.to_string(), Animation { frames: vec![frame("walk", 1)], looping: true });
+		let system = RenderingSystem::State(states);
+
+		assert_eq!(sprite(&system, WALK, 0), Some(Tag::rsa("walk")));
+		assert_eq!(sprite(&system, "swim", 0), Some(Tag::rsa("idle")));
+	}
+}
@@ -35,6 +35,28 @@ impl Default for ConnectionType {
 	}
 }
 
+/// The shape a colliding tile has for entities.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, Serialize, frogelua::FromLua)]
+#[serde(rename_all = "snake_case")]
+#[use_default]
+pub enum TileShape {
+	Full,
+	/// The bottom half of the tile.
+	HalfBlock,
+	/// Only blocks entities from above, they can drop through it.
+	Platform,
+	/// A 45° slope which is high on the left side.
+	SlopeLeft,
+	/// A 45° slope which is high on the right side.
+	SlopeRight,
+}
+
+impl Default for TileShape {
+	fn default() -> Self {
+		TileShape::Full
+	}
+}
+
 pub trait NeighborAware {
 	fn connection_ty(&self) -> ConnectionType;
 }
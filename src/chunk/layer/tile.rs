@@ -2,13 +2,14 @@ use serde::{Deserialize, Serialize};
 use rsa_core::ty::{KernelIdentifier, Prototype, RawId, Tag};
 use std::collections::HashSet;
 use rsa_core::api::lua::FromLua;
-use crate::api::ty::{ConnectionType, NeighborAware};
+use crate::api::ty::{ConnectionType, NeighborAware, TileShape};
 
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct Tile {
 	pub id: RawId,
 	pub collision: bool,
+	pub shape: TileShape,
 	pub opaque: bool,
 }
 
@@ -18,7 +19,19 @@ pub struct TilePrototype {
 	pub sprite: Option<Tag>,
 	pub connection: ConnectionType,
 	pub collision: bool,
-	//   pub opaque: LockableValue<bool>,
+	#[serde(default)]
+	pub shape: TileShape,
+	/// The tool power needed to mine this, anything can mine it if this is not set.
+	pub hardness: Option<u32>,
+	/// The item this drops when it gets mined.
+	pub item: Option<Tag>,
+	/// Rolled instead of dropping `item` when this gets mined.
+	pub loot_table: Option<Tag>,
+	/// Blocks sky light, which is what spawn rules look at. Solid tiles block it if this is not set.
+	pub opaque: Option<bool>,
+	/// Players who place this respawn on top of it.
+	#[serde(default)]
+	pub bed: bool,
 	//  #[serde(default = "TilePrototype::default_blast_resistance")]
 	//  pub blast_resistance: BlastResistance,
 	//   #[serde(default = "TilePrototype::default_break_resistance")]
@@ -44,9 +57,9 @@ impl Prototype for TilePrototype {
 		Tile {
 			id,
 			// collision: *self.collision.default(),
-			// opaque: *self.opaque.default(),
 			collision: self.collision,
-			opaque: false,
+			shape: self.shape,
+			opaque: self.opaque.unwrap_or(self.collision),
 		}
 	}
 
@@ -60,3 +73,40 @@ impl Prototype for TilePrototype {
 		"tile"
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::logging::LevelFilter;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+
+	use crate::chunk::layer::tile::TilePrototype;
+
+	#[test]
+	fn solid_tiles_are_opaque_by_default() -> Result<()> {
+		rsa_core::initialize(LevelFilter::Off)?;
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["tile"]:insert {
+				["dirt"] = { collision = true },
+				["air"] = { collision = false },
+				["platform"] = { collision = true, opaque = false },
+				["glow"] = { collision = false, opaque = true },
+			}
+			"#,
+		);
+		reload!((TilePrototype) => api);
+		let carrier = api.get_carrier();
+		let tiles = carrier.get::<TilePrototype>();
+
+		let opaque = |tag: &'static str| tiles.create_from_tag(&Tag::new(format!("hello:{tag}")).unwrap()).unwrap().opaque;
+		assert!(opaque("dirt"));
+		assert!(!opaque("air"));
+		assert!(!opaque("platform"));
+		assert!(opaque("glow"));
+		Ok(())
+	}
+}
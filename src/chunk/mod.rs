@@ -1,9 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Index, IndexMut};
 
 use layer::ChunkLayer;
 use layer::tile::Tile;
-use rsa_core::ty::ChunkPos;
+use rsa_core::ty::{ChunkPos, TilePos};
 
 pub mod layer;
 
@@ -15,12 +15,15 @@ pub struct Chunk {
 // Kinda empty for now
 pub struct ChunkSystem {
 	chunks: HashMap<ChunkPos, Chunk>,
+	/// Chunks that got changed since the last `take_dirty`.
+	dirty: HashSet<ChunkPos>,
 }
 
 impl ChunkSystem {
 	pub fn new() -> ChunkSystem  {
 		ChunkSystem {
-			chunks: Default::default()
+			chunks: Default::default(),
+			dirty: Default::default(),
 		}
 	} 
 	
@@ -32,6 +35,22 @@ impl ChunkSystem {
 		self.chunks.get(&pos)
 	}
 
+	pub fn get_tile(&self, pos: TilePos) -> Option<&Tile> {
+		Some(&self.get_chunk(pos.chunk)?.tiles[pos.sub])
+	}
+
+	/// Changes a tile and marks its chunk as dirty. Returns the old tile, `None` if the chunk is not loaded.
+	pub fn set_tile(&mut self, pos: TilePos, tile: Tile) -> Option<Tile> {
+		let chunk = self.chunks.get_mut(&pos.chunk)?;
+		self.dirty.insert(pos.chunk);
+		Some(std::mem::replace(&mut chunk.tiles[pos.sub], tile))
+	}
+
+	/// The chunks that changed since the last time this got called.
+	pub fn take_dirty(&mut self) -> HashSet<ChunkPos> {
+		std::mem::take(&mut self.dirty)
+	}
+
 	pub fn get_chunk_mut(&mut self, pos: ChunkPos) -> Option<&mut Chunk> {
 		self.chunks.get_mut(&pos)
 	}
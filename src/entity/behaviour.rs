@@ -0,0 +1,384 @@
+use apollo::*;
+use hecs::Entity;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use mlua::{FromLua, Function, Lua, Value};
+use mlua::prelude::LuaError;
+use rsa_core::api::lua::LuaResult;
+use rsa_core::math::vec2;
+use rsa_core::ty::Tag;
+
+use crate::api::data::LuaData;
+use crate::entity::component::animation::AnimationComp;
+use crate::entity::component::custom::CustomComps;
+use crate::entity::component::effect::EffectsComp;
+use crate::entity::component::health::HealthComp;
+use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::pos::PositionComp;
+use crate::entity::component::respawn::RespawnComp;
+use crate::entity::component::stats::{StatModifiers, StatsComp};
+use crate::entity::EntitySystem;
+
+/// Lua functions that get run server-side for every entity of a prototype.
+/// Every function gets an `EntityHandle` to the entity it runs for.
+#[derive(Clone, Debug)]
+pub struct Behaviour {
+	/// Runs every tick.
+	pub tick: Option<Function>,
+	/// Runs on the first tick after the entity got spawned.
+	pub spawn: Option<Function>,
+	/// Runs when a projectile hits the entity, also gets a handle to the projectile.
+	pub hit: Option<Function>,
+	/// Runs when the entity runs out of health.
+	pub death: Option<Function>,
+}
+
+impl FromLua for Behaviour {
+	fn from_lua(lua_value: Value, _: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = lua_value {
+			Ok(Behaviour {
+				tick: table.get("tick")?,
+				spawn: table.get("spawn")?,
+				hit: table.get("hit")?,
+				death: table.get("death")?,
+			})
+		} else {
+			Err(LuaError::UserDataTypeMismatch)
+		}
+	}
+}
+
+/// What Lua gets to poke an entity with. Every access checks if the entity is still around.
+/// Handles only work while the [`EntityLease`] they came from is alive, Lua keeping one around
+/// afterwards just gets errors out of it.
+#[derive(Clone)]
+pub struct EntityHandle {
+	entity: Entity,
+	slot: Arc<Mutex<Option<EntityPtr>>>,
+}
+
+/// Lends the EntitySystem to Lua for as long as it is alive, see [`EntityLease::scope`].
+pub struct EntityLease {
+	slot: Arc<Mutex<Option<EntityPtr>>>,
+}
+
+#[derive(Copy, Clone)]
+struct EntityPtr(*mut EntitySystem);
+
+// The pointer only gets used behind the mutex, on the thread that lent the EntitySystem.
+unsafe impl Send for EntityPtr {}
+
+impl EntityLease {
+	/// Runs `func` with handles to `entities` available, they stop working once it returns.
+	pub fn scope<R>(entities: &mut EntitySystem, func: impl FnOnce(&EntityLease) -> R) -> R {
+		let lease = EntityLease {
+			slot: Arc::new(Mutex::new(Some(EntityPtr(entities)))),
+		};
+		func(&lease)
+	}
+
+	pub fn handle(&self, entity: Entity) -> EntityHandle {
+		EntityHandle {
+			entity,
+			slot: self.slot.clone(),
+		}
+	}
+}
+
+impl Drop for EntityLease {
+	fn drop(&mut self) {
+		// Also runs when unwinding, so nothing can get to the entities after the scope.
+		if let Ok(mut slot) = self.slot.lock() {
+			*slot = None;
+		}
+	}
+}
+
+/// The lent EntitySystem, nothing else can get to it while this is around.
+struct Lent<'a> {
+	slot: MutexGuard<'a, Option<EntityPtr>>,
+}
+
+impl<'a> Deref for Lent<'a> {
+	type Target = EntitySystem;
+
+	fn deref(&self) -> &EntitySystem {
+		// Only made when the slot is filled, and the lease keeps the EntitySystem borrowed until it empties it.
+		unsafe { &*self.slot.expect("Lent without an EntitySystem").0 }
+	}
+}
+
+impl<'a> DerefMut for Lent<'a> {
+	fn deref_mut(&mut self) -> &mut EntitySystem {
+		unsafe { &mut *self.slot.expect("Lent without an EntitySystem").0 }
+	}
+}
+
+impl EntityHandle {
+	fn entities(&self) -> LuaResult<Lent<'_>> {
+		// Failing to lock means a handle is already in use further up, which would alias the EntitySystem.
+		let slot = self
+			.slot
+			.try_lock()
+			.map_err(|_| LuaError::RuntimeError("Entities are already in use".to_string()))?;
+		if slot.is_none() {
+			return Err(LuaError::RuntimeError(format!(
+				"Handle to entity {:?} got used after the call it was passed to ended",
+				self.entity
+			)));
+		}
+		Ok(Lent { slot })
+	}
+}
+
+fn gone(entity: Entity) -> LuaError {
+	LuaError::RuntimeError(format!("Entity {:?} does not exist anymore.", entity))
+}
+
+fn no_respawn(entity: Entity) -> LuaError {
+	LuaError::RuntimeError(format!("Entity {:?} does not exist anymore or does not respawn.", entity))
+}
+
+#[lua_impl]
+impl EntityHandle {
+	#[lua_field]
+	pub fn get_id(&self) -> LuaResult<u32> {
+		Ok(self.entity.id())
+	}
+
+	#[lua_method]
+	pub fn position(&mut self) -> LuaResult<(f32, f32)> {
+		let entity = self.entity;
+		let entities = self.entities()?;
+		let position = entities.get::<PositionComp>(entity).map_err(|_| gone(entity))?.position;
+		Ok((position.x, position.y))
+	}
+
+	#[lua_method]
+	pub fn set_position(&mut self, x: f32, y: f32) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		if !entities.teleport(entity, vec2(x, y)) {
+			return Err(gone(entity));
+		}
+		Ok(())
+	}
+
+	#[lua_method]
+	pub fn velocity(&mut self) -> LuaResult<(f32, f32)> {
+		let entity = self.entity;
+		let entities = self.entities()?;
+		let velocity = entities.get::<PhysicsComp>(entity).map_err(|_| gone(entity))?.velocity;
+		Ok((velocity.x, velocity.y))
+	}
+
+	#[lua_method]
+	pub fn set_velocity(&mut self, x: f32, y: f32) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		entities.get_mut::<PhysicsComp>(entity).map_err(|_| gone(entity))?.velocity = vec2(x, y);
+		Ok(())
+	}
+
+	/// Returns the health and the maximum health.
+	#[lua_method]
+	pub fn health(&mut self) -> LuaResult<(f32, f32)> {
+		let entity = self.entity;
+		let entities = self.entities()?;
+		let health = entities.get::<HealthComp>(entity).map_err(|_| gone(entity))?;
+		Ok((health.health, health.maximum))
+	}
+
+	/// Gets the combined stat modifiers from status effects and equipment, all of them do nothing if it has none.
+	#[lua_method]
+	pub fn stats(&mut self) -> LuaResult<StatModifiers> {
+		let entity = self.entity;
+		let entities = self.entities()?;
+		if entities.get_entity(entity).is_none() {
+			return Err(gone(entity));
+		}
+		Ok(entities.get::<StatsComp>(entity).map_or_else(|_| StatModifiers::default(), |stats| stats.modifiers))
+	}
+
+	/// Damages the entity, the amount goes through the damage_taken and defense modifiers first.
+	#[lua_method]
+	pub fn damage(&mut self, amount: f32) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		let amount = match entities.get::<StatsComp>(entity) {
+			Ok(stats) => stats.modifiers.damage(amount),
+			Err(_) => amount,
+		};
+		entities.get_mut::<HealthComp>(entity).map_err(|_| gone(entity))?.damage(amount);
+		Ok(())
+	}
+
+	#[lua_method]
+	pub fn heal(&mut self, amount: f32) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		entities.get_mut::<HealthComp>(entity).map_err(|_| gone(entity))?.heal(amount);
+		Ok(())
+	}
+
+	/// Gets where the entity respawns, nil if it uses the world spawn.
+	#[lua_method]
+	pub fn spawn_point(&mut self) -> LuaResult<(Option<f32>, Option<f32>)> {
+		let entity = self.entity;
+		let entities = self.entities()?;
+		let spawn_point = entities.get::<RespawnComp>(entity).map_err(|_| no_respawn(entity))?.spawn_point;
+		Ok((spawn_point.map(|pos| pos.x), spawn_point.map(|pos| pos.y)))
+	}
+
+	/// Sets where the entity respawns, nil goes back to the world spawn. Only works on entities that respawn.
+	#[lua_method]
+	pub fn set_spawn_point(&mut self, x: Option<f32>, y: Option<f32>) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		entities.get_mut::<RespawnComp>(entity).map_err(|_| no_respawn(entity))?.spawn_point = x.zip(y).map(|(x, y)| vec2(x, y));
+		Ok(())
+	}
+
+	/// Gets a copy of the value of a custom component, changing it does nothing until it gets set again.
+	#[lua_method]
+	pub fn get_component(&mut self, name: String) -> LuaResult<LuaData> {
+		let entity = self.entity;
+		let entities = self.entities()?;
+		let comps = entities.get::<CustomComps>(entity).map_err(|_| gone(entity))?;
+		comps
+			.get(&name)
+			.cloned()
+			.ok_or_else(|| LuaError::RuntimeError(format!("Entity does not have a {name} component")))
+	}
+
+	#[lua_method]
+	pub fn set_component(&mut self, name: String, value: LuaData) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		let mut comps = entities.get_mut::<CustomComps>(entity).map_err(|_| gone(entity))?;
+		if comps.set(&name, value) {
+			Ok(())
+		} else {
+			Err(LuaError::RuntimeError(format!("Entity does not have a {name} component")))
+		}
+	}
+
+	/// Gets every other entity within `radius` of this one, as of the end of the last tick.
+	#[lua_method]
+	pub fn query_radius(&mut self, radius: f32) -> LuaResult<Vec<EntityHandle>> {
+		let entity = self.entity;
+		let entities = self.entities()?;
+		let center = entities.spatial().get_bounds(entity).ok_or_else(|| gone(entity))?.center().to_vector();
+
+		let found = entities.query_radius(center, radius);
+		Ok(found
+			.into_iter()
+			.filter(|other| *other != entity)
+			.map(|other| EntityHandle {
+				entity: other,
+				slot: self.slot.clone(),
+			})
+			.collect())
+	}
+
+	/// Puts a status effect on the entity, it gets applied at the end of the tick.
+	/// The duration of the effect prototype gets used if `duration` is nil.
+	#[lua_method]
+	pub fn apply_effect(&mut self, effect: Tag, duration: Option<u32>) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		if entities.get_entity(entity).is_none() {
+			return Err(gone(entity));
+		}
+
+		if entities.get::<EffectsComp>(entity).is_err() {
+			entities.add(entity, EffectsComp::default());
+		}
+		entities.get_mut::<EffectsComp>(entity).map_err(|_| gone(entity))?.pending.push((effect, duration));
+		Ok(())
+	}
+
+	/// Takes a status effect off the entity at the end of the tick.
+	#[lua_method]
+	pub fn remove_effect(&mut self, effect: Tag) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		if let Ok(mut effects) = entities.get_mut::<EffectsComp>(entity) {
+			effects.removing.push(effect);
+		}
+		Ok(())
+	}
+
+	/// Gets the animation state the entity is in.
+	#[lua_method]
+	pub fn animation(&mut self) -> LuaResult<String> {
+		let entity = self.entity;
+		let entities = self.entities()?;
+		let animation = entities.get::<AnimationComp>(entity).map_err(|_| gone(entity))?;
+		Ok(animation.state.clone())
+	}
+
+	/// Forces the entity into a custom animation state, nil goes back to the movement states.
+	#[lua_method]
+	pub fn set_animation(&mut self, state: Option<String>) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		entities.get_mut::<AnimationComp>(entity).map_err(|_| gone(entity))?.custom = state;
+		Ok(())
+	}
+
+	/// Removes the entity at the start of the next tick without it dying.
+	#[lua_method]
+	pub fn remove(&mut self) -> LuaResult<()> {
+		let entity = self.entity;
+		let mut entities = self.entities()?;
+		entities.kill_later(entity);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::logging::LevelFilter;
+	use rsa_core::math::vec2;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+
+	use crate::entity::behaviour::EntityLease;
+	use crate::entity::component::pos::PositionComp;
+	use crate::entity::EntitySystem;
+
+	#[test]
+	fn kept_handles_stop_working() -> Result<()> {
+		rsa_core::initialize(LevelFilter::Off)?;
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.hook["r:keep"]:subscribe("keep", function(entity)
+				local x, y = entity:position()
+				assert(x == 3 and y == 4)
+				kept = entity
+			end)
+			reload.hook["r:use_kept"]:subscribe("use_kept", function()
+				kept:set_position(0, 0)
+			end)
+			"#,
+		);
+		reload!(() => api);
+
+		let mut entities = EntitySystem::new();
+		let entity = entities.push((PositionComp {
+			position: vec2(3.0, 4.0),
+		},));
+		EntityLease::scope(&mut entities, |lease| {
+			api.invoke_hook(&Tag::rsa("keep"), || lease.handle(entity))
+		})?;
+
+		assert!(api.invoke_hook(&Tag::rsa("use_kept"), || ()).is_err());
+		assert_eq!(entities.get::<PositionComp>(entity)?.position, vec2(3.0, 4.0));
+		Ok(())
+	}
+}
@@ -0,0 +1,155 @@
+use mlua::{FromLua, Lua, Value};
+use mlua::prelude::LuaError;
+use serde::Deserialize;
+
+use crate::entity::component::hitbox::HitboxComp;
+use crate::entity::component::physics::PhysicsComp;
+
+/// The state entities fall back to, also drawn for states without an animation.
+pub const IDLE: &str = "idle";
+pub const WALK: &str = "walk";
+pub const JUMP: &str = "jump";
+pub const FALL: &str = "fall";
+
+/// Below this speed an entity counts as standing still.
+const MOVING_THRESHOLD: f32 = 0.001;
+
+/// The animation state of an entity, picked by the server from how it moves unless Lua set a custom state.
+/// ```lua
+/// animation = { state = "idle" }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct AnimationComp {
+	pub state: String,
+	/// Overrides the movement states while set.
+	pub custom: Option<String>,
+	/// How many ticks the entity has been in the current state.
+	pub ticks: u32,
+}
+
+impl AnimationComp {
+	/// Picks the state from the custom state or the movement, returns true if it changed.
+	pub fn select(&mut self, physics: Option<&PhysicsComp>, hitbox: Option<&HitboxComp>) -> bool {
+		let state = match (&self.custom, physics) {
+			(Some(custom), _) => custom.as_str(),
+			(None, Some(physics)) => {
+				let grounded = hitbox.map_or(true, |hitbox| hitbox.touches_ground);
+				if !grounded && physics.velocity.y > 0.0 {
+					JUMP
+				} else if !grounded {
+					FALL
+				} else if physics.velocity.x.abs() > MOVING_THRESHOLD {
+					WALK
+				} else {
+					IDLE
+				}
+			}
+			(None, None) => IDLE,
+		};
+
+		if self.state == state {
+			false
+		} else {
+			self.set(state.to_string());
+			true
+		}
+	}
+
+	/// Switches to a state and starts its animation over.
+	pub fn set(&mut self, state: String) {
+		self.state = state;
+		self.ticks = 0;
+	}
+}
+
+impl FromLua for AnimationComp {
+	fn from_lua(lua_value: Value, _: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = lua_value {
+			Ok(AnimationComp {
+				state: table.get::<_, Option<String>>("state")?.unwrap_or_else(|| IDLE.to_string()),
+				custom: None,
+				ticks: 0,
+			})
+		} else {
+			Err(LuaError::UserDataTypeMismatch)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::math::{rect, vec2};
+
+	use crate::entity::component::animation::{AnimationComp, FALL, IDLE, JUMP, WALK};
+	use crate::entity::component::hitbox::HitboxComp;
+	use crate::entity::component::physics::PhysicsComp;
+
+	fn animation() -> AnimationComp {
+		AnimationComp {
+			state: IDLE.to_string(),
+			custom: None,
+			ticks: 10,
+		}
+	}
+
+	fn physics(x: f32, y: f32) -> PhysicsComp {
+		PhysicsComp {
+			velocity: vec2(x, y),
+			acceleration: vec2(0.0, 0.0),
+		}
+	}
+
+	fn hitbox(touches_ground: bool) -> HitboxComp {
+		HitboxComp {
+			hitbox: rect(0.0, 0.0, 1.0, 2.0),
+			touches_ground,
+			drop_through: false,
+		}
+	}
+
+	#[test]
+	fn states_from_movement() {
+		let cases = [
+			(physics(0.0, 0.0), hitbox(true), IDLE),
+			(physics(1.0, 0.0), hitbox(true), WALK),
+			(physics(1.0, 2.0), hitbox(false), JUMP),
+			(physics(0.0, -2.0), hitbox(false), FALL),
+		];
+
+		for (physics, hitbox, state) in cases {
+			let mut animation = animation();
+			animation.select(Some(&physics), Some(&hitbox));
+			assert_eq!(animation.state, state);
+		}
+	}
+
+	#[test]
+	fn only_changes_restart() {
+		let mut animation = animation();
+		assert!(!animation.select(Some(&physics(0.0, 0.0)), Some(&hitbox(true))));
+		assert_eq!(animation.ticks, 10);
+
+		assert!(animation.select(Some(&physics(1.0, 0.0)), Some(&hitbox(true))));
+		assert_eq!(animation.ticks, 0);
+	}
+
+	#[test]
+	fn custom_overrides_movement() {
+		let mut animation = animation();
+		animation.custom = Some("attack".to_string());
+		assert!(animation.select(Some(&physics(1.0, 2.0)), Some(&hitbox(false))));
+		assert_eq!(animation.state, "attack");
+
+		animation.custom = None;
+		animation.select(Some(&physics(1.0, 2.0)), Some(&hitbox(false)));
+		assert_eq!(animation.state, JUMP);
+	}
+
+	#[test]
+	fn idle_without_physics() {
+		let mut animation = animation();
+		animation.state = WALK.to_string();
+		animation.select(None, None);
+		assert_eq!(animation.state, IDLE);
+	}
+}
@@ -0,0 +1,22 @@
+use mlua::{FromLua, Lua, Value};
+use mlua::prelude::LuaError;
+use serde::Deserialize;
+
+/// Entities with a collector pull in items that are in range and pick them up when they touch.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CollectorComp {
+	/// How far away in tiles items get pulled in from.
+	pub range: f32,
+}
+
+impl FromLua for CollectorComp {
+	fn from_lua(lua_value: Value, _: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = lua_value {
+			Ok(CollectorComp {
+				range: table.get::<_, Option<f32>>("range")?.unwrap_or(5.0),
+			})
+		} else {
+			Err(LuaError::UserDataTypeMismatch)
+		}
+	}
+}
@@ -0,0 +1,43 @@
+use mlua::{FromLua, Lua, Value};
+use mlua::prelude::LuaError;
+use serde::Deserialize;
+
+/// Lets an entity touch other entities.
+/// Two entities only make contact if each of their masks contain a layer of the other one.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ContactComp {
+	/// The layers this entity is on.
+	pub layers: u32,
+	/// The layers this entity wants to touch.
+	pub mask: u32,
+	/// If both entities are solid they get pushed apart.
+	pub solid: bool,
+}
+
+impl ContactComp {
+	pub fn interacts(&self, other: &ContactComp) -> bool {
+		(self.mask & other.layers) != 0 && (other.mask & self.layers) != 0
+	}
+
+	/// Like `interacts`, but entities without a ContactComp are not on any layer so nothing filters them out.
+	pub fn may_interact(this: Option<&ContactComp>, other: Option<&ContactComp>) -> bool {
+		match (this, other) {
+			(Some(this), Some(other)) => this.interacts(other),
+			_ => true,
+		}
+	}
+}
+
+impl FromLua for ContactComp {
+	fn from_lua(lua_value: Value, _: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = lua_value {
+			Ok(ContactComp {
+				layers: table.get::<_, Option<u32>>("layers")?.unwrap_or(1),
+				mask: table.get::<_, Option<u32>>("mask")?.unwrap_or(u32::MAX),
+				solid: table.get::<_, Option<bool>>("solid")?.unwrap_or(false),
+			})
+		} else {
+			Err(LuaError::UserDataTypeMismatch)
+		}
+	}
+}
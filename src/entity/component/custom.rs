@@ -0,0 +1,171 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use mlua::{FromLua, Lua, Value};
+use mlua::prelude::LuaError;
+use serde::{Deserialize, Serialize};
+
+use crate::api::data::LuaData;
+
+/// Components that plugins declare on their prototypes, keyed by their name.
+/// ```lua
+/// components = {
+///     slime = { replicate = true, default = { color = "green" } },
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CustomComps {
+	pub components: BTreeMap<String, CustomComp>,
+	/// Replicated components that changed since they were last sent to the clients.
+	#[serde(skip)]
+	pub dirty: BTreeSet<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CustomComp {
+	pub value: LuaData,
+	/// If the clients get to know about this component.
+	#[serde(default)]
+	pub replicate: bool,
+}
+
+impl CustomComps {
+	pub fn get(&self, name: &str) -> Option<&LuaData> {
+		self.components.get(name).map(|comp| &comp.value)
+	}
+
+	/// Sets the value of a declared component, returns false if the component does not exist.
+	pub fn set(&mut self, name: &str, value: LuaData) -> bool {
+		match self.components.get_mut(name) {
+			Some(comp) => {
+				comp.value = value;
+				if comp.replicate {
+					self.dirty.insert(name.to_string());
+				}
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// The value of every component, this is what gets saved.
+	pub fn values(&self) -> BTreeMap<String, LuaData> {
+		self.components
+			.iter()
+			.map(|(name, comp)| (name.clone(), comp.value.clone()))
+			.collect()
+	}
+
+	/// Puts saved values back, returns the names of the ones the prototype does not declare anymore.
+	pub fn load(&mut self, values: BTreeMap<String, LuaData>) -> Vec<String> {
+		values
+			.into_iter()
+			.filter_map(|(name, value)| if self.set(&name, value) { None } else { Some(name) })
+			.collect()
+	}
+
+	/// The replicated components with their current values, for clients that do not know the entity yet.
+	pub fn replicated(&self) -> impl Iterator<Item = (&String, &LuaData)> {
+		self.components
+			.iter()
+			.filter(|(_, comp)| comp.replicate)
+			.map(|(name, comp)| (name, &comp.value))
+	}
+}
+
+impl FromLua for CustomComps {
+	fn from_lua(lua_value: Value, lua: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = lua_value {
+			let mut components = BTreeMap::new();
+			for pair in table.pairs::<String, Value>() {
+				let (name, value) = pair?;
+				components.insert(name, CustomComp::from_lua(value, lua)?);
+			}
+
+			Ok(CustomComps {
+				components,
+				dirty: BTreeSet::new(),
+			})
+		} else {
+			Err(LuaError::UserDataTypeMismatch)
+		}
+	}
+}
+
+impl FromLua for CustomComp {
+	fn from_lua(lua_value: Value, lua: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = lua_value {
+			Ok(CustomComp {
+				value: LuaData::from_lua(table.get::<_, Value>("default")?, lua)?,
+				replicate: table.get::<_, Option<bool>>("replicate")?.unwrap_or(false),
+			})
+		} else {
+			Err(LuaError::UserDataTypeMismatch)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::{BTreeMap, BTreeSet};
+
+	use crate::api::data::LuaData;
+	use crate::entity::component::custom::{CustomComp, CustomComps};
+
+	fn comps() -> CustomComps {
+		let mut components = BTreeMap::new();
+		components.insert(
+			"color".to_string(),
+			CustomComp {
+				value: LuaData::String("green".to_string()),
+				replicate: true,
+			},
+		);
+		components.insert(
+			"phase".to_string(),
+			CustomComp {
+				value: LuaData::Integer(0),
+				replicate: false,
+			},
+		);
+		CustomComps {
+			components,
+			dirty: BTreeSet::new(),
+		}
+	}
+
+	#[test]
+	fn saved_values_come_back() {
+		let mut comps = comps();
+		comps.set("phase", LuaData::Integer(2));
+		comps.set(
+			"color",
+			LuaData::Table(vec![(LuaData::String("r".to_string()), LuaData::Number(0.5))]),
+		);
+
+		// The same way profiles store them.
+		let saved = serde_json::to_string(&comps.values()).unwrap();
+		let mut loaded = self::comps();
+		let missing = loaded.load(serde_json::from_str(&saved).unwrap());
+
+		assert!(missing.is_empty());
+		assert_eq!(loaded.values(), comps.values());
+		// The clients need to hear about the loaded value.
+		assert!(loaded.dirty.contains("color"));
+	}
+
+	#[test]
+	fn undeclared_values_get_dropped() {
+		let mut values = BTreeMap::new();
+		values.insert("removed".to_string(), LuaData::Boolean(true));
+		let mut comps = comps();
+		assert_eq!(comps.load(values), vec!["removed".to_string()]);
+		assert!(comps.get("removed").is_none());
+	}
+
+	#[test]
+	fn only_replicated_get_sent() {
+		let comps = comps();
+		let names: Vec<&String> = comps.replicated().map(|(name, _)| name).collect();
+		assert_eq!(names, vec!["color"]);
+	}
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use rsa_core::registry::Registry;
+use rsa_core::ty::{RawId, Tag};
+
+use crate::entity::component::stats::StatModifiers;
+use crate::entity::status_effect::StatusEffectPrototype;
+
+/// An effect an entity currently has.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActiveEffect {
+	pub id: RawId,
+	/// Ticks until it wears off.
+	pub remaining: u32,
+	pub stacks: u32,
+	/// Ticks since it got applied, used for the tick interval.
+	pub age: u32,
+}
+
+/// The status effects on an entity.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EffectsComp {
+	pub effects: Vec<ActiveEffect>,
+	/// Effects that got applied during this tick, these get resolved by the server.
+	#[serde(skip)]
+	pub pending: Vec<(Tag, Option<u32>)>,
+	/// Effects that got removed during this tick.
+	#[serde(skip)]
+	pub removing: Vec<Tag>,
+}
+
+impl EffectsComp {
+	pub fn get(&self, id: RawId) -> Option<&ActiveEffect> {
+		self.effects.iter().find(|effect| effect.id == id)
+	}
+
+	/// Combines the modifiers of every stack of every effect.
+	pub fn modifiers(&self, registry: &Registry<StatusEffectPrototype>) -> StatModifiers {
+		let mut modifiers = StatModifiers::default();
+		for effect in &self.effects {
+			let prototype = registry.prototype_from_id(effect.id);
+			for _ in 0..effect.stacks {
+				modifiers.combine(&prototype.modifiers);
+			}
+		}
+		modifiers
+	}
+}
@@ -0,0 +1,40 @@
+use mlua::{FromLua, Lua, Value};
+use mlua::prelude::LuaError;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HealthComp {
+	pub maximum: f32,
+	/// The entity dies when this hits 0.
+	pub health: f32,
+}
+
+impl HealthComp {
+	pub fn damage(&mut self, amount: f32) {
+		self.health = (self.health - amount).max(0.0);
+	}
+
+	/// Changes the maximum, the health gets lowered if it is above it.
+	pub fn set_maximum(&mut self, maximum: f32) {
+		self.maximum = maximum.max(1.0);
+		self.health = self.health.min(self.maximum);
+	}
+
+	pub fn heal(&mut self, amount: f32) {
+		self.health = (self.health + amount).min(self.maximum);
+	}
+}
+
+impl FromLua for HealthComp {
+	fn from_lua(lua_value: Value, _: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = lua_value {
+			let maximum: f32 = table.get("maximum")?;
+			Ok(HealthComp {
+				maximum,
+				health: table.get::<_, Option<f32>>("health")?.unwrap_or(maximum),
+			})
+		} else {
+			Err(LuaError::UserDataTypeMismatch)
+		}
+	}
+}
@@ -8,6 +8,9 @@ use rsa_core::ty::Rectangle;
 pub struct HitboxComp {
 	pub hitbox: Rect<f32, WorldSpace>,
 	pub touches_ground: bool,
+	/// Falls through platforms.
+	#[serde(default)]
+	pub drop_through: bool,
 }
 
 impl FromLua for HitboxComp {
@@ -15,6 +18,7 @@ impl FromLua for HitboxComp {
 		Ok(HitboxComp {
 			hitbox: Rectangle::from_lua(lua_value, lua)?.into(),
 			touches_ground: false,
+			drop_through: false,
 		})
 	}
 }
@@ -1,6 +1,7 @@
 use mlua::{FromLua, Lua};
 use mlua::prelude::{LuaError, LuaValue};
 use rsa_core::math::{Vector2D, WorldSpace};
+use rsa_item::stats::StatModifiers;
 
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct HumanoidSettings {
@@ -14,6 +15,18 @@ pub struct HumanoidSettings {
 	pub run_max_speed: f32,
 }
 
+impl HumanoidSettings {
+	/// These settings with the jump and run stats of `modifiers` applied.
+	pub fn modified(&self, modifiers: &StatModifiers) -> HumanoidSettings {
+		HumanoidSettings {
+			jump_speed: modifiers.jump_speed.apply(self.jump_speed),
+			run_acceleration: modifiers.run_acceleration.apply(self.run_acceleration),
+			run_max_speed: modifiers.run_speed.apply(self.run_max_speed),
+			..self.clone()
+		}
+	}
+}
+
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct HumanoidComp {
 	pub settings: HumanoidSettings,
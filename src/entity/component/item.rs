@@ -0,0 +1,9 @@
+use rsa_item::stack::ItemStack;
+
+/// An item lying around in the world, this gets added when spawning the item and not by the prototype.
+#[derive(Clone, Debug)]
+pub struct ItemComp {
+	pub stack: ItemStack,
+	/// How many ticks until a collector can pick this up.
+	pub pickup_delay: f32,
+}
@@ -1,6 +1,17 @@
+pub mod animation;
+pub mod collector;
+pub mod contact;
+pub mod custom;
+pub mod effect;
 pub mod gravity;
+pub mod health;
 pub mod hitbox;
+pub mod item;
 pub mod pos;
 pub mod physics;
 pub mod humanoid;
 pub mod prototype;
+pub mod projectile;
+pub mod respawn;
+pub mod spawned;
+pub mod stats;
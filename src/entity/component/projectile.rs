@@ -0,0 +1,43 @@
+use hecs::Entity;
+use mlua::{FromLua, Lua, Value};
+use mlua::prelude::LuaError;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProjectileComp {
+	/// The speed in tiles per second the projectile gets shot at.
+	pub speed: f32,
+	/// How many ticks the projectile lives for.
+	pub lifetime: f32,
+	/// How many entities the projectile can go through, 0 means it stops at the first one.
+	pub pierce: u32,
+	/// How much gravity pulls on the projectile, 0 flies straight. A GravityComp on a projectile is ignored.
+	pub gravity: f32,
+
+	/// Who shot the projectile, this entity never gets hit by it.
+	#[serde(skip)]
+	pub owner: Option<Entity>,
+	#[serde(skip)]
+	pub age: f32,
+	/// Entities that already got hit, so piercing projectiles only hit once.
+	#[serde(skip)]
+	pub hit: Vec<Entity>,
+}
+
+impl FromLua for ProjectileComp {
+	fn from_lua(lua_value: Value, _: &Lua) -> mlua::Result<Self> {
+		if let Value::Table(table) = lua_value {
+			Ok(ProjectileComp {
+				speed: table.get("speed")?,
+				lifetime: table.get::<_, Option<f32>>("lifetime")?.unwrap_or(600.0),
+				pierce: table.get::<_, Option<u32>>("pierce")?.unwrap_or(0),
+				gravity: table.get::<_, Option<f32>>("gravity")?.unwrap_or(0.0),
+				owner: None,
+				age: 0.0,
+				hit: Vec::new(),
+			})
+		} else {
+			Err(LuaError::UserDataTypeMismatch)
+		}
+	}
+}
@@ -0,0 +1,10 @@
+use rsa_core::math::{Vector2D, WorldSpace};
+
+/// Entities with this do not get removed when they die, they stay dead until something brings them back.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct RespawnComp {
+	pub dead: bool,
+	/// Where the entity comes back, like a bed. Falls back to the world spawn.
+	#[serde(default)]
+	pub spawn_point: Option<Vector2D<f32, WorldSpace>>,
+}
@@ -0,0 +1,8 @@
+use rsa_core::ty::RawId;
+
+/// Marks an entity that got naturally spawned, these get despawned when no player is close.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct SpawnedComp {
+	/// The spawn rule that spawned it.
+	pub rule: RawId,
+}
@@ -0,0 +1,163 @@
+use serde::Deserialize;
+
+use rsa_core::api::carrier::Carrier;
+
+use crate::entity::component::health::HealthComp;
+use crate::entity::component::prototype::PrototypeComp;
+use crate::entity::prototype::EntityPrototype;
+use crate::entity::{Entity, EntitySystem};
+
+pub use rsa_item::stats::{Modifier, StatModifiers};
+
+/// The combined modifiers currently applied to an entity, the systems read this instead of the sources.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct StatsComp {
+	pub modifiers: StatModifiers,
+	/// What the status effects add up to.
+	#[serde(default)]
+	pub effects: StatModifiers,
+	/// What the equipped items add up to.
+	#[serde(default)]
+	pub equipment: StatModifiers,
+}
+
+impl StatsComp {
+	/// Changes the stats of `entity`, giving it some first if it has none.
+	/// The maximum health follows the max_health modifier on top of what the prototype says.
+	pub fn modify(carrier: &Carrier, entities: &mut EntitySystem, entity: Entity, func: impl FnOnce(&mut StatsComp)) {
+		if entities.get::<StatsComp>(entity).is_err() {
+			entities.add(entity, StatsComp::default());
+		}
+		let max_health = match entities.get_mut::<StatsComp>(entity) {
+			Ok(mut stats) => {
+				func(&mut stats);
+				stats.modifiers.max_health
+			}
+			Err(_) => return,
+		};
+
+		let base = match entities.get::<PrototypeComp>(entity) {
+			Ok(comp) => carrier
+				.get::<EntityPrototype>()
+				.prototype_from_id(comp.0)
+				.health
+				.as_ref()
+				.map(|health| health.maximum),
+			Err(_) => None,
+		};
+		if let (Some(base), Ok(mut health)) = (base, entities.get_mut::<HealthComp>(entity)) {
+			health.set_maximum(max_health.apply(base));
+		}
+	}
+
+	pub fn set_effects(&mut self, effects: StatModifiers) {
+		self.effects = effects;
+		self.update();
+	}
+
+	pub fn set_equipment(&mut self, equipment: StatModifiers) {
+		self.equipment = equipment;
+		self.update();
+	}
+
+	fn update(&mut self) {
+		let mut modifiers = self.effects;
+		modifiers.combine(&self.equipment);
+		self.modifiers = modifiers;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::api::carrier::Carrier;
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::logging::LevelFilter;
+	use rsa_core::math::vec2;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+	use rsa_item::item::ItemPrototype;
+	use rsa_item::stack::ItemStack;
+
+	use crate::entity::component::health::HealthComp;
+	use crate::entity::component::stats::{Modifier, StatModifiers, StatsComp};
+	use crate::entity::prototype::EntityPrototype;
+	use crate::entity::{Entity, EntitySystem};
+	use crate::player::inventory::{InventorySlot, PlayerInventory, SlotAction};
+
+	fn setup() -> Result<(Carrier, EntitySystem, Entity)> {
+		rsa_core::initialize(LevelFilter::Off)?;
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["helmet"] = { max_stack = 1, equipment = "Head", modifiers = { max_health = { add = 20 } } },
+			}
+			reload.registry["entity"]:insert {
+				["player"] = { health = { maximum = 100 } },
+			}
+			"#,
+		);
+		reload!((ItemPrototype, EntityPrototype) => api);
+		let carrier = api.get_carrier();
+
+		let mut entities = EntitySystem::new();
+		let entity = {
+			let registry = carrier.get::<EntityPrototype>();
+			let id = registry.id_from_tag(&Tag::new("hello:player")?)?;
+			entities.spawn(vec2(0.0, 0.0), id, registry.prototype_from_id(id))
+		};
+		Ok((carrier, entities, entity))
+	}
+
+	fn maximum(entities: &EntitySystem, entity: Entity) -> f32 {
+		entities.get::<HealthComp>(entity).unwrap().maximum
+	}
+
+	#[test]
+	fn equipping_changes_max_health() -> Result<()> {
+		let (carrier, mut entities, entity) = setup()?;
+		let helmet = carrier
+			.get::<ItemPrototype>()
+			.create_from_tag(&Tag::new("hello:helmet")?)?;
+
+		let mut inventory = PlayerInventory::new();
+		inventory.cursor = Some(ItemStack::new(helmet, None));
+		inventory.click(&carrier, InventorySlot::Equipment(0), SlotAction::PickUp)?;
+		let modifiers = inventory.equipment_modifiers(&carrier);
+		StatsComp::modify(&carrier, &mut entities, entity, |stats| stats.set_equipment(modifiers));
+		assert_eq!(maximum(&entities, entity), 120.0);
+
+		inventory.click(&carrier, InventorySlot::Equipment(0), SlotAction::PickUp)?;
+		let modifiers = inventory.equipment_modifiers(&carrier);
+		StatsComp::modify(&carrier, &mut entities, entity, |stats| stats.set_equipment(modifiers));
+		assert_eq!(maximum(&entities, entity), 100.0);
+		Ok(())
+	}
+
+	#[test]
+	fn effects_and_equipment_combine() -> Result<()> {
+		let (carrier, mut entities, entity) = setup()?;
+		let max_health = |add, multiply| StatModifiers {
+			max_health: Modifier { add, multiply },
+			..StatModifiers::default()
+		};
+
+		StatsComp::modify(&carrier, &mut entities, entity, |stats| {
+			stats.set_effects(max_health(0.0, 2.0))
+		});
+		StatsComp::modify(&carrier, &mut entities, entity, |stats| {
+			stats.set_equipment(max_health(20.0, 1.0))
+		});
+		assert_eq!(maximum(&entities, entity), 240.0);
+		entities.get_mut::<HealthComp>(entity).unwrap().heal(1000.0);
+
+		// Losing the effect keeps the equipment, and the health follows the lower maximum.
+		StatsComp::modify(&carrier, &mut entities, entity, |stats| {
+			stats.set_effects(StatModifiers::default())
+		});
+		assert_eq!(maximum(&entities, entity), 120.0);
+		assert_eq!(entities.get::<HealthComp>(entity).unwrap().health, 120.0);
+		Ok(())
+	}
+}
@@ -0,0 +1,26 @@
+use hecs::Entity;
+use rsa_core::ty::TilePos;
+
+/// Things that happened to entities during a tick.
+/// These get collected by the `EntitySystem` and are cleared at the start of the next tick.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EntityEvent {
+	/// An entity got spawned since the last tick.
+	Spawn(Entity),
+	/// An entity ran out of health, it gets removed at the start of the next tick.
+	Death(Entity),
+	/// Two entities with a `ContactComp` started touching.
+	ContactBegin(Entity, Entity),
+	/// Two entities with a `ContactComp` stopped touching.
+	ContactEnd(Entity, Entity),
+	/// A projectile hit something. It keeps on flying if it can still pierce.
+	ProjectileHit(Entity, ProjectileTarget),
+	/// An item touched a collector and can be picked up by it.
+	ItemTouch(Entity, Entity),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProjectileTarget {
+	Tile(TilePos),
+	Entity(Entity),
+}
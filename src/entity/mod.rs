@@ -1,33 +1,61 @@
 pub mod component;
 pub mod systems;
 
+pub mod behaviour;
+pub mod event;
 pub mod packet;
 pub mod prototype;
+pub mod spatial;
+pub mod spawn_rule;
+pub mod status_effect;
 
 pub use hecs::{
 	Component, ComponentError, DynamicBundle, Entity, Query, QueryBorrow, QueryMut, Ref, RefMut,
 };
 use hecs::{EntityBuilder, EntityRef, TakenEntity};
+use rayon::ThreadPool;
 use std::collections::HashSet;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 use crate::chunk::ChunkSystem;
+use crate::entity::component::animation::AnimationComp;
+use crate::entity::component::collector::CollectorComp;
+use crate::entity::component::contact::ContactComp;
+use crate::entity::component::custom::CustomComps;
+use crate::entity::component::effect::EffectsComp;
 use crate::entity::component::gravity::GravityComp;
+use crate::entity::component::health::HealthComp;
 use crate::entity::component::hitbox::HitboxComp;
 use crate::entity::component::humanoid::HumanoidComp;
+use crate::entity::component::item::ItemComp;
 use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::projectile::ProjectileComp;
+use crate::entity::component::respawn::RespawnComp;
+use crate::entity::component::spawned::SpawnedComp;
+use crate::entity::component::stats::StatsComp;
+use mlua::UserData;
 use rsa_core::error::Result;
 use rsa_core::logging::trace;
-use rsa_core::math::{Vector2D, WorldSpace};
+use rsa_core::math::{Rect, Vector2D, WorldSpace};
 use rsa_core::ty::{Prototype, RawId};
+use rsa_item::stack::ItemStack;
 
 use crate::entity::component::pos::PositionComp;
 use crate::entity::component::prototype::PrototypeComp;
+use crate::entity::event::EntityEvent;
 use crate::entity::prototype::EntityPrototype;
+use crate::entity::spatial::SpatialIndex;
+use crate::entity::systems::animation::AnimationECSystem;
 use crate::entity::systems::collision::CollisionECSystem;
+use crate::entity::systems::contact::ContactECSystem;
 use crate::entity::systems::gravity::GravityECSystem;
+use crate::entity::systems::health::HealthECSystem;
+use crate::entity::systems::item::ItemECSystem;
 use crate::entity::systems::movement::MovementECSystem;
 use crate::entity::systems::physics::PhysicsECSystem;
+use crate::entity::systems::projectile::ProjectileECSystem;
+use crate::entity::systems::schedule::{self, Task};
 
 pub struct EntityStorage {
 	data: hecs::World,
@@ -42,6 +70,11 @@ impl EntityStorage {
 		self.data.spawn_at(entity, components);
 	}
 
+	/// Adds a component to an existing entity, does nothing if the entity does not exist.
+	pub fn add(&mut self, entity: Entity, component: impl Component) {
+		let _ = self.data.insert_one(entity, component);
+	}
+
 	pub fn get_entity(&self, entity: Entity) -> Option<EntityRef<'_>> {
 		self.data.entity(entity).ok()
 	}
@@ -57,6 +90,15 @@ impl EntityStorage {
 		self.data.get_mut(entity)
 	}
 
+	/// Like `get_mut` but through a shared reference, the borrow gets checked at runtime.
+	/// This is for systems running next to each other, which declared that they write `T`.
+	pub(crate) fn get_mut_shared<T: Component>(
+		&self,
+		entity: Entity,
+	) -> Result<RefMut<'_, T>, ComponentError> {
+		self.data.get_mut(entity)
+	}
+
 	pub fn query<Q: Query>(&self) -> QueryBorrow<'_, Q> {
 		self.data.query()
 	}
@@ -102,10 +144,64 @@ impl EntityStorage {
 			builder.add((*comp).clone());
 		}
 
+		if let Some(comp) = entity.get::<ContactComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<ProjectileComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<CustomComps>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<HealthComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<ItemComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<CollectorComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<SpawnedComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<RespawnComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<EffectsComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<StatsComp>() {
+			builder.add((*comp).clone());
+		}
+
+		if let Some(comp) = entity.get::<AnimationComp>() {
+			builder.add((*comp).clone());
+		}
+
 		Some(builder)
 	}
 }
 
+/// Every tick simulates the same amount of time, a variable delta would make every machine end up somewhere else.
+const TICK_DELTA: f32 = 1.0;
+/// Positions and velocities get rounded to 1/1024th of a tile.
+/// This is a power of two so every step is exactly representable, up to 16384 tiles away from the origin.
+const FIXED_POINT_SCALE: f32 = 1024.0;
+
+fn quantize(value: Vector2D<f32, WorldSpace>) -> Vector2D<f32, WorldSpace> {
+	(value * FIXED_POINT_SCALE).round() / FIXED_POINT_SCALE
+}
+
 pub struct EntitySystem {
 	storage: EntityStorage,
 
@@ -113,6 +209,21 @@ pub struct EntitySystem {
 	gravity_system: GravityECSystem,
 	movement_system: MovementECSystem,
 	collision_system: CollisionECSystem,
+	contact_system: ContactECSystem,
+	projectile_system: ProjectileECSystem,
+	item_system: ItemECSystem,
+	health_system: HealthECSystem,
+	animation_system: AnimationECSystem,
+
+	// The systems spread their work across this if it is set.
+	thread_pool: Option<Arc<ThreadPool>>,
+	fixed_point: bool,
+	spatial: SpatialIndex,
+	events: Vec<EntityEvent>,
+	// Spawns happen between ticks, so they get held back until the next tick sends out its events.
+	spawned: Vec<Entity>,
+	// Moved by the server instead of their own movement, the ValidationModule takes these every tick.
+	teleported: HashSet<Entity>,
 
 	// Keep out of reach of children
 	dead: HashSet<Entity>,
@@ -128,10 +239,26 @@ impl EntitySystem {
 			gravity_system: GravityECSystem::new(),
 			movement_system: Default::default(),
 			collision_system: Default::default(),
+			contact_system: Default::default(),
+			projectile_system: Default::default(),
+			item_system: Default::default(),
+			health_system: Default::default(),
+			animation_system: Default::default(),
+			thread_pool: None,
+			fixed_point: true,
+			spatial: Default::default(),
+			events: Default::default(),
+			spawned: Default::default(),
+			teleported: Default::default(),
 			dead: Default::default(),
 		}
 	}
 
+	/// Lets the systems spread their work across the pool, else everything runs on the ticking thread.
+	pub fn set_thread_pool(&mut self, thread_pool: Arc<ThreadPool>) {
+		self.thread_pool = Some(thread_pool);
+	}
+
 	pub fn spawn(
 		&mut self,
 		position: Vector2D<f32, WorldSpace>,
@@ -140,7 +267,9 @@ impl EntitySystem {
 	) -> Entity {
 		let mut builder = prototype.create(id);
 		builder.add(PositionComp { position });
-		self.storage.push(builder.build())
+		let entity = self.storage.push(builder.build());
+		self.spawned.push(entity);
+		entity
 	}
 
 	pub fn spawn_at(
@@ -153,17 +282,214 @@ impl EntitySystem {
 		let mut builder = prototype.create(id);
 		builder.add(PositionComp { position });
 		self.storage.insert(entity, builder.build());
+		self.spawned.push(entity);
+	}
+
+	/// Shoots a projectile from `position` towards `direction` at the speed of its `ProjectileComp`.
+	pub fn spawn_projectile(
+		&mut self,
+		position: Vector2D<f32, WorldSpace>,
+		direction: Vector2D<f32, WorldSpace>,
+		owner: Option<Entity>,
+		id: RawId,
+		prototype: &EntityPrototype,
+	) -> Option<Entity> {
+		// A NaN velocity turns into a NaN position, which nothing downstream can handle.
+		if !direction.x.is_finite() || !direction.y.is_finite() {
+			return None;
+		}
+		let mut projectile = prototype.projectile.clone()?;
+		projectile.owner = owner;
+
+		let mut builder = prototype.create(id);
+		builder.add(PositionComp { position });
+		builder.add(PhysicsComp {
+			velocity: direction.try_normalize()? * projectile.speed,
+			acceleration: Default::default(),
+		});
+		builder.add(projectile);
+		let entity = self.storage.push(builder.build());
+		self.spawned.push(entity);
+		Some(entity)
+	}
+
+	/// Drops `stack` into the world at `position`, collectors can only pick it up after `pickup_delay` ticks.
+	pub fn spawn_item(
+		&mut self,
+		position: Vector2D<f32, WorldSpace>,
+		stack: ItemStack,
+		pickup_delay: f32,
+		id: RawId,
+		prototype: &EntityPrototype,
+	) -> Entity {
+		let mut builder = prototype.create(id);
+		builder.add(PositionComp { position });
+		builder.add(ItemComp {
+			stack,
+			pickup_delay,
+		});
+		let entity = self.storage.push(builder.build());
+		self.spawned.push(entity);
+		entity
+	}
+
+	/// Marks an entity to be removed at the start of the next tick.
+	pub fn kill_later(&mut self, entity: Entity) {
+		self.dead.insert(entity);
 	}
 
-	pub fn tick(&mut self, chunks: &ChunkSystem, delta: f32) -> Result<()> {
-		self.gravity_system.tick(&mut self.storage, delta);
-		self.movement_system.tick(&mut self.storage, delta);
-		self.collision_system.tick(&mut self.storage, chunks, delta);
-		self.physics_system.tick(&mut self.storage, delta);
+	/// Turns the fixed-point positions on or off, this is on by default.
+	/// The server and everything predicting it need to agree on this.
+	pub fn set_fixed_point(&mut self, fixed_point: bool) {
+		self.fixed_point = fixed_point;
+	}
+
+	/// Moves the simulation forward by one tick.
+	/// The same entities with the same inputs always end up in the same state, no matter which thread pool runs it.
+	/// This is what lets the client predict its player with the exact same results as the server.
+	///
+	/// The math is still done in floats, so this only holds for the same build of the game.
+	/// Another compiler or target could fuse or reorder operations, the client and server have to match.
+	pub fn tick(&mut self, chunks: &ChunkSystem) -> Result<()> {
+		let delta = TICK_DELTA;
+
+		// Killing frees up entity ids which get reused by the next spawn, so always do it in the same order.
+		let mut dead: Vec<Entity> = self.dead.drain().collect();
+		dead.sort();
+		for entity in dead {
+			self.storage.kill(entity);
+		}
+
+		self.events.clear();
+		self.events
+			.extend(self.spawned.drain(..).map(EntityEvent::Spawn));
+		let pool = self.thread_pool.as_deref();
+		// The movement systems get scheduled the same way as the ones below, the entities inside of them get split up.
+		// They all write PhysicsComp so right now they end up running in the order they are listed.
+		let storage = &self.storage;
+		let spatial = &self.spatial;
+		let (gravity_system, movement_system, projectile_system, collision_system, physics_system) = (
+			&self.gravity_system,
+			&self.movement_system,
+			&self.projectile_system,
+			&self.collision_system,
+			&self.physics_system,
+		);
+		let (dead, events) = (&mut self.dead, &mut self.events);
+		schedule::run(
+			pool,
+			vec![
+				Task::new(GravityECSystem::access(), || gravity_system.tick(storage, pool, delta)),
+				Task::new(MovementECSystem::access(), || movement_system.tick(storage, pool, delta)),
+				Task::new(ProjectileECSystem::access(), || {
+					projectile_system.tick(storage, chunks, spatial, gravity_system, dead, events, delta)
+				}),
+				Task::new(CollisionECSystem::access(), || {
+					collision_system.tick(storage, chunks, pool, delta)
+				}),
+				Task::new(PhysicsECSystem::access(), || physics_system.tick(storage, pool, delta)),
+			],
+		);
+		if self.fixed_point {
+			self.snap_to_fixed_point();
+		}
+		self.spatial.rebuild(&self.storage);
+
+		// These get scheduled by the components they touch, the ones that do not share any run at the same time.
+		// Every system gets its own events which get put in the usual order afterwards.
+		let storage = &self.storage;
+		let spatial = &self.spatial;
+		let (contact_system, item_system, health_system, animation_system) = (
+			&mut self.contact_system,
+			&self.item_system,
+			&self.health_system,
+			&self.animation_system,
+		);
+		let dead = &mut self.dead;
+		let mut contact_events = Vec::new();
+		let mut item_events = Vec::new();
+		let mut health_events = Vec::new();
+		schedule::run(
+			pool,
+			vec![
+				Task::new(ContactECSystem::access(), || {
+					contact_system.tick(storage, spatial, &mut contact_events)
+				}),
+				Task::new(ItemECSystem::access(), || {
+					item_system.tick(storage, spatial, &mut item_events, delta)
+				}),
+				Task::new(HealthECSystem::access(), || {
+					health_system.tick(storage, dead, &mut health_events)
+				}),
+				Task::new(AnimationECSystem::access(), || animation_system.tick(storage)),
+			],
+		);
+
+		// The index needs to know where contacts pushed things, else the rest of the tick looks at old positions.
+		self.spatial.update(&self.storage, &self.contact_system.pushed());
+
+		self.events.append(&mut contact_events);
+		self.events.append(&mut item_events);
+		self.events.append(&mut health_events);
 		Ok(())
 	}
+
+	/// Snaps every position and velocity onto a fixed grid.
+	/// Tiny float differences, like from a different order of operations on another machine,
+	/// get rounded away instead of adding up over time.
+	fn snap_to_fixed_point(&mut self) {
+		for (_, (position, physics)) in self
+			.storage
+			.query_mut::<(&mut PositionComp, Option<&mut PhysicsComp>)>()
+		{
+			position.position = quantize(position.position);
+			if let Some(physics) = physics {
+				physics.velocity = quantize(physics.velocity);
+			}
+		}
+	}
+
+	/// Gets the events that happened on the last tick.
+	pub fn events(&self) -> &[EntityEvent] {
+		&self.events
+	}
+
+	pub fn spatial(&self) -> &SpatialIndex {
+		&self.spatial
+	}
+
+	/// Moves an entity somewhere else, returns false if it has no position.
+	/// Anything the server moves should go through here so players do not get reported for the jump.
+	pub fn teleport(&mut self, entity: Entity, position: Vector2D<f32, WorldSpace>) -> bool {
+		match self.storage.get_mut::<PositionComp>(entity) {
+			Ok(mut comp) => comp.position = position,
+			Err(_) => return false,
+		}
+		self.teleported.insert(entity);
+		true
+	}
+
+	/// Takes the entities which got teleported since the last call.
+	pub fn take_teleported(&mut self) -> HashSet<Entity> {
+		std::mem::take(&mut self.teleported)
+	}
+
+	/// Gets all of the entities whose hitbox touches `area`, entities without a hitbox use their position.
+	/// This is the world as it was at the end of the last tick.
+	pub fn query_area(&self, area: Rect<f32, WorldSpace>) -> Vec<Entity> {
+		self.spatial.query_area(area)
+	}
+
+	/// Gets all of the entities whose hitbox is within `radius` of `center`.
+	/// This is the world as it was at the end of the last tick.
+	pub fn query_radius(&self, center: Vector2D<f32, WorldSpace>, radius: f32) -> Vec<Entity> {
+		self.spatial.query_radius(center, radius)
+	}
 }
 
+// Lua only gets to see this through an EntityHandle.
+impl UserData for EntitySystem {}
+
 impl Deref for EntitySystem {
 	type Target = EntityStorage;
 
@@ -177,3 +503,172 @@ impl DerefMut for EntitySystem {
 		&mut self.storage
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use rayon::ThreadPoolBuilder;
+	use rsa_core::error::Result;
+	use rsa_core::math::vec2;
+
+	use crate::chunk::ChunkSystem;
+	use crate::entity::component::gravity::GravityComp;
+	use crate::entity::component::humanoid::HumanoidComp;
+	use crate::entity::component::physics::PhysicsComp;
+	use crate::entity::component::pos::PositionComp;
+	use crate::entity::systems::collision::tests::{setup, spawn_player};
+	use crate::entity::{Entity, EntitySystem, FIXED_POINT_SCALE};
+
+	fn hills() -> Result<ChunkSystem> {
+		setup(|x, y| match (x, y) {
+			(_, y) if y < 4 => "dirt",
+			(6, 4) => "slope",
+			(x, 4) if x >= 7 && x < 12 => "dirt",
+			(_, 8) if x < 4 => "platform",
+			_ => "air",
+		})
+	}
+
+	/// Walks back and forth and jumps every now and then, the same for every run.
+	fn input(tick: u32, entity: usize) -> (f32, bool) {
+		let dir = if (tick / 40 + entity as u32) % 2 == 0 { 1.0 } else { -1.0 };
+		(dir, (tick + entity as u32 * 7) % 50 < 10)
+	}
+
+	fn spawn(entities: &mut EntitySystem, amount: usize) -> Vec<Entity> {
+		(0..amount)
+			.map(|i| spawn_player(entities, 1.0 + (i % 14) as f32, 4.0 + (i / 14) as f32 * 0.25))
+			.collect()
+	}
+
+	fn run(entities: &mut EntitySystem, players: &[Entity], chunks: &ChunkSystem, from: u32, ticks: u32) -> Result<()> {
+		for tick in from..from + ticks {
+			for (i, player) in players.iter().enumerate() {
+				let (dir, jumping) = input(tick, i);
+				let mut humanoid = entities.get_mut::<HumanoidComp>(*player)?;
+				humanoid.dir = vec2(dir, 0.0);
+				humanoid.jumping = jumping;
+			}
+			entities.tick(chunks)?;
+		}
+		Ok(())
+	}
+
+	/// The exact bits, close enough is not good enough here.
+	fn state(entities: &EntitySystem, players: &[Entity]) -> Vec<[u32; 4]> {
+		players
+			.iter()
+			.map(|player| {
+				let position = entities.get::<PositionComp>(*player).unwrap().position;
+				let velocity = entities.get::<PhysicsComp>(*player).unwrap().velocity;
+				[position.x.to_bits(), position.y.to_bits(), velocity.x.to_bits(), velocity.y.to_bits()]
+			})
+			.collect()
+	}
+
+	#[test]
+	fn same_inputs_same_state() -> Result<()> {
+		let chunks = hills()?;
+		let mut first = EntitySystem::new();
+		let mut second = EntitySystem::new();
+		let first_players = spawn(&mut first, 20);
+		let second_players = spawn(&mut second, 20);
+
+		run(&mut first, &first_players, &chunks, 0, 300)?;
+		run(&mut second, &second_players, &chunks, 0, 300)?;
+		assert_eq!(state(&first, &first_players), state(&second, &second_players));
+		Ok(())
+	}
+
+	#[test]
+	fn thread_pool_matches_single_thread() -> Result<()> {
+		let chunks = hills()?;
+		let mut single = EntitySystem::new();
+		let mut pooled = EntitySystem::new();
+		pooled.set_thread_pool(Arc::new(ThreadPoolBuilder::new().num_threads(4).build().unwrap()));
+
+		// Enough to need multiple batches.
+		let single_players = spawn(&mut single, 700);
+		let pooled_players = spawn(&mut pooled, 700);
+
+		run(&mut single, &single_players, &chunks, 0, 120)?;
+		run(&mut pooled, &pooled_players, &chunks, 0, 120)?;
+		assert_eq!(state(&single, &single_players), state(&pooled, &pooled_players));
+		Ok(())
+	}
+
+	#[test]
+	fn replay_from_snapshot() -> Result<()> {
+		let chunks = hills()?;
+		let mut server = EntitySystem::new();
+		let players = spawn(&mut server, 3);
+		run(&mut server, &players, &chunks, 0, 50)?;
+
+		// This is what the client does when it starts predicting its player.
+		let mut replay = EntitySystem::new();
+		for player in &players {
+			let mut builder = server.clone(*player).unwrap();
+			replay.insert(*player, builder.build());
+		}
+
+		run(&mut server, &players, &chunks, 50, 200)?;
+		run(&mut replay, &players, &chunks, 50, 200)?;
+		assert_eq!(state(&server, &players), state(&replay, &players));
+		Ok(())
+	}
+
+	/// Falls through the air for a while, the expected values are recorded from the current build.
+	/// If these change, clients on the old build will no longer predict the same as the server.
+	#[test]
+	fn golden_fall() -> Result<()> {
+		let chunks = setup(|_, _| "air")?;
+		let mut entities = EntitySystem::new();
+		let entity = entities.push((
+			PositionComp {
+				position: vec2(2.0, 100.0),
+			},
+			PhysicsComp {
+				velocity: vec2(3.3, 7.7),
+				acceleration: vec2(0.7, 0.0),
+			},
+			GravityComp { speed: 1.0 },
+		));
+
+		let fixed_point = |entities: &EntitySystem| {
+			let position = entities.get::<PositionComp>(entity).unwrap().position * FIXED_POINT_SCALE;
+			let velocity = entities.get::<PhysicsComp>(entity).unwrap().velocity * FIXED_POINT_SCALE;
+			[position.x as i32, position.y as i32, velocity.x as i32, velocity.y as i32]
+		};
+
+		let mut tick = 0;
+		for (at, expected) in [
+			(1, [2104, 102526, 3391, 7543]),
+			(30, [3827, 103700, 3739, -2346]),
+			(60, [5786, 99883, 4099, -12576]),
+			(120, [10244, 76906, 4819, -33036]),
+		] {
+			while tick < at {
+				entities.tick(&chunks)?;
+				tick += 1;
+			}
+			assert_eq!(fixed_point(&entities), expected, "tick {at}");
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn positions_are_fixed_point() -> Result<()> {
+		let chunks = hills()?;
+		let mut entities = EntitySystem::new();
+		let players = spawn(&mut entities, 5);
+		run(&mut entities, &players, &chunks, 0, 77)?;
+
+		for player in players {
+			let position = entities.get::<PositionComp>(player)?.position * FIXED_POINT_SCALE;
+			assert_eq!(position.x, position.x.round());
+			assert_eq!(position.y, position.y.round());
+		}
+		Ok(())
+	}
+}
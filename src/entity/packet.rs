@@ -1,6 +1,8 @@
 use hecs::Entity;
 use rsa_core::math::{Vector2D, WorldSpace};
 use rsa_core::ty::RawId;
+use crate::api::data::LuaData;
+use crate::entity::component::effect::ActiveEffect;
 use crate::{ClientPacket, ServerPacket};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
@@ -14,6 +16,13 @@ pub enum ClientEntityPacket {
 pub enum ServerEntityPacket {
 	Pos(u32, Entity, Vector2D<f32, WorldSpace>),
 	Spawn(u32, Entity, RawId),
+	Kill(Entity),
+	/// A replicated custom component changed.
+	Component(Entity, String, LuaData),
+	/// The status effects of an entity changed.
+	Effects(Entity, Vec<ActiveEffect>),
+	/// An entity switched animation state.
+	Animation(Entity, String),
 }
 
 
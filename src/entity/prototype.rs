@@ -1,12 +1,20 @@
 use hecs::EntityBuilder;
 use rsa_core::ty::{Prototype, RawId, Tag};
 use std::collections::HashSet;
+#[cfg(feature = "client")]
 use crate::api::rendering::RenderingSystem;
+use crate::entity::behaviour::Behaviour;
+use crate::entity::component::animation::AnimationComp;
+use crate::entity::component::collector::CollectorComp;
+use crate::entity::component::contact::ContactComp;
+use crate::entity::component::custom::CustomComps;
 use crate::entity::component::gravity::GravityComp;
+use crate::entity::component::health::HealthComp;
 use crate::entity::component::hitbox::HitboxComp;
 use crate::entity::component::humanoid::HumanoidComp;
 use crate::entity::component::prototype::PrototypeComp;
 use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::projectile::ProjectileComp;
 
 // please when adding new shit add also to mod.rs
 #[derive(Clone, Debug, serde::Deserialize, frogelua::FromLua)]
@@ -15,6 +23,17 @@ pub struct EntityPrototype {
 	pub hitbox: Option<HitboxComp>,
 	pub gravity: Option<GravityComp>,
 	pub humanoid: Option<HumanoidComp>,
+	pub contact: Option<ContactComp>,
+	pub projectile: Option<ProjectileComp>,
+	pub collector: Option<CollectorComp>,
+	pub health: Option<HealthComp>,
+	pub components: Option<CustomComps>,
+	pub animation: Option<AnimationComp>,
+	/// What the entity drops when it dies.
+	pub loot_table: Option<Tag>,
+	/// Lua functions are not something you can deserialize.
+	#[serde(skip)]
+	pub behaviour: Option<Behaviour>,
 	#[cfg(feature = "client")]
 	pub rendering: Option<RenderingSystem>,
 }
@@ -41,6 +60,30 @@ impl Prototype for EntityPrototype {
 			builder.add(comp.clone());
 		}
 
+		if let Some(comp) = &self.contact {
+			builder.add(comp.clone());
+		}
+
+		if let Some(comp) = &self.projectile {
+			builder.add(comp.clone());
+		}
+
+		if let Some(comp) = &self.collector {
+			builder.add(comp.clone());
+		}
+
+		if let Some(comp) = &self.health {
+			builder.add(comp.clone());
+		}
+
+		if let Some(comp) = &self.components {
+			builder.add(comp.clone());
+		}
+
+		if let Some(comp) = &self.animation {
+			builder.add(comp.clone());
+		}
+
 		builder
 	}
 
@@ -52,8 +95,10 @@ impl Prototype for EntityPrototype {
 					sprites.insert(pane.sprite.clone());
 				}
 				RenderingSystem::State(states) => {
-					for pane in states.values() {
-						sprites.insert(pane.sprite.clone());
+					for animation in states.values() {
+						for frame in &animation.frames {
+							sprites.insert(frame.pane.sprite.clone());
+						}
 					}
 				}
 			}
@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+
+use hecs::Entity;
+use rsa_core::math::{rect, vec2, Rect, Size2D, Vector2D, WorldSpace};
+use rsa_core::settings::CHUNK_SIZE;
+
+use crate::entity::component::hitbox::HitboxComp;
+use crate::entity::component::pos::PositionComp;
+use crate::entity::component::respawn::RespawnComp;
+use crate::entity::EntityStorage;
+
+/// Cells are chunk sized so an entity usually lives in one or two buckets.
+const CELL_SIZE: f32 = CHUNK_SIZE as f32;
+
+type CellPos = (i64, i64);
+
+/// The SpatialIndex buckets every entity with a `PositionComp` into chunk sized cells.
+/// This lets area lookups only look at the entities near them instead of every entity in the world.
+///
+/// The index gets rebuilt at the end of every entity tick, so anything spawned or moved
+/// after that is only visible on the next tick. Entities pushed apart by contacts get moved over right away. Dead entities are left out so nothing can hit or touch them,
+/// and they can not collect anything.
+#[derive(Default)]
+pub struct SpatialIndex {
+	cells: HashMap<CellPos, Vec<Entity>>,
+	bounds: HashMap<Entity, Rect<f32, WorldSpace>>,
+}
+
+impl SpatialIndex {
+	pub(crate) fn rebuild(&mut self, storage: &EntityStorage) {
+		// Keep the allocations around, most cells will be filled again.
+		for cell in self.cells.values_mut() {
+			cell.clear();
+		}
+		self.bounds.clear();
+
+		for (entity, (position, hitbox, respawn)) in storage
+			.query::<(&PositionComp, Option<&HitboxComp>, Option<&RespawnComp>)>()
+			.iter()
+		{
+			if respawn.map_or(false, |comp| comp.dead) {
+				continue;
+			}
+			self.put(entity, bounds_of(position, hitbox));
+		}
+
+		self.cells.retain(|_, cell| !cell.is_empty());
+	}
+
+	/// Moves entities which moved since the last rebuild into their new cells.
+	pub(crate) fn update(&mut self, storage: &EntityStorage, entities: &[Entity]) {
+		for entity in entities {
+			if let Some(old) = self.bounds.remove(entity) {
+				for pos in cells_of(old) {
+					if let Some(cell) = self.cells.get_mut(&pos) {
+						cell.retain(|other| other != entity);
+					}
+				}
+			}
+
+			let dead = storage.get::<RespawnComp>(*entity).map_or(false, |comp| comp.dead);
+			if let (Ok(position), false) = (storage.get::<PositionComp>(*entity), dead) {
+				let hitbox = storage.get::<HitboxComp>(*entity).ok();
+				self.put(*entity, bounds_of(&position, hitbox.as_deref()));
+			}
+		}
+
+		self.cells.retain(|_, cell| !cell.is_empty());
+	}
+
+	fn put(&mut self, entity: Entity, bounds: Rect<f32, WorldSpace>) {
+		for pos in cells_of(bounds) {
+			self.cells.entry(pos).or_insert_with(Vec::new).push(entity);
+		}
+		self.bounds.insert(entity, bounds);
+	}
+
+	/// Gets the WorldSpace bounds of an entity from the last rebuild.
+	pub fn get_bounds(&self, entity: Entity) -> Option<Rect<f32, WorldSpace>> {
+		self.bounds.get(&entity).copied()
+	}
+
+	/// Gets every entity whose bounds touch `area`.
+	pub fn query_area(&self, area: Rect<f32, WorldSpace>) -> Vec<Entity> {
+		self.query(area, |bounds| touches(bounds, area))
+	}
+
+	/// Gets every entity whose bounds are within `radius` of `center`.
+	pub fn query_radius(&self, center: Vector2D<f32, WorldSpace>, radius: f32) -> Vec<Entity> {
+		let area = rect(
+			center.x - radius,
+			center.y - radius,
+			radius * 2.0,
+			radius * 2.0,
+		);
+
+		self.query(area, |bounds| {
+			// Closest point of the bounds to the center.
+			let closest = vec2(
+				center.x.clamp(bounds.min_x(), bounds.max_x()),
+				center.y.clamp(bounds.min_y(), bounds.max_y()),
+			);
+			(closest - center).square_length() <= radius * radius
+		})
+	}
+
+	fn query(
+		&self,
+		area: Rect<f32, WorldSpace>,
+		mut filter: impl FnMut(Rect<f32, WorldSpace>) -> bool,
+	) -> Vec<Entity> {
+		let (min, max) = cell_range(area);
+		let width = max.0.saturating_sub(min.0).saturating_add(1);
+		let height = max.1.saturating_sub(min.1).saturating_add(1);
+		let cells: Vec<&Vec<Entity>> = if width.saturating_mul(height) > self.cells.len() as i64 {
+			// The area covers more cells than there are filled ones, so just go through the filled ones.
+			self.cells
+				.iter()
+				.filter(|((x, y), _)| (min.0..=max.0).contains(x) && (min.1..=max.1).contains(y))
+				.map(|(_, cell)| cell)
+				.collect()
+		} else {
+			cells_of(area).filter_map(|pos| self.cells.get(&pos)).collect()
+		};
+
+		let mut seen = HashSet::new();
+		let mut out = Vec::new();
+		for cell in cells {
+			for entity in cell {
+				// Big entities live in multiple cells.
+				if seen.insert(*entity) && filter(self.bounds[entity]) {
+					out.push(*entity);
+				}
+			}
+		}
+		// The cells come out of a HashMap, sort them so every machine sees the same order.
+		out.sort();
+		out
+	}
+}
+
+fn bounds_of(position: &PositionComp, hitbox: Option<&HitboxComp>) -> Rect<f32, WorldSpace> {
+	match hitbox {
+		Some(hitbox) => hitbox.hitbox.translate(position.position),
+		// Entities without a hitbox are just a point.
+		None => Rect::new(position.position.to_point(), Size2D::zero()),
+	}
+}
+
+fn cell_range(bounds: Rect<f32, WorldSpace>) -> (CellPos, CellPos) {
+	(
+		(
+			(bounds.min_x() / CELL_SIZE).floor() as i64,
+			(bounds.min_y() / CELL_SIZE).floor() as i64,
+		),
+		(
+			(bounds.max_x() / CELL_SIZE).floor() as i64,
+			(bounds.max_y() / CELL_SIZE).floor() as i64,
+		),
+	)
+}
+
+fn cells_of(bounds: Rect<f32, WorldSpace>) -> impl Iterator<Item = CellPos> {
+	let ((x1, y1), (x2, y2)) = cell_range(bounds);
+	(x1..=x2).flat_map(move |x| (y1..=y2).map(move |y| (x, y)))
+}
+
+// Unlike aabb::rect_vs_rect this counts touching edges, else point entities would never match.
+#[inline(always)]
+fn touches(r1: Rect<f32, WorldSpace>, r2: Rect<f32, WorldSpace>) -> bool {
+	r1.min_x() <= r2.max_x()
+		&& r1.max_x() >= r2.min_x()
+		&& r1.min_y() <= r2.max_y()
+		&& r1.max_y() >= r2.min_y()
+}
+
+#[cfg(test)]
+mod tests {
+	use hecs::Entity;
+	use rsa_core::error::Result;
+	use rsa_core::math::{rect, vec2};
+
+	use crate::chunk::ChunkSystem;
+	use crate::entity::component::contact::ContactComp;
+	use crate::entity::component::hitbox::HitboxComp;
+	use crate::entity::component::pos::PositionComp;
+	use crate::entity::component::respawn::RespawnComp;
+	use crate::entity::spatial::SpatialIndex;
+	use crate::entity::EntitySystem;
+
+	fn boxed(entities: &mut EntitySystem, x: f32, y: f32, size: f32) -> Entity {
+		entities.push((
+			PositionComp { position: vec2(x, y) },
+			HitboxComp {
+				hitbox: rect(0.0, 0.0, size, size),
+				touches_ground: false,
+				drop_through: false,
+			},
+		))
+	}
+
+	#[test]
+	fn area_counts_touching_edges() {
+		let mut entities = EntitySystem::new();
+		let inside = boxed(&mut entities, 1.0, 1.0, 1.0);
+		let edge = entities.push((PositionComp { position: vec2(4.0, 2.0) },));
+		boxed(&mut entities, 6.0, 1.0, 1.0);
+
+		let mut spatial = SpatialIndex::default();
+		spatial.rebuild(&entities);
+		assert_eq!(spatial.query_area(rect(0.0, 0.0, 4.0, 4.0)), vec![inside, edge]);
+	}
+
+	#[test]
+	fn radius_is_round() {
+		let mut entities = EntitySystem::new();
+		let side = boxed(&mut entities, 2.0, -0.5, 1.0);
+		// Inside of the square around the radius, but not the circle.
+		boxed(&mut entities, 1.5, 1.5, 1.0);
+
+		let mut spatial = SpatialIndex::default();
+		spatial.rebuild(&entities);
+		assert_eq!(spatial.query_radius(vec2(0.0, 0.0), 2.0), vec![side]);
+	}
+
+	#[test]
+	fn big_entities_come_once() {
+		let mut entities = EntitySystem::new();
+		// Spans four cells.
+		let big = boxed(&mut entities, 10.0, 10.0, 20.0);
+
+		let mut spatial = SpatialIndex::default();
+		spatial.rebuild(&entities);
+		assert_eq!(spatial.query_area(rect(0.0, 0.0, 64.0, 64.0)), vec![big]);
+	}
+
+	#[test]
+	fn huge_queries_finish() {
+		let mut entities = EntitySystem::new();
+		let near = boxed(&mut entities, 1.0, 1.0, 1.0);
+		let far = boxed(&mut entities, -100000.0, 50000.0, 1.0);
+
+		let mut spatial = SpatialIndex::default();
+		spatial.rebuild(&entities);
+		assert_eq!(spatial.query_radius(vec2(0.0, 0.0), f32::MAX), vec![near, far]);
+		assert_eq!(
+			spatial.query_area(rect(f32::MIN, f32::MIN, f32::INFINITY, f32::INFINITY)),
+			vec![near, far]
+		);
+		assert!(spatial.query_radius(vec2(0.0, 0.0), f32::NAN).is_empty());
+	}
+
+	#[test]
+	fn updates_move_entities() {
+		let mut entities = EntitySystem::new();
+		let entity = boxed(&mut entities, 1.0, 1.0, 1.0);
+		let mut spatial = SpatialIndex::default();
+		spatial.rebuild(&entities);
+
+		entities.get_mut::<PositionComp>(entity).unwrap().position = vec2(40.0, 40.0);
+		spatial.update(&entities, &[entity]);
+		assert!(spatial.query_radius(vec2(1.0, 1.0), 2.0).is_empty());
+		assert_eq!(spatial.query_radius(vec2(40.0, 40.0), 2.0), vec![entity]);
+		assert_eq!(spatial.get_bounds(entity), Some(rect(40.0, 40.0, 1.0, 1.0)));
+	}
+
+	#[test]
+	fn pushed_entities_get_updated() -> Result<()> {
+		let chunks = ChunkSystem::new();
+		let mut entities = EntitySystem::new();
+		let solid = ContactComp {
+			layers: 1,
+			mask: 1,
+			solid: true,
+		};
+		let left = boxed(&mut entities, 1.0, 1.0, 1.0);
+		let right = boxed(&mut entities, 1.5, 1.0, 1.0);
+		entities.add(left, solid.clone());
+		entities.add(right, solid);
+		entities.set_fixed_point(false);
+		entities.tick(&chunks)?;
+
+		for entity in [left, right] {
+			let position = entities.get::<PositionComp>(entity)?.position;
+			assert_eq!(
+				entities.spatial().get_bounds(entity),
+				Some(rect(position.x, position.y, 1.0, 1.0))
+			);
+		}
+		assert_eq!(entities.spatial().get_bounds(left).map(|bounds| bounds.min_x()), Some(0.75));
+		Ok(())
+	}
+
+	#[test]
+	fn dead_entities_are_left_out() {
+		let mut entities = EntitySystem::new();
+		let alive = entities.push((PositionComp { position: vec2(1.0, 1.0) },));
+		let dead = entities.push((
+			PositionComp { position: vec2(1.5, 1.0) },
+			RespawnComp { dead: true, spawn_point: None },
+		));
+
+		let mut spatial = SpatialIndex::default();
+		spatial.rebuild(&entities);
+		assert_eq!(spatial.query_radius(vec2(1.0, 1.0), 2.0), vec![alive]);
+		assert_eq!(spatial.get_bounds(dead), None);
+	}
+}
@@ -0,0 +1,182 @@
+use serde::Deserialize;
+
+use rsa_core::ty::{Prototype, RawId, Tag};
+
+/// Describes where and when an entity naturally spawns around players.
+/// ```lua
+/// reload.registry.spawn_rule:insert {
+///     ["bunne"] = {
+///         entity = "bunne",
+///         weight = 10,
+///         zones = { "Surface" },
+///         biomes = { "forest" },
+///         light = { min = 0.5 },
+///         time = "Day",
+///         max_per_player = 4,
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize, frogelua::FromLua)]
+pub struct SpawnRulePrototype {
+	/// The entity prototype that gets spawned.
+	pub entity: Tag,
+	/// How likely this is to get picked over the other rules that fit a spot.
+	pub weight: u32,
+	/// Where it can spawn, every zone is allowed if this is not set.
+	pub zones: Option<Vec<SpawnZone>>,
+	/// Which biomes it can spawn in, spots in any biome or in none are allowed if this is not set.
+	pub biomes: Option<Vec<Tag>>,
+	/// How many solid tiles can be above the spot.
+	pub depth: SpawnRange,
+	/// How much sky light the spot needs to have, from 0 to 1.
+	pub light: SpawnRange,
+	pub time: SpawnTime,
+	/// How many entities of this rule can be around a single player.
+	pub max_per_player: Option<u32>,
+}
+
+impl Prototype for SpawnRulePrototype {
+	type Item = SpawnRulePrototype;
+
+	fn create(&self, _: RawId) -> Self::Item {
+		self.clone()
+	}
+
+	fn lua_registry_name() -> &'static str {
+		"spawn_rule"
+	}
+}
+
+impl SpawnRulePrototype {
+	pub fn allows(&self, spot: &SpawnSpot, day: bool) -> bool {
+		let zone_fits = match &self.zones {
+			Some(zones) => zones.contains(&spot.zone),
+			None => true,
+		};
+		let biome_fits = match (&self.biomes, &spot.biome) {
+			(Some(biomes), Some(biome)) => biomes.contains(biome),
+			(Some(_), None) => false,
+			(None, _) => true,
+		};
+		let time_fits = match self.time {
+			SpawnTime::Any => true,
+			SpawnTime::Day => day,
+			SpawnTime::Night => !day,
+		};
+
+		zone_fits
+			&& biome_fits
+			&& time_fits
+			&& self.depth.contains(spot.depth as f32)
+			&& self.light.contains(spot.light)
+	}
+}
+
+/// A floor tile which something could spawn on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnSpot {
+	pub zone: SpawnZone,
+	pub biome: Option<Tag>,
+	pub depth: u32,
+	pub light: f32,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, frogelua::FromLua)]
+pub enum SpawnZone {
+	/// Nothing solid above.
+	Surface,
+	Underground,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, frogelua::FromLua)]
+#[use_default]
+pub enum SpawnTime {
+	Any,
+	Day,
+	Night,
+}
+
+impl Default for SpawnTime {
+	fn default() -> Self {
+		SpawnTime::Any
+	}
+}
+
+/// An inclusive range where both ends are optional.
+#[derive(Copy, Clone, PartialEq, Debug, Default, Deserialize, frogelua::FromLua)]
+#[use_default]
+pub struct SpawnRange {
+	pub min: Option<f32>,
+	pub max: Option<f32>,
+}
+
+impl SpawnRange {
+	pub fn contains(&self, value: f32) -> bool {
+		self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::ty::Tag;
+
+	use crate::entity::spawn_rule::{SpawnRange, SpawnRulePrototype, SpawnSpot, SpawnTime, SpawnZone};
+
+	fn rule() -> SpawnRulePrototype {
+		SpawnRulePrototype {
+			entity: Tag::rsa("bunne"),
+			weight: 1,
+			zones: None,
+			biomes: None,
+			depth: SpawnRange::default(),
+			light: SpawnRange::default(),
+			time: SpawnTime::Any,
+			max_per_player: None,
+		}
+	}
+
+	fn spot(biome: Option<&'static str>) -> SpawnSpot {
+		SpawnSpot {
+			zone: SpawnZone::Surface,
+			biome: biome.map(Tag::rsa),
+			depth: 0,
+			light: 1.0,
+		}
+	}
+
+	#[test]
+	fn biomes() {
+		let rule = SpawnRulePrototype {
+			biomes: Some(vec![Tag::rsa("forest")]),
+			..rule()
+		};
+		assert!(rule.allows(&spot(Some("forest")), true));
+		assert!(!rule.allows(&spot(Some("caves")), true));
+		assert!(!rule.allows(&spot(None), true));
+		assert!(self::rule().allows(&spot(None), true));
+	}
+
+	#[test]
+	fn zone_time_and_light() {
+		let rule = SpawnRulePrototype {
+			zones: Some(vec![SpawnZone::Underground]),
+			..rule()
+		};
+		assert!(!rule.allows(&spot(None), true));
+
+		let rule = SpawnRulePrototype {
+			time: SpawnTime::Night,
+			..self::rule()
+		};
+		assert!(!rule.allows(&spot(None), true));
+		assert!(rule.allows(&spot(None), false));
+
+		let rule = SpawnRulePrototype {
+			light: SpawnRange { min: None, max: Some(0.5) },
+			..self::rule()
+		};
+		let dark = SpawnSpot { light: 0.2, ..spot(None) };
+		assert!(!rule.allows(&spot(None), true));
+		assert!(rule.allows(&dark, true));
+	}
+}
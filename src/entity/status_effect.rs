@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use mlua::Function;
+use serde::Deserialize;
+
+use rsa_core::ty::{Prototype, RawId, Tag};
+
+use crate::entity::component::stats::StatModifiers;
+
+/// A buff or debuff that can be put on entities for a while.
+/// ```lua
+/// reload.registry.status_effect:insert {
+///     ["slowness"] = {
+///         duration = 300,
+///         stacking = "Stack",
+///         max_stacks = 3,
+///         modifiers = { run_speed = { multiply = 0.7 } },
+///         sprite = "slowness.png",
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize, frogelua::FromLua)]
+pub struct StatusEffectPrototype {
+	/// How many ticks the effect lasts.
+	pub duration: u32,
+	pub stacking: EffectStacking,
+	/// Only used with `Stack`, there is no limit if this is not set.
+	pub max_stacks: Option<u32>,
+	/// How many ticks there are between every `on_tick` call.
+	pub tick_interval: Option<u32>,
+	/// Get applied once for every stack.
+	pub modifiers: StatModifiers,
+	/// The icon clients show for the effect.
+	pub sprite: Option<Tag>,
+	/// Lua functions are not something you can deserialize.
+	/// These all get an `EntityHandle` to the entity which has the effect.
+	#[serde(skip)]
+	pub on_apply: Option<Function>,
+	#[serde(skip)]
+	pub on_tick: Option<Function>,
+	#[serde(skip)]
+	pub on_remove: Option<Function>,
+}
+
+impl Prototype for StatusEffectPrototype {
+	type Item = StatusEffectPrototype;
+
+	fn create(&self, _: RawId) -> Self::Item {
+		self.clone()
+	}
+
+	fn get_sprites(&self, sprites: &mut HashSet<Tag>) {
+		if let Some(sprite) = &self.sprite {
+			sprites.insert(sprite.clone());
+		}
+	}
+
+	fn lua_registry_name() -> &'static str {
+		"status_effect"
+	}
+}
+
+/// What happens when an effect gets applied to an entity which already has it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, frogelua::FromLua)]
+#[use_default]
+pub enum EffectStacking {
+	/// Resets the time left to the full duration.
+	Refresh,
+	/// Adds the duration to the time left.
+	Extend,
+	/// Adds another stack and refreshes the time left.
+	Stack,
+	/// Keeps the effect as it is.
+	Ignore,
+}
+
+impl Default for EffectStacking {
+	fn default() -> Self {
+		EffectStacking::Refresh
+	}
+}
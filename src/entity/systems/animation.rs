@@ -0,0 +1,19 @@
+use crate::entity::component::animation::AnimationComp;
+use crate::entity::systems::schedule::Access;
+use crate::entity::EntityStorage;
+
+/// Moves the animations along, picking the states is done by the server.
+#[derive(Default)]
+pub(crate) struct AnimationECSystem;
+
+impl AnimationECSystem {
+	pub(crate) fn access() -> Access {
+		Access::default().write::<AnimationComp>()
+	}
+
+	pub(crate) fn tick(&self, storage: &EntityStorage) {
+		for (_, animation) in storage.query::<&mut AnimationComp>().iter() {
+			animation.ticks = animation.ticks.saturating_add(1);
+		}
+	}
+}
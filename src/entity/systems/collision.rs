@@ -1,38 +1,80 @@
 use std::ops::Index;
 
-use hecs::World;
-use rsa_core::logging::trace;
-
+use rayon::ThreadPool;
 use rsa_core::math::{Rect, Size2D, vec2, Vector2D, WorldSpace};
+use rsa_core::settings::UPS;
 use rsa_core::ty::TilePos;
+use crate::api::ty::TileShape;
 use crate::chunk::ChunkSystem;
+use crate::chunk::layer::tile::Tile;
 
 use crate::entity::component::hitbox::HitboxComp;
 use crate::entity::component::physics::PhysicsComp;
 use crate::entity::component::pos::PositionComp;
+use crate::entity::component::projectile::ProjectileComp;
+use crate::entity::systems::schedule::{run_batched, Access, BATCH_SIZE};
 use crate::entity::EntityStorage;
 use crate::util::aabb;
 
+/// How high a grounded entity can walk up without jumping, this is what lets you walk up slopes and half blocks.
+const STEP_HEIGHT: f32 = 0.5;
+/// How far into a platform you can be while still counting as on top of it.
+const PLATFORM_MARGIN: f32 = 0.05;
+
 #[derive(Default)]
 pub(crate) struct CollisionECSystem;
 
 impl CollisionECSystem {
-	pub(crate) fn tick(&self, storage: &mut EntityStorage, chunks: &ChunkSystem, delta: f32) {
-		for (_, (position, physics, hitbox)) in storage.query_mut::<(&PositionComp, &mut PhysicsComp, &mut HitboxComp)>() {
-			self.tick_entity(position, physics, hitbox, chunks, delta);
-		}
+	pub(crate) fn access() -> Access {
+		Access::default()
+			.read::<ProjectileComp>()
+			.write::<PositionComp>()
+			.write::<PhysicsComp>()
+			.write::<HitboxComp>()
+	}
+
+	/// Every entity only looks at the tiles around itself, so the entities get spread across the pool.
+	pub(crate) fn tick(&self, storage: &EntityStorage, chunks: &ChunkSystem, pool: Option<&ThreadPool>, delta: f32) {
+		let mut query = storage.query::<(&mut PositionComp, &mut PhysicsComp, &mut HitboxComp, Option<&ProjectileComp>)>();
+		run_batched(pool, query.iter_batched(BATCH_SIZE).collect(), |batch| {
+			for (_, (position, physics, hitbox, projectile)) in batch {
+				// Projectiles do their own sweeping.
+				if projectile.is_none() {
+					self.tick_entity(position, physics, hitbox, chunks, delta);
+				}
+			}
+		});
 	}
 
 	#[inline(always)]
-	pub(crate) fn tick_entity(&self, position: &PositionComp, physics: &mut PhysicsComp, hitbox: &mut HitboxComp, chunks: &ChunkSystem, delta: f32) {
+	pub(crate) fn tick_entity(&self, position: &mut PositionComp, physics: &mut PhysicsComp, hitbox: &mut HitboxComp, chunks: &ChunkSystem, delta: f32) {
+		// Velocity is in tiles per second, the sweep has to cover what the physics system moves this tick.
+		let time_step = delta / UPS as f32;
+		let was_grounded = hitbox.touches_ground;
 		hitbox.touches_ground = false;
 
 		// hitbox is the hitbox so we need to offset it to WorldSpace.
 		let mut old_rect = hitbox.hitbox;
-		old_rect.origin += position.position * delta;
+		old_rect.origin += position.position;
+
+		// Walk up slopes and small steps, this needs to happen before the sweep or the step blocks us.
+		if was_grounded && physics.velocity.x != 0.0 {
+			let mut moved_rect = old_rect;
+			moved_rect.origin.x += physics.velocity.x * time_step;
+			let feet = old_rect.min_y();
+			if let Some(ground) = ground_height(moved_rect, feet - STEP_HEIGHT, feet + STEP_HEIGHT, feet, hitbox.drop_through, chunks) {
+				let lift = ground - feet;
+				moved_rect.origin.y += lift;
+				if lift > 0.0 && !is_blocked(moved_rect, chunks) {
+					position.position.y += lift;
+					old_rect.origin.y += lift;
+					physics.velocity.y = physics.velocity.y.max(0.0);
+				}
+			}
+		}
 
 		let mut new_rect = old_rect;
-		new_rect.origin += physics.velocity * delta;
+		new_rect.origin += physics.velocity * time_step;
 
 		let x1 = new_rect.min_x().min(old_rect.min_x()).floor() as i64;
 		let y1 = new_rect.min_y().min(old_rect.min_y()).floor() as i64;
@@ -43,7 +85,7 @@ impl CollisionECSystem {
 		for x in x1..=x2 {
 			for y in y1..=y2 {
 				if let Some((pos, contact_time)) =
-				test_tile(vec2(x as f32, y as f32), physics.velocity * delta, old_rect, chunks)
+				test_tile(vec2(x as f32, y as f32), physics.velocity * time_step, old_rect, chunks, !hitbox.drop_through)
 				{
 					collisions.push((pos, contact_time));
 				}
@@ -54,7 +96,7 @@ impl CollisionECSystem {
 
 		for (pos, _) in collisions {
 			if let Some((d, contact)) =
-			aabb::resolve_dynamic_rect_vs_rect(physics.velocity, old_rect, delta, pos)
+			aabb::resolve_dynamic_rect_vs_rect(physics.velocity, old_rect, time_step, pos)
 			{
 				physics.velocity += d;
 				physics.acceleration += contact.component_mul(vec2(
@@ -67,24 +109,326 @@ impl CollisionECSystem {
 				}
 			}
 		}
+
+		// Slopes are not boxes so the sweep ignores them, land on them here.
+		// This also keeps grounded entities glued to the floor when walking down slopes.
+		if physics.velocity.y <= 0.0 {
+			let mut new_rect = old_rect;
+			new_rect.origin += physics.velocity * time_step;
+			let feet = new_rect.min_y();
+			// Only snap down if we were on the ground, else we would stick to anything we fall past.
+			let low = if was_grounded { feet - STEP_HEIGHT } else { feet };
+			let high = old_rect.min_y() + STEP_HEIGHT;
+			if let Some(ground) = ground_height(new_rect, low, high, old_rect.min_y(), hitbox.drop_through, chunks) {
+				position.position.y += ground - feet;
+				physics.velocity.y = 0.0;
+				hitbox.touches_ground = true;
+			}
+		}
 	}
 }
 
-fn test_tile(
+fn get_tile(pos: Vector2D<f32, WorldSpace>, chunks: &ChunkSystem) -> Option<&Tile> {
+	let tile_pos = TilePos::try_from(pos).ok()?;
+	let chunk = chunks.get_chunk(tile_pos.chunk)?;
+	Some(chunk.tiles.index(tile_pos.sub))
+}
+
+/// Sweeps `collision_area` along `vel` against the tile at `pos`.
+/// Slopes are never hit here and platforms only if `platforms` is set and the area lands on them from above.
+pub(crate) fn test_tile(
 	pos: Vector2D<f32, WorldSpace>,
 	vel: Vector2D<f32, WorldSpace>,
 	collision_area: Rect<f32, WorldSpace>,
 	chunks: &ChunkSystem,
+	platforms: bool,
 ) -> Option<(Rect<f32, WorldSpace>, f32)> {
-	const TILE_SIZE: Size2D<f32, WorldSpace> = Size2D::new(1.0, 1.0);
-
-	let tile_pos = TilePos::try_from(pos).ok()?;
-	let chunk = chunks.get_chunk(tile_pos.chunk)?;
-	if !chunk.tiles.index(tile_pos.sub).collision {
+	let tile = get_tile(pos, chunks)?;
+	if !tile.collision {
 		// dont move.
 		return None;
 	}
-	let tile = Rect::new(pos.to_point(), TILE_SIZE);
+
+	let tile = match tile.shape {
+		TileShape::Full => Rect::new(pos.to_point(), Size2D::new(1.0, 1.0)),
+		TileShape::HalfBlock => Rect::new(pos.to_point(), Size2D::new(1.0, 0.5)),
+		TileShape::Platform => {
+			if !platforms || vel.y >= 0.0 || collision_area.min_y() < pos.y + 1.0 - PLATFORM_MARGIN {
+				return None;
+			}
+			Rect::new(pos.to_point(), Size2D::new(1.0, 1.0))
+		}
+		TileShape::SlopeLeft | TileShape::SlopeRight => return None,
+	};
 	aabb::dynamic_rect_vs_rect(vel, collision_area, 1.0, tile)
 		.map(|collision| (tile, collision.contact_time))
 }
+
+/// Gets the highest floor under `area` with its surface between `low` and `high`.
+/// Platforms only count if `feet` is on top of them.
+fn ground_height(
+	area: Rect<f32, WorldSpace>,
+	low: f32,
+	high: f32,
+	feet: f32,
+	drop_through: bool,
+	chunks: &ChunkSystem,
+) -> Option<f32> {
+	let x1 = area.min_x().floor() as i64;
+	let x2 = area.max_x().ceil() as i64 - 1;
+	// A surface sits on the top of the tile below it.
+	let y1 = low.floor() as i64 - 1;
+	let y2 = high.floor() as i64;
+
+	let mut ground: Option<f32> = None;
+	for x in x1..=x2 {
+		for y in y1..=y2 {
+			let tile = match get_tile(vec2(x as f32, y as f32), chunks) {
+				Some(tile) if tile.collision => tile,
+				_ => continue,
+			};
+
+			let (x, y) = (x as f32, y as f32);
+			let surface = match tile.shape {
+				TileShape::Full => y + 1.0,
+				TileShape::HalfBlock => y + 0.5,
+				TileShape::Platform => {
+					if drop_through || feet < y + 1.0 - PLATFORM_MARGIN {
+						continue;
+					}
+					y + 1.0
+				}
+				// The height at the edge of the area that is the furthest up the slope.
+				TileShape::SlopeLeft => y + 1.0 - (area.min_x().clamp(x, x + 1.0) - x),
+				TileShape::SlopeRight => y + (area.max_x().clamp(x, x + 1.0) - x),
+			};
+
+			if surface >= low && surface <= high && ground.map_or(true, |ground| surface > ground) {
+				ground = Some(surface);
+			}
+		}
+	}
+
+	ground
+}
+
+/// Checks if `area` is inside of any full or half block.
+fn is_blocked(area: Rect<f32, WorldSpace>, chunks: &ChunkSystem) -> bool {
+	for x in area.min_x().floor() as i64..=area.max_x().ceil() as i64 {
+		for y in area.min_y().floor() as i64..=area.max_y().ceil() as i64 {
+			let pos = vec2(x as f32, y as f32);
+			let size = match get_tile(pos, chunks) {
+				Some(Tile {
+					collision: true,
+					shape: TileShape::Full,
+					..
+				}) => Size2D::new(1.0, 1.0),
+				Some(Tile {
+					collision: true,
+					shape: TileShape::HalfBlock,
+					..
+				}) => Size2D::new(1.0, 0.5),
+				_ => continue,
+			};
+
+			if aabb::rect_vs_rect(area, Rect::new(pos.to_point(), size)) {
+				return true;
+			}
+		}
+	}
+
+	false
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use hecs::Entity;
+	use rsa_core::api::Api;
+	use rsa_core::error::{Result, WrapErr};
+	use rsa_core::logging::LevelFilter;
+	use rsa_core::math::vec2;
+	use rsa_core::reload;
+	use rsa_core::settings::CHUNK_SIZE;
+	use rsa_core::ty::{ChunkPos, ChunkSubPos, Tag};
+
+	use crate::chunk::layer::tile::{Tile, TilePrototype};
+	use crate::chunk::layer::ChunkLayer;
+	use crate::chunk::{Chunk, ChunkSystem};
+	use crate::entity::component::gravity::GravityComp;
+	use crate::entity::component::hitbox::HitboxComp;
+	use crate::entity::component::humanoid::{HumanoidComp, HumanoidSettings};
+	use crate::entity::component::physics::PhysicsComp;
+	use crate::entity::component::pos::PositionComp;
+	use crate::entity::systems::collision::CollisionECSystem;
+	use crate::entity::EntitySystem;
+
+	pub(crate) fn setup(mut generate: impl FnMut(usize, usize) -> &'static str) -> Result<ChunkSystem> {
+		rsa_core::initialize(LevelFilter::Off)?;
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["tile"]:insert {
+				["r:air"] = {},
+				["r:dirt"] = { collision = true },
+				["r:platform"] = { collision = true, shape = "Platform" },
+				["r:slope"] = { collision = true, shape = "SlopeRight" },
+			}
+			"#,
+		);
+		reload!((TilePrototype) => api);
+
+		let carrier = api.get_carrier();
+		let tiles = carrier.get::<TilePrototype>();
+		let mut chunk = Chunk {
+			tiles: ChunkLayer::new([[tiles.create_from_tag(&Tag::rsa("air"))?; CHUNK_SIZE]; CHUNK_SIZE]),
+		};
+		for y in 0..CHUNK_SIZE {
+			for x in 0..CHUNK_SIZE {
+				let tile: Tile = tiles.create_from_tag(&Tag::rsa(generate(x, y)))?;
+				chunk.tiles[ChunkSubPos::new(x as u8, y as u8)] = tile;
+			}
+		}
+
+		let mut chunks = ChunkSystem::new();
+		chunks.put_chunk(ChunkPos { x: 0, y: 0 }, chunk);
+		Ok(chunks)
+	}
+
+	pub(crate) fn spawn_player(entities: &mut EntitySystem, x: f32, y: f32) -> Entity {
+		entities.push((
+			PositionComp { position: vec2(x, y) },
+			PhysicsComp::default(),
+			HitboxComp {
+				hitbox: rsa_core::math::rect(0.0, 0.0, 1.0, 1.0),
+				touches_ground: true,
+				drop_through: false,
+			},
+			GravityComp { speed: 1.0 },
+			HumanoidComp {
+				settings: HumanoidSettings {
+					jump_frames: 15,
+					jump_speed: 20.0,
+					run_acceleration: 4.8 * 60.0,
+					run_slowdown: 1.2 * 60.0,
+					run_max_speed: 12.0 * 60.0,
+				},
+				dir: Default::default(),
+				jumping: false,
+				jump_frames_remaining: 0.0,
+			},
+		))
+	}
+
+	fn position(entities: &EntitySystem, entity: Entity) -> (f32, f32) {
+		let position = entities.get::<PositionComp>(entity).unwrap().position;
+		(position.x, position.y)
+	}
+
+	#[test]
+	fn walks_up_slope() -> Result<()> {
+		// A slope going up from y 4 to 5 at x 6 and a raised floor after it.
+		let chunks = setup(|x, y| match (x, y) {
+			(_, y) if y < 4 => "dirt",
+			(6, 4) => "slope",
+			(x, 4) if x >= 7 => "dirt",
+			_ => "air",
+		})?;
+
+		let mut entities = EntitySystem::new();
+		let player = spawn_player(&mut entities, 2.0, 4.0);
+		entities.get_mut::<HumanoidComp>(player).unwrap().dir = vec2(1.0, 0.0);
+
+		for _ in 0..60 {
+			entities.tick(&chunks)?;
+		}
+
+		let (x, y) = position(&entities, player);
+		assert!(x > 9.0, "stuck on the slope at x {x}");
+		assert!((y - 5.0).abs() < 0.01, "not standing on the raised floor, y {y}");
+		Ok(())
+	}
+
+	#[test]
+	fn drops_through_platform() -> Result<()> {
+		let chunks = setup(|_, y| match y {
+			y if y < 4 => "dirt",
+			8 => "platform",
+			_ => "air",
+		})?;
+
+		let mut entities = EntitySystem::new();
+		let player = spawn_player(&mut entities, 4.0, 9.0);
+
+		// Standing on the platform.
+		for _ in 0..30 {
+			entities.tick(&chunks)?;
+		}
+		let (_, y) = position(&entities, player);
+		assert!((y - 9.0).abs() < 0.01, "fell off the platform, y {y}");
+
+		// Holding down drops through it.
+		entities.get_mut::<HumanoidComp>(player).unwrap().dir = vec2(0.0, -1.0);
+		for _ in 0..5 {
+			entities.tick(&chunks)?;
+		}
+		entities.get_mut::<HumanoidComp>(player).unwrap().dir = vec2(0.0, 0.0);
+		for _ in 0..120 {
+			entities.tick(&chunks)?;
+		}
+
+		let (_, y) = position(&entities, player);
+		assert!((y - 4.0).abs() < 0.01, "did not land on the floor, y {y}");
+		assert!(entities.get::<HitboxComp>(player).unwrap().touches_ground);
+		Ok(())
+	}
+
+	#[test]
+	fn lands_on_platform_from_below() -> Result<()> {
+		let chunks = setup(|_, y| match y {
+			y if y < 4 => "dirt",
+			6 => "platform",
+			_ => "air",
+		})?;
+
+		let mut entities = EntitySystem::new();
+		let player = spawn_player(&mut entities, 4.0, 4.0);
+		// Jump up through the platform.
+		entities.get_mut::<PhysicsComp>(player).unwrap().velocity = vec2(0.0, 15.0);
+		entities.get_mut::<HitboxComp>(player).unwrap().touches_ground = false;
+
+		for _ in 0..180 {
+			entities.tick(&chunks)?;
+		}
+
+		let (_, y) = position(&entities, player);
+		assert!((y - 7.0).abs() < 0.01, "did not land on top of the platform, y {y}");
+		Ok(())
+	}
+
+	/// The sweep used to go `velocity * delta` from `position * delta`, which is a whole second of movement
+	/// from the wrong spot, so walls far ahead stopped entities and any other delta looked at the wrong tiles.
+	#[test]
+	fn sweeps_one_tick_of_movement() -> Result<()> {
+		let chunks = setup(|x, _| if x == 10 { "dirt" } else { "air" })?;
+		let sweep = |delta| {
+			let mut position = PositionComp { position: vec2(8.0, 5.0) };
+			let mut physics = PhysicsComp {
+				velocity: vec2(120.0, 0.0),
+				acceleration: vec2(0.0, 0.0),
+			};
+			let mut hitbox = HitboxComp {
+				hitbox: rsa_core::math::rect(0.0, 0.0, 1.0, 1.0),
+				touches_ground: false,
+				drop_through: false,
+			};
+			CollisionECSystem.tick_entity(&mut position, &mut physics, &mut hitbox, &chunks, delta);
+			physics.velocity.x
+		};
+
+		// Two tiles a tick, the wall is one tile away.
+		assert!((sweep(1.0) - 60.0).abs() < 0.001, "did not stop at the wall");
+		// Half a tick only gets to the wall.
+		assert_eq!(sweep(0.5), 120.0);
+		Ok(())
+	}
+}
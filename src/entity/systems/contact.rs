@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use hecs::Entity;
+use rsa_core::math::{Rect, vec2, WorldSpace};
+
+use crate::entity::component::contact::ContactComp;
+use crate::entity::component::pos::PositionComp;
+use crate::entity::event::EntityEvent;
+use crate::entity::spatial::SpatialIndex;
+use crate::entity::systems::schedule::Access;
+use crate::entity::EntityStorage;
+use crate::util::aabb;
+
+#[derive(Default)]
+pub(crate) struct ContactECSystem {
+	// Ordered so the events come out the same way every time.
+	contacts: BTreeSet<(Entity, Entity)>,
+	// Entities that got pushed apart on the last tick.
+	pushed: BTreeSet<Entity>,
+}
+
+impl ContactECSystem {
+	pub(crate) fn access() -> Access {
+		Access::default()
+			.read::<ContactComp>()
+			.write::<PositionComp>()
+	}
+
+	pub(crate) fn tick(
+		&mut self,
+		storage: &EntityStorage,
+		spatial: &SpatialIndex,
+		events: &mut Vec<EntityEvent>,
+	) {
+		let participants: BTreeMap<Entity, ContactComp> = storage
+			.query::<&ContactComp>()
+			.iter()
+			.map(|(entity, contact)| (entity, contact.clone()))
+			.collect();
+
+		self.pushed.clear();
+		let mut contacts = BTreeSet::new();
+		for (entity, contact) in &participants {
+			let bounds = match spatial.get_bounds(*entity) {
+				Some(bounds) => bounds,
+				None => continue,
+			};
+
+			for other in spatial.query_area(bounds) {
+				// Only look at every pair once.
+				if other <= *entity {
+					continue;
+				}
+
+				if let (Some(other_contact), Some(other_bounds)) =
+					(participants.get(&other), spatial.get_bounds(other))
+				{
+					if contact.interacts(other_contact) && aabb::rect_vs_rect(bounds, other_bounds)
+					{
+						contacts.insert((*entity, other));
+						if contact.solid && other_contact.solid {
+							self.push_apart(storage, (*entity, bounds), (other, other_bounds));
+							self.pushed.insert(*entity);
+							self.pushed.insert(other);
+						}
+					}
+				}
+			}
+		}
+
+		for (e1, e2) in contacts.difference(&self.contacts) {
+			events.push(EntityEvent::ContactBegin(*e1, *e2));
+		}
+
+		for (e1, e2) in self.contacts.difference(&contacts) {
+			events.push(EntityEvent::ContactEnd(*e1, *e2));
+		}
+
+		self.contacts = contacts;
+	}
+
+	/// The entities that got pushed apart on the last tick, the SpatialIndex still has their old position.
+	pub(crate) fn pushed(&self) -> Vec<Entity> {
+		self.pushed.iter().copied().collect()
+	}
+
+	/// Moves both entities half of the overlap away from each other on the axis that overlaps the least.
+	fn push_apart(
+		&self,
+		storage: &EntityStorage,
+		(e1, r1): (Entity, Rect<f32, WorldSpace>),
+		(e2, r2): (Entity, Rect<f32, WorldSpace>),
+	) {
+		let overlap_x = r1.max_x().min(r2.max_x()) - r1.min_x().max(r2.min_x());
+		let overlap_y = r1.max_y().min(r2.max_y()) - r1.min_y().max(r2.min_y());
+
+		let push = if overlap_x < overlap_y {
+			let dir = if r1.center().x < r2.center().x { -1.0 } else { 1.0 };
+			vec2(dir * overlap_x / 2.0, 0.0)
+		} else {
+			let dir = if r1.center().y < r2.center().y { -1.0 } else { 1.0 };
+			vec2(0.0, dir * overlap_y / 2.0)
+		};
+
+		if let Ok(mut position) = storage.get_mut_shared::<PositionComp>(e1) {
+			position.position += push;
+		}
+		if let Ok(mut position) = storage.get_mut_shared::<PositionComp>(e2) {
+			position.position -= push;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use hecs::Entity;
+	use rsa_core::error::Result;
+	use rsa_core::math::{rect, vec2};
+
+	use crate::chunk::ChunkSystem;
+	use crate::entity::component::contact::ContactComp;
+	use crate::entity::component::hitbox::HitboxComp;
+	use crate::entity::component::pos::PositionComp;
+	use crate::entity::event::EntityEvent;
+	use crate::entity::EntitySystem;
+
+	fn spawn(entities: &mut EntitySystem, x: f32, layers: u32, mask: u32, solid: bool) -> Entity {
+		entities.push((
+			PositionComp { position: vec2(x, 0.0) },
+			HitboxComp {
+				hitbox: rect(0.0, 0.0, 1.0, 1.0),
+				touches_ground: false,
+				drop_through: false,
+			},
+			ContactComp { layers, mask, solid },
+		))
+	}
+
+	fn contacts(entities: &EntitySystem) -> Vec<EntityEvent> {
+		entities
+			.events()
+			.iter()
+			.copied()
+			.filter(|event| matches!(event, EntityEvent::ContactBegin(..) | EntityEvent::ContactEnd(..)))
+			.collect()
+	}
+
+	#[test]
+	fn begins_and_ends() -> Result<()> {
+		let chunks = ChunkSystem::new();
+		let mut entities = EntitySystem::new();
+		let first = spawn(&mut entities, 0.0, 1, 1, false);
+		let second = spawn(&mut entities, 0.5, 1, 1, false);
+
+		entities.tick(&chunks)?;
+		assert_eq!(contacts(&entities), vec![EntityEvent::ContactBegin(first, second)]);
+
+		// Still touching, nothing new.
+		entities.tick(&chunks)?;
+		assert!(contacts(&entities).is_empty());
+
+		entities.teleport(second, vec2(5.0, 0.0));
+		entities.tick(&chunks)?;
+		assert_eq!(contacts(&entities), vec![EntityEvent::ContactEnd(first, second)]);
+		Ok(())
+	}
+
+	#[test]
+	fn masks_filter() -> Result<()> {
+		let chunks = ChunkSystem::new();
+		let mut entities = EntitySystem::new();
+		let player = spawn(&mut entities, 0.0, 0b01, 0b10, false);
+		// Wants to touch the player, but the player does not want to touch it.
+		spawn(&mut entities, 0.25, 0b01, 0b01, false);
+		let enemy = spawn(&mut entities, 0.5, 0b10, 0b01, false);
+
+		entities.tick(&chunks)?;
+		assert_eq!(contacts(&entities), vec![EntityEvent::ContactBegin(player, enemy)]);
+		Ok(())
+	}
+
+	#[test]
+	fn solids_get_pushed_apart() -> Result<()> {
+		let chunks = ChunkSystem::new();
+		let mut entities = EntitySystem::new();
+		let left = spawn(&mut entities, 0.0, 1, 1, true);
+		let right = spawn(&mut entities, 0.5, 1, 1, true);
+
+		entities.tick(&chunks)?;
+		assert_eq!(entities.get::<PositionComp>(left)?.position, vec2(-0.25, 0.0));
+		assert_eq!(entities.get::<PositionComp>(right)?.position, vec2(0.75, 0.0));
+		Ok(())
+	}
+}
@@ -1,6 +1,10 @@
+use rayon::ThreadPool;
 use rsa_core::settings::UPS;
 use crate::entity::component::gravity::GravityComp;
 use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::projectile::ProjectileComp;
+use crate::entity::component::stats::StatsComp;
+use crate::entity::systems::schedule::{run_batched, Access, BATCH_SIZE};
 use crate::entity::EntityStorage;
 
 pub(crate) struct GravityECSystem {
@@ -13,10 +17,32 @@ impl GravityECSystem {
 			gravity_pull: 20.0
 		}
 	}
-	pub(crate) fn tick(&self, storage: &mut EntityStorage, delta: f32) {
-		for (_, (gravity, physics)) in storage.query_mut::<(&GravityComp, &mut PhysicsComp)>() {
-			self.tick_entity(gravity, physics, delta);
-		}
+	pub(crate) fn access() -> Access {
+		Access::default()
+			.read::<GravityComp>()
+			.read::<StatsComp>()
+			.read::<ProjectileComp>()
+			.write::<PhysicsComp>()
+	}
+
+	pub(crate) fn tick(&self, storage: &EntityStorage, pool: Option<&ThreadPool>, delta: f32) {
+		// Projectiles bring their own gravity, the ProjectileECSystem pulls on them.
+		let mut query = storage
+			.query::<(&GravityComp, &mut PhysicsComp, Option<&StatsComp>)>()
+			.without::<ProjectileComp>();
+		run_batched(pool, query.iter_batched(BATCH_SIZE).collect(), |batch| {
+			for (_, (gravity, physics, stats)) in batch {
+				match stats {
+					Some(stats) => {
+						let gravity = GravityComp {
+							speed: stats.modifiers.gravity.apply(gravity.speed),
+						};
+						self.tick_entity(&gravity, physics, delta);
+					}
+					None => self.tick_entity(gravity, physics, delta),
+				}
+			}
+		});
 	}
 
 	#[inline(always)]
@@ -0,0 +1,51 @@
+use std::collections::HashSet;
+
+use hecs::Entity;
+
+use crate::entity::component::health::HealthComp;
+use crate::entity::component::respawn::RespawnComp;
+use crate::entity::event::EntityEvent;
+use crate::entity::systems::schedule::Access;
+use crate::entity::EntityStorage;
+
+#[derive(Default)]
+pub(crate) struct HealthECSystem;
+
+impl HealthECSystem {
+	pub(crate) fn access() -> Access {
+		Access::default()
+			.read::<HealthComp>()
+			.write::<RespawnComp>()
+	}
+
+	pub(crate) fn tick(
+		&self,
+		storage: &EntityStorage,
+		dead: &mut HashSet<Entity>,
+		events: &mut Vec<EntityEvent>,
+	) {
+		for (entity, (health, respawn)) in storage
+			.query::<(&HealthComp, Option<&mut RespawnComp>)>()
+			.iter()
+		{
+			if health.health > 0.0 {
+				continue;
+			}
+
+			match respawn {
+				Some(respawn) => {
+					if !respawn.dead {
+						respawn.dead = true;
+						events.push(EntityEvent::Death(entity));
+					}
+				}
+				// The entity lives on until the next tick so the death can still look at it.
+				None => {
+					if dead.insert(entity) {
+						events.push(EntityEvent::Death(entity));
+					}
+				}
+			}
+		}
+	}
+}
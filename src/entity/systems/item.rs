@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use hecs::Entity;
+use rsa_core::math::{Vector2D, WorldSpace};
+use rsa_core::settings::UPS;
+
+use crate::entity::component::collector::CollectorComp;
+use crate::entity::component::item::ItemComp;
+use crate::entity::component::physics::PhysicsComp;
+use crate::entity::event::EntityEvent;
+use crate::entity::spatial::SpatialIndex;
+use crate::entity::systems::schedule::Access;
+use crate::entity::EntityStorage;
+use crate::util::aabb;
+
+/// How fast items accelerate towards a collector in tiles per second squared, this needs to beat gravity.
+const MAGNET_ACCELERATION: f32 = 80.0;
+/// The fastest an item can fly towards a collector in tiles per second.
+const MAGNET_MAX_SPEED: f32 = 20.0;
+
+struct Target {
+	distance: f32,
+	collector: Entity,
+	direction: Vector2D<f32, WorldSpace>,
+	touching: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct ItemECSystem;
+
+impl ItemECSystem {
+	pub(crate) fn access() -> Access {
+		Access::default()
+			.read::<CollectorComp>()
+			.write::<ItemComp>()
+			.write::<PhysicsComp>()
+	}
+
+	pub(crate) fn tick(
+		&self,
+		storage: &EntityStorage,
+		spatial: &SpatialIndex,
+		events: &mut Vec<EntityEvent>,
+		delta: f32,
+	) {
+		for (_, item) in storage.query::<&mut ItemComp>().iter() {
+			item.pickup_delay = (item.pickup_delay - delta).max(0.0);
+		}
+
+		// Find the closest collector for every item that can be picked up.
+		let mut targets: BTreeMap<Entity, Target> = BTreeMap::new();
+		for (collector, collector_comp) in storage.query::<&CollectorComp>().iter() {
+			let bounds = match spatial.get_bounds(collector) {
+				Some(bounds) => bounds,
+				None => continue,
+			};
+
+			let center = bounds.center().to_vector();
+			for item in spatial.query_radius(center, collector_comp.range) {
+				let item_bounds = match (storage.get::<ItemComp>(item), spatial.get_bounds(item)) {
+					(Ok(comp), Some(item_bounds)) if comp.pickup_delay <= 0.0 => item_bounds,
+					_ => continue,
+				};
+
+				let distance = (center - item_bounds.center().to_vector()).square_length();
+				if targets.get(&item).map_or(true, |target| distance < target.distance) {
+					targets.insert(
+						item,
+						Target {
+							distance,
+							collector,
+							direction: center - item_bounds.center().to_vector(),
+							touching: aabb::rect_vs_rect(bounds, item_bounds),
+						},
+					);
+				}
+			}
+		}
+
+		for (item, target) in targets {
+			if target.touching {
+				events.push(EntityEvent::ItemTouch(item, target.collector));
+			}
+
+			if let (Ok(mut physics), Some(dir)) = (storage.get_mut_shared::<PhysicsComp>(item), target.direction.try_normalize()) {
+				physics.velocity += dir * (MAGNET_ACCELERATION / UPS as f32) * delta;
+				physics.velocity = physics.velocity.with_max_length(MAGNET_MAX_SPEED);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use hecs::Entity;
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::math::{rect, vec2};
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+	use rsa_item::item::ItemPrototype;
+	use rsa_item::stack::ItemStack;
+
+	use crate::chunk::ChunkSystem;
+	use crate::entity::component::collector::CollectorComp;
+	use crate::entity::component::hitbox::HitboxComp;
+	use crate::entity::component::item::ItemComp;
+	use crate::entity::component::physics::PhysicsComp;
+	use crate::entity::component::pos::PositionComp;
+	use crate::entity::event::EntityEvent;
+	use crate::entity::EntitySystem;
+
+	fn stick() -> Result<ItemStack> {
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["stick"] = { max_stack = 10 },
+			}
+			"#,
+		);
+		reload!((ItemPrototype) => api);
+		let item = api.get_carrier().get::<ItemPrototype>().create_from_tag(&Tag::new("hello:stick")?)?;
+		Ok(ItemStack::new(item, Some(3)))
+	}
+
+	fn hitbox(width: f32, height: f32) -> HitboxComp {
+		HitboxComp {
+			hitbox: rect(0.0, 0.0, width, height),
+			touches_ground: false,
+			drop_through: false,
+		}
+	}
+
+	fn collector(entities: &mut EntitySystem) -> Entity {
+		entities.push((
+			PositionComp { position: vec2(2.0, 2.0) },
+			hitbox(1.0, 2.0),
+			CollectorComp { range: 5.0 },
+		))
+	}
+
+	fn item(entities: &mut EntitySystem, x: f32, pickup_delay: f32) -> Result<Entity> {
+		Ok(entities.push((
+			PositionComp { position: vec2(x, 2.5) },
+			hitbox(0.5, 0.5),
+			PhysicsComp::default(),
+			ItemComp {
+				stack: stick()?,
+				pickup_delay,
+			},
+		)))
+	}
+
+	fn touches(entities: &EntitySystem) -> Vec<(Entity, Entity)> {
+		entities
+			.events()
+			.iter()
+			.filter_map(|event| match *event {
+				EntityEvent::ItemTouch(item, collector) => Some((item, collector)),
+				_ => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn touches_after_the_delay() -> Result<()> {
+		let chunks = ChunkSystem::new();
+		let mut entities = EntitySystem::new();
+		let collector = collector(&mut entities);
+		let item = item(&mut entities, 2.25, 2.0)?;
+
+		entities.tick(&chunks)?;
+		assert!(touches(&entities).is_empty());
+		entities.tick(&chunks)?;
+		assert_eq!(touches(&entities), vec![(item, collector)]);
+		Ok(())
+	}
+
+	#[test]
+	fn pulls_items_in() -> Result<()> {
+		let chunks = ChunkSystem::new();
+		let mut entities = EntitySystem::new();
+		collector(&mut entities);
+		let near = item(&mut entities, 5.0, 0.0)?;
+		let waiting = item(&mut entities, 5.0, 60.0)?;
+		let far = item(&mut entities, 12.0, 0.0)?;
+
+		entities.tick(&chunks)?;
+		assert!(touches(&entities).is_empty());
+		assert!(entities.get::<PhysicsComp>(near)?.velocity.x < 0.0);
+		assert_eq!(entities.get::<PhysicsComp>(waiting)?.velocity.x, 0.0);
+		assert_eq!(entities.get::<PhysicsComp>(far)?.velocity.x, 0.0);
+		Ok(())
+	}
+}
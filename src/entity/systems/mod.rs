@@ -1,5 +1,11 @@
+pub(crate) mod animation;
 pub(crate) mod collision;
+pub(crate) mod contact;
 pub(crate) mod gravity;
+pub(crate) mod health;
+pub(crate) mod item;
 pub(crate) mod movement;
 pub(crate) mod server_network;
 pub(crate) mod physics;
+pub(crate) mod projectile;
+pub(crate) mod schedule;
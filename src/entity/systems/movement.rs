@@ -3,11 +3,14 @@ use std::collections::HashMap;
 use hecs::Entity;
 use rsa_core::logging::trace;
 use rsa_core::math::default::Vector2D;
+use rayon::ThreadPool;
 use rsa_core::settings::UPS;
 
 use crate::entity::component::hitbox::HitboxComp;
 use crate::entity::component::humanoid::HumanoidComp;
 use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::stats::StatsComp;
+use crate::entity::systems::schedule::{run_batched, Access, BATCH_SIZE};
 use crate::entity::EntityStorage;
 
 #[derive(Default)]
@@ -16,15 +19,42 @@ pub(crate) struct MovementECSystem {
 }
 
 impl MovementECSystem {
-	pub(crate) fn tick(&mut self, storage: &mut EntityStorage, delta: f32) {
+	pub(crate) fn access() -> Access {
+		Access::default()
+			.read::<StatsComp>()
+			.write::<HumanoidComp>()
+			.write::<PhysicsComp>()
+			.write::<HitboxComp>()
+	}
+
+	pub(crate) fn tick(&self, storage: &EntityStorage, pool: Option<&ThreadPool>, delta: f32) {
 		// Humanoids
-		for (entity, (humanoid, physics,hitbox)) in storage.query_mut::<(&mut HumanoidComp, &mut PhysicsComp, &HitboxComp)>() {
-			self.tick_entity(entity, humanoid, physics, hitbox, delta);
-		}
+		let mut query = storage.query::<(&mut HumanoidComp, &mut PhysicsComp, &mut HitboxComp, Option<&StatsComp>)>();
+		run_batched(pool, query.iter_batched(BATCH_SIZE).collect(), |batch| {
+			for (entity, (humanoid, physics, hitbox, stats)) in batch {
+				// Run with the modified settings and put the real ones back afterwards.
+				let settings = match stats {
+					Some(stats) => {
+						let modified = humanoid.settings.modified(&stats.modifiers);
+						Some(std::mem::replace(&mut humanoid.settings, modified))
+					}
+					None => None,
+				};
+
+				self.tick_entity(entity, humanoid, physics, hitbox, delta);
+
+				if let Some(settings) = settings {
+					humanoid.settings = settings;
+				}
+			}
+		});
 	}
 
 	#[inline(always)]
-	pub(crate) fn tick_entity(&mut self, entity: Entity, humanoid: &mut HumanoidComp, physics: &mut PhysicsComp, hitbox: &HitboxComp, delta: f32) {
+	pub(crate) fn tick_entity(&self, entity: Entity, humanoid: &mut HumanoidComp, physics: &mut PhysicsComp, hitbox: &mut HitboxComp, delta: f32) {
+		// Holding down drops through platforms.
+		hitbox.drop_through = humanoid.dir.y < 0.0;
+
 		physics.velocity.x += (humanoid.dir.x * (humanoid.settings.run_acceleration / UPS as f32)) * delta;
 		physics.velocity.y += (humanoid.dir.y * (humanoid.settings.run_acceleration / UPS as f32)) * delta;
 
@@ -1,18 +1,28 @@
+use rayon::ThreadPool;
 use rsa_core::settings::UPS;
 
 use crate::entity::component::physics::PhysicsComp;
 use crate::entity::component::pos::PositionComp;
+use crate::entity::systems::schedule::{run_batched, Access, BATCH_SIZE};
 use crate::entity::EntityStorage;
 
 #[derive(Default)]
 pub(crate) struct PhysicsECSystem;
 
 impl PhysicsECSystem {
-	pub(crate) fn tick(&self, storage: &mut EntityStorage, delta: f32) {
-		for (_, (position, physics)) in storage.query_mut::<(&mut PositionComp, &mut PhysicsComp)>()
-		{
-			self.tick_entity(position, physics, delta)
-		}
+	pub(crate) fn access() -> Access {
+		Access::default()
+			.write::<PositionComp>()
+			.write::<PhysicsComp>()
+	}
+
+	pub(crate) fn tick(&self, storage: &EntityStorage, pool: Option<&ThreadPool>, delta: f32) {
+		let mut query = storage.query::<(&mut PositionComp, &mut PhysicsComp)>();
+		run_batched(pool, query.iter_batched(BATCH_SIZE).collect(), |batch| {
+			for (_, (position, physics)) in batch {
+				self.tick_entity(position, physics, delta)
+			}
+		});
 	}
 
 	#[inline(always)]
@@ -0,0 +1,344 @@
+use std::collections::HashSet;
+
+use hecs::Entity;
+use rsa_core::math::{Rect, Vector2D, WorldSpace};
+use rsa_core::settings::UPS;
+use rsa_core::ty::TilePos;
+
+use crate::chunk::ChunkSystem;
+use crate::entity::component::contact::ContactComp;
+use crate::entity::component::gravity::GravityComp;
+use crate::entity::component::hitbox::HitboxComp;
+use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::pos::PositionComp;
+use crate::entity::component::projectile::ProjectileComp;
+use crate::entity::component::respawn::RespawnComp;
+use crate::entity::event::{EntityEvent, ProjectileTarget};
+use crate::entity::spatial::SpatialIndex;
+use crate::entity::systems::collision;
+use crate::entity::systems::gravity::GravityECSystem;
+use crate::entity::systems::schedule::Access;
+use crate::entity::EntityStorage;
+use crate::util::aabb;
+
+#[derive(Default)]
+pub(crate) struct ProjectileECSystem;
+
+impl ProjectileECSystem {
+	pub(crate) fn access() -> Access {
+		Access::default()
+			.read::<PositionComp>()
+			.read::<HitboxComp>()
+			.read::<ContactComp>()
+			.read::<RespawnComp>()
+			.write::<ProjectileComp>()
+			.write::<PhysicsComp>()
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	pub(crate) fn tick(
+		&self,
+		storage: &EntityStorage,
+		chunks: &ChunkSystem,
+		spatial: &SpatialIndex,
+		gravity: &GravityECSystem,
+		dead: &mut HashSet<Entity>,
+		events: &mut Vec<EntityEvent>,
+		delta: f32,
+	) {
+		for (entity, (projectile, physics)) in storage
+			.query::<(&mut ProjectileComp, &mut PhysicsComp)>()
+			.iter()
+		{
+			gravity.tick_entity(
+				&GravityComp {
+					speed: projectile.gravity,
+				},
+				physics,
+				delta,
+			);
+
+			projectile.age += delta;
+			if projectile.age >= projectile.lifetime {
+				dead.insert(entity);
+			}
+		}
+
+		// We need to look at other entities while sweeping, so grab the projectiles first.
+		let projectiles: Vec<(Entity, Rect<f32, WorldSpace>, Vector2D<f32, WorldSpace>)> = storage
+			.query::<(&ProjectileComp, &PositionComp, &PhysicsComp, &HitboxComp)>()
+			.iter()
+			.filter(|(entity, _)| !dead.contains(entity))
+			.map(|(entity, (_, position, physics, hitbox))| {
+				(
+					entity,
+					hitbox.hitbox.translate(position.position),
+					(physics.velocity / UPS as f32) * delta,
+				)
+			})
+			.collect();
+
+		for (entity, bounds, motion) in projectiles {
+			let tile_hit = self.sweep_tiles(bounds, motion, chunks);
+			let stop_time = tile_hit.map(|(_, time)| time).unwrap_or(1.0);
+
+			// Entities between the projectile and the wall it hits.
+			let mut entity_hits = Vec::new();
+			if let Ok(projectile) = storage.get::<ProjectileComp>(entity) {
+				let contact = storage.get::<ContactComp>(entity).ok();
+				for target in spatial.query_area(bounds.union(&bounds.translate(motion))) {
+					if target == entity
+						|| projectile.owner == Some(target)
+						|| projectile.hit.contains(&target)
+						|| dead.contains(&target)
+						|| storage.get::<RespawnComp>(target).map_or(false, |comp| comp.dead)
+						|| !ContactComp::may_interact(
+							contact.as_deref(),
+							storage.get::<ContactComp>(target).ok().as_deref(),
+						) {
+						continue;
+					}
+
+					if let Some(target_bounds) = spatial.get_bounds(target) {
+						let time = if aabb::rect_vs_rect(bounds, target_bounds) {
+							Some(0.0)
+						} else {
+							aabb::dynamic_rect_vs_rect(motion, bounds, 1.0, target_bounds)
+								.map(|collision| collision.contact_time)
+						};
+
+						if let Some(time) = time {
+							if time <= stop_time {
+								entity_hits.push((target, time));
+							}
+						}
+					}
+				}
+			}
+			entity_hits.sort_by(|(_, t0), (_, t1)| t0.total_cmp(t1));
+
+			let mut death_time = None;
+			if let Ok(mut projectile) = storage.get_mut_shared::<ProjectileComp>(entity) {
+				for (target, time) in entity_hits {
+					events.push(EntityEvent::ProjectileHit(
+						entity,
+						ProjectileTarget::Entity(target),
+					));
+					projectile.hit.push(target);
+					if projectile.hit.len() as u32 > projectile.pierce {
+						death_time = Some(time);
+						break;
+					}
+				}
+			}
+
+			if death_time.is_none() {
+				if let Some((pos, time)) = tile_hit {
+					events.push(EntityEvent::ProjectileHit(
+						entity,
+						ProjectileTarget::Tile(pos),
+					));
+					death_time = Some(time);
+				}
+			}
+
+			if let Some(time) = death_time {
+				// Stop right where we hit so it does not look like it went through.
+				if let Ok(mut physics) = storage.get_mut_shared::<PhysicsComp>(entity) {
+					physics.velocity *= time;
+				}
+				dead.insert(entity);
+			}
+		}
+	}
+
+	fn sweep_tiles(
+		&self,
+		bounds: Rect<f32, WorldSpace>,
+		motion: Vector2D<f32, WorldSpace>,
+		chunks: &ChunkSystem,
+	) -> Option<(TilePos, f32)> {
+		let area = bounds.union(&bounds.translate(motion));
+		let x1 = area.min_x().floor() as i64;
+		let y1 = area.min_y().floor() as i64;
+		let x2 = area.max_x().ceil() as i64;
+		let y2 = area.max_y().ceil() as i64;
+
+		let mut closest: Option<(TilePos, f32)> = None;
+		for x in x1..=x2 {
+			for y in y1..=y2 {
+				let pos = Vector2D::new(x as f32, y as f32);
+				if let Some((tile, time)) = collision::test_tile(pos, motion, bounds, chunks) {
+					if closest.map_or(true, |(_, closest)| time < closest) {
+						if let Ok(pos) = TilePos::try_from(tile.origin.to_vector()) {
+							closest = Some((pos, time));
+						}
+					}
+				}
+			}
+		}
+
+		closest
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use hecs::Entity;
+	use rsa_core::error::Result;
+	use rsa_core::math::{rect, vec2, Vector2D, WorldSpace};
+	use rsa_core::ty::TilePos;
+
+	use crate::chunk::ChunkSystem;
+	use crate::entity::component::contact::ContactComp;
+	use crate::entity::component::gravity::GravityComp;
+	use crate::entity::component::hitbox::HitboxComp;
+	use crate::entity::component::physics::PhysicsComp;
+	use crate::entity::component::pos::PositionComp;
+	use crate::entity::component::projectile::ProjectileComp;
+	use crate::entity::event::{EntityEvent, ProjectileTarget};
+	use crate::entity::systems::collision::tests::setup;
+	use crate::entity::EntitySystem;
+
+	/// Dirt below y 4, air above.
+	fn ground() -> Result<ChunkSystem> {
+		setup(|_, y| if y < 4 { "dirt" } else { "air" })
+	}
+
+	fn hitbox(size: f32) -> HitboxComp {
+		HitboxComp {
+			hitbox: rect(0.0, 0.0, size, size),
+			touches_ground: false,
+			drop_through: false,
+		}
+	}
+
+	fn target(entities: &mut EntitySystem, x: f32) -> Entity {
+		entities.push((PositionComp { position: vec2(x, 6.0) }, hitbox(1.0)))
+	}
+
+	/// Flies 4 tiles every tick in `velocity`'s direction.
+	fn shoot(entities: &mut EntitySystem, x: f32, y: f32, velocity: (f32, f32), pierce: u32) -> Entity {
+		entities.push((
+			PositionComp { position: vec2(x, y) },
+			PhysicsComp {
+				velocity: vec2(velocity.0, velocity.1) * 240.0,
+				acceleration: Default::default(),
+			},
+			hitbox(0.5),
+			ProjectileComp {
+				speed: 240.0,
+				lifetime: 300.0,
+				pierce,
+				gravity: 0.0,
+				owner: None,
+				age: 0.0,
+				hit: vec![],
+			},
+		))
+	}
+
+	fn hits(entities: &EntitySystem) -> Vec<(Entity, ProjectileTarget)> {
+		entities
+			.events()
+			.iter()
+			.filter_map(|event| match *event {
+				EntityEvent::ProjectileHit(projectile, target) => Some((projectile, target)),
+				_ => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn hits_the_first_entity() -> Result<()> {
+		let chunks = ground()?;
+		let mut entities = EntitySystem::new();
+		let near = target(&mut entities, 4.0);
+		target(&mut entities, 5.0);
+		// The targets need to be in the spatial index first.
+		entities.tick(&chunks)?;
+
+		let arrow = shoot(&mut entities, 2.0, 6.25, (1.0, 0.0), 0);
+		entities.tick(&chunks)?;
+		assert_eq!(hits(&entities), vec![(arrow, ProjectileTarget::Entity(near))]);
+
+		// It stopped there and gets removed.
+		entities.tick(&chunks)?;
+		assert!(entities.get::<ProjectileComp>(arrow).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn pierces_through() -> Result<()> {
+		let chunks = ground()?;
+		let mut entities = EntitySystem::new();
+		let near = target(&mut entities, 4.0);
+		let far = target(&mut entities, 5.0);
+		entities.tick(&chunks)?;
+
+		let arrow = shoot(&mut entities, 2.0, 6.25, (1.0, 0.0), 1);
+		entities.tick(&chunks)?;
+		assert_eq!(
+			hits(&entities),
+			vec![
+				(arrow, ProjectileTarget::Entity(near)),
+				(arrow, ProjectileTarget::Entity(far)),
+			]
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn skips_owner_and_other_layers() -> Result<()> {
+		let chunks = ground()?;
+		let mut entities = EntitySystem::new();
+		let owner = target(&mut entities, 3.0);
+		let friend = target(&mut entities, 4.0);
+		let enemy = target(&mut entities, 5.0);
+		let contact = |layers, mask| ContactComp {
+			layers,
+			mask,
+			solid: false,
+		};
+		entities.add(friend, contact(0b01, 0b11));
+		entities.add(enemy, contact(0b10, 0b11));
+		entities.tick(&chunks)?;
+
+		let arrow = shoot(&mut entities, 2.0, 6.25, (1.0, 0.0), 0);
+		entities.add(arrow, contact(0b01, 0b10));
+		entities.get_mut::<ProjectileComp>(arrow)?.owner = Some(owner);
+		entities.tick(&chunks)?;
+		assert_eq!(hits(&entities), vec![(arrow, ProjectileTarget::Entity(enemy))]);
+		Ok(())
+	}
+
+	#[test]
+	fn stops_at_walls() -> Result<()> {
+		let chunks = ground()?;
+		let mut entities = EntitySystem::new();
+		let arrow = shoot(&mut entities, 2.25, 6.0, (0.0, -1.0), 0);
+		entities.tick(&chunks)?;
+
+		let tile: Vector2D<f32, WorldSpace> = vec2(2.0, 3.0);
+		assert_eq!(
+			hits(&entities),
+			vec![(arrow, ProjectileTarget::Tile(TilePos::try_from(tile).unwrap()))]
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn gravity_only_pulls_once() -> Result<()> {
+		let chunks = ground()?;
+		let mut entities = EntitySystem::new();
+		entities.set_fixed_point(false);
+		let arrow = shoot(&mut entities, 2.0, 10.0, (0.0, 0.0), 0);
+		entities.get_mut::<ProjectileComp>(arrow)?.gravity = 0.5;
+		entities.add(arrow, GravityComp { speed: 1.0 });
+		entities.tick(&chunks)?;
+
+		let velocity = entities.get::<PhysicsComp>(arrow)?.velocity;
+		assert_eq!(velocity.y, -((20.0 * 0.5) / 60.0));
+		Ok(())
+	}
+}
@@ -0,0 +1,189 @@
+use std::any::TypeId;
+
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+use crate::entity::Component;
+
+/// How many entities a thread takes at once, smaller batches spread out better but cost more to hand out.
+pub(crate) const BATCH_SIZE: u32 = 256;
+
+/// Which components a system touches. Systems whose access does not conflict can run at the same time.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Access {
+	reads: Vec<TypeId>,
+	writes: Vec<TypeId>,
+}
+
+impl Access {
+	pub(crate) fn read<C: Component>(mut self) -> Access {
+		self.reads.push(TypeId::of::<C>());
+		self
+	}
+
+	pub(crate) fn write<C: Component>(mut self) -> Access {
+		self.writes.push(TypeId::of::<C>());
+		self
+	}
+
+	/// Two systems conflict if one writes something the other one touches.
+	pub(crate) fn conflicts(&self, other: &Access) -> bool {
+		self.writes
+			.iter()
+			.any(|ty| other.writes.contains(ty) || other.reads.contains(ty))
+			|| other.writes.iter().any(|ty| self.reads.contains(ty))
+	}
+
+}
+
+/// A system that is ready to run, with the components it touches.
+pub(crate) struct Task<'a> {
+	access: Access,
+	run: Box<dyn FnOnce() + Send + 'a>,
+}
+
+impl<'a> Task<'a> {
+	pub(crate) fn new(access: Access, run: impl FnOnce() + Send + 'a) -> Task<'a> {
+		Task {
+			access,
+			run: Box::new(run),
+		}
+	}
+}
+
+/// Splits the systems into stages in which nothing conflicts, by their index.
+/// Every system goes into the stage after the last one it conflicts with,
+/// so systems that touch the same components still run in the order they were given.
+pub(crate) fn stages(systems: &[Access]) -> Vec<Vec<usize>> {
+	let mut stages: Vec<Vec<usize>> = Vec::new();
+	for (i, access) in systems.iter().enumerate() {
+		let stage = stages
+			.iter()
+			.rposition(|stage| stage.iter().any(|&other| systems[other].conflicts(access)))
+			.map_or(0, |stage| stage + 1);
+		if stage == stages.len() {
+			stages.push(Vec::new());
+		}
+		stages[stage].push(i);
+	}
+	stages
+}
+
+/// Runs the tasks stage by stage, the tasks within a stage run at the same time if there is a pool.
+pub(crate) fn run(pool: Option<&ThreadPool>, tasks: Vec<Task>) {
+	let access: Vec<Access> = tasks.iter().map(|task| task.access.clone()).collect();
+	let mut tasks: Vec<Option<Task>> = tasks.into_iter().map(Some).collect();
+	for stage in stages(&access) {
+		let stage: Vec<Task> = stage.into_iter().filter_map(|i| tasks[i].take()).collect();
+		match pool {
+			Some(pool) if stage.len() > 1 => pool.scope(|scope| {
+				for task in stage {
+					scope.spawn(move |_| (task.run)());
+				}
+			}),
+			_ => stage.into_iter().for_each(|task| (task.run)()),
+		}
+	}
+}
+
+/// Runs `func` on every batch, spread across the pool if there is one.
+pub(crate) fn run_batched<B, F>(pool: Option<&ThreadPool>, batches: Vec<B>, func: F)
+where
+	B: Send,
+	F: Fn(B) + Send + Sync,
+{
+	match pool {
+		Some(pool) => pool.install(|| batches.into_par_iter().for_each(func)),
+		None => batches.into_iter().for_each(func),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Mutex;
+
+	use rayon::ThreadPoolBuilder;
+
+	use crate::entity::systems::animation::AnimationECSystem;
+	use crate::entity::systems::collision::CollisionECSystem;
+	use crate::entity::systems::contact::ContactECSystem;
+	use crate::entity::systems::gravity::GravityECSystem;
+	use crate::entity::systems::health::HealthECSystem;
+	use crate::entity::systems::item::ItemECSystem;
+	use crate::entity::systems::movement::MovementECSystem;
+	use crate::entity::systems::physics::PhysicsECSystem;
+	use crate::entity::systems::projectile::ProjectileECSystem;
+	use crate::entity::systems::schedule::{run, stages, Access, Task};
+
+	struct Apple;
+	struct Pear;
+	struct Plum;
+
+	#[test]
+	fn conflicts_get_split_up() {
+		let systems = [
+			Access::default().write::<Apple>(),
+			Access::default().read::<Pear>(),
+			Access::default().read::<Apple>().write::<Plum>(),
+			Access::default().read::<Pear>(),
+			Access::default().write::<Pear>(),
+			Access::default().read::<Plum>(),
+		];
+		assert_eq!(stages(&systems), vec![vec![0, 1, 3], vec![2, 4], vec![5]]);
+	}
+
+	#[test]
+	fn readers_share_a_stage() {
+		let systems = [
+			Access::default().read::<Apple>(),
+			Access::default().read::<Apple>(),
+		];
+		assert_eq!(stages(&systems), vec![vec![0, 1]]);
+	}
+
+	#[test]
+	fn entity_systems_run_together() {
+		let systems = [
+			ContactECSystem::access(),
+			ItemECSystem::access(),
+			HealthECSystem::access(),
+			AnimationECSystem::access(),
+		];
+		assert_eq!(stages(&systems).len(), 1);
+	}
+
+	#[test]
+	fn movement_systems_keep_their_order() {
+		let systems = [
+			GravityECSystem::access(),
+			MovementECSystem::access(),
+			ProjectileECSystem::access(),
+			CollisionECSystem::access(),
+			PhysicsECSystem::access(),
+		];
+		assert_eq!(stages(&systems), vec![vec![0], vec![1], vec![2], vec![3], vec![4]]);
+	}
+
+	#[test]
+	fn conflicting_tasks_keep_their_order() {
+		let pool = ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+		let order = Mutex::new(Vec::new());
+		let order_ref = &order;
+		let tasks = (0..8)
+			.map(|i| {
+				let access = if i % 2 == 0 {
+					Access::default().write::<Apple>()
+				} else {
+					Access::default().read::<Pear>()
+				};
+				Task::new(access, move || order_ref.lock().unwrap().push(i))
+			})
+			.collect();
+		run(Some(&pool), tasks);
+
+		let order = order.into_inner().unwrap();
+		assert_eq!(order.len(), 8);
+		let writers: Vec<i32> = order.into_iter().filter(|i| i % 2 == 0).collect();
+		assert_eq!(writers, vec![0, 2, 4, 6]);
+	}
+}
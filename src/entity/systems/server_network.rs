@@ -1,8 +1,8 @@
 use hecs::Entity;
 use std::collections::HashMap;
 
-use rsa_core::error::Result;
-use rsa_core::logging::{debug, error};
+use rsa_core::error::{Report, Result};
+use rsa_core::logging::debug;
 
 use rsa_network::Token;
 use rsa_network::tunnel::Tunnel;
@@ -13,13 +13,20 @@ use crate::entity::packet::{ClientEntityPacket, ServerEntityPacket};
 use crate::entity::EntityStorage;
 use crate::{PlayerModule, ServerTunnel};
 use crate::entity::component::pos::PositionComp;
+use crate::entity::component::respawn::RespawnComp;
 
 #[derive(Default)]
 pub(crate) struct ServerNetworkECSystem {
 	position_queue: Vec<(u32, Entity)>,
+	// The last tick every player sent a direction for.
+	last_tick: HashMap<Token, u32>,
 }
 
 impl ServerNetworkECSystem {
+	pub(crate) fn leave(&mut self, token: Token) {
+		self.last_tick.remove(&token);
+	}
+
 	pub(crate) fn tick(
 		&mut self,
 		world: &mut EntityStorage,
@@ -48,13 +55,23 @@ impl ServerNetworkECSystem {
 				// Check if its actually the correct player entity.
 				if let Some(player) = players.get_player(token) {
 					if let Some(entity) = &player.entity {
+						let dead = storage.get::<RespawnComp>(*entity).map_or(false, |comp| comp.dead);
 						if let Ok(mut comp) = storage.get_mut::<HumanoidComp>(*entity) {
-							if dir.x > 1.0 || dir.x < -1.0 || dir.y > 1.0 || dir.y < -1.0 {
-								// Out of bounds
-								//return Err(Report::new(NetworkError::Hacking(HackingInfraction::Imposter)));
-								error!("haccks {:?}", dir);
+							if !(-1.0..=1.0).contains(&dir.x) || !(-1.0..=1.0).contains(&dir.y) {
+								// Out of bounds, the client never sends this.
+								return Err(Report::new(NetworkError::Hacking(HackingInfraction::Imposter)));
+							}
+
+							// Ticks only go up, a repeat or an old one is either a lot of lag or a replay.
+							if let Some(last) = self.last_tick.insert(*token, tick) {
+								if tick <= last {
+									return Err(Report::new(NetworkError::Hacking(HackingInfraction::Sus)));
+								}
+							}
+							// The dead dont walk.
+							if !dead {
+								comp.dir = dir;
 							}
-							comp.dir = dir;
 							// Wants response back on move
 							self.position_queue.push((tick, *entity));
 							return Ok(());
@@ -75,18 +92,23 @@ impl ServerNetworkECSystem {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, thiserror::Error)]
 pub enum HackingInfraction {
+	#[error("suspicious behaviour")]
 	// For minor errors that may just be caused by a de-sync
 	Sus,
+	#[error("impossible behaviour")]
 	// For bigger sus marks that they are definitely hacking but does not pose a risk for the rustaria kernel.
 	Imposter,
+	#[error("malicious behaviour")]
 	// Should instantly be kicked. DDOS level shit.
 	Emergency,
 }
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum NetworkError {
+	#[error("Network warning")]
 	Warn,
+	#[error("Player infraction, {0}")]
 	Hacking(HackingInfraction),
 }
@@ -24,15 +24,20 @@ use rsa_network::server::integrated::Integrated;
 
 // Internals
 use crate::module::chunks::ChunkModule;
+use crate::module::effects::EffectModule;
 use crate::module::entities::EntityModule;
+use crate::module::items::ItemModule;
 use crate::module::networking::NetworkModule;
 use crate::module::players::PlayerModule;
+use crate::module::spawning::SpawnModule;
+use crate::module::validation::{ValidationModule, ValidationPolicy};
 use crate::packet::{ClientPacket, ServerPacket};
 use crate::world::World;
 
 pub mod api;
 pub mod chunk;
 pub mod entity;
+pub mod loot;
 pub(crate) mod module;
 pub mod packet;
 pub mod player;
@@ -51,7 +56,11 @@ pub struct Server {
 	pub network: NetworkModule,
 	pub chunk: ChunkModule,
 	pub entity: EntityModule,
+	pub effect: EffectModule,
+	pub item: ItemModule,
 	pub player: PlayerModule,
+	pub spawn: SpawnModule,
+	pub validation: ValidationModule,
 
 	// Holds the actual data
 	pub world: World,
@@ -59,6 +68,8 @@ pub struct Server {
 
 impl Server {
 	pub fn new_integrated(api: &Api, thread_pool: Arc<ThreadPool>) -> Result<Server> {
+		let mut world = World::new();
+		world.entities.set_thread_pool(thread_pool.clone());
 		Ok(Server {
 			api: api.clone(),
 			network: NetworkModule::new(ServerNetwork {
@@ -67,8 +78,12 @@ impl Server {
 			}),
 			chunk: ChunkModule::new(thread_pool),
 			entity: EntityModule::new(),
+			effect: EffectModule::new(),
+			item: ItemModule::new(world.seed),
 			player: PlayerModule::new(api),
-			world: World::new(),
+			spawn: SpawnModule::new(),
+			validation: ValidationModule::new(ValidationPolicy::default()),
+			world,
 		})
 	}
 
@@ -81,6 +96,11 @@ impl Server {
 		self.world.tick()?;
 		ChunkModule::tick(self).wrap_err(SystemFail(SystemType::Chunk))?;
 		EntityModule::tick(self).wrap_err(SystemFail(SystemType::Entity))?;
+		ValidationModule::tick(self).wrap_err(SystemFail(SystemType::Validation))?;
+		EffectModule::tick(self).wrap_err(SystemFail(SystemType::Effect))?;
+		PlayerModule::tick(self).wrap_err(SystemFail(SystemType::Player))?;
+		ItemModule::tick(self).wrap_err(SystemFail(SystemType::Item))?;
+		SpawnModule::tick(self).wrap_err(SystemFail(SystemType::Spawn))?;
 
 		// Send
 		NetworkModule::tick(self).wrap_err(SystemFail(SystemType::Network))?;
@@ -89,8 +109,13 @@ impl Server {
 
 	pub fn reload(&mut self, api: &Api) {
 		self.chunk.reload(api);
+		// Players joining need somewhere to go.
+		self.chunk.init_spawn_point(&mut self.world);
 		self.player.reload(api);
 		self.entity.reload(api);
+		self.effect.reload(api);
+		self.item.reload(api);
+		self.spawn.reload(api);
 	}
 }
 
@@ -116,7 +141,11 @@ impl Display for CarrierUnavailable {
 #[derive(Debug)]
 pub enum SystemType {
 	Entity,
+	Effect,
+	Item,
 	Chunk,
 	Network,
 	Player,
+	Spawn,
+	Validation,
 }
@@ -0,0 +1,358 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use rsa_core::api::carrier::Carrier;
+use rsa_core::logging::warn;
+use rsa_core::ty::{Prototype, RawId, Tag};
+use rsa_item::item::ItemPrototype;
+use rsa_item::stack::ItemStack;
+
+use crate::entity::spawn_rule::SpawnZone;
+
+/// How deep tables can reference other tables, so tables referencing each other do not loop forever.
+pub const MAX_LOOT_DEPTH: u32 = 8;
+
+/// What drops from tiles, entities and chests.
+/// Every pool rolls on its own, each roll picks one of the entries that fit by their weight.
+/// ```lua
+/// reload.registry.loot_table:insert {
+///     ["bunne"] = {
+///         pools = {
+///             {
+///                 rolls = { min = 1, max = 2 },
+///                 entries = {
+///                     { item = "slime", weight = 3, amount = { min = 1, max = 3 } },
+///                     { table = "rare_drops", conditions = { luck = 1, chance = 0.1 } },
+///                 },
+///             },
+///             { entries = { { item = "bow" } }, conditions = { zones = { "Underground" } } },
+///             { entries = { { item = "slime_crown" } }, conditions = { biomes = { "jungle" }, chance = 0.01 } },
+///         },
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize, frogelua::FromLua)]
+pub struct LootTablePrototype {
+	pub pools: Vec<LootPool>,
+}
+
+impl Prototype for LootTablePrototype {
+	type Item = LootTablePrototype;
+
+	fn create(&self, _: RawId) -> Self::Item {
+		self.clone()
+	}
+
+	fn lua_registry_name() -> &'static str {
+		"loot_table"
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, frogelua::FromLua)]
+pub struct LootPool {
+	/// How many times an entry gets picked, once if this is not set.
+	#[serde(default)]
+	pub rolls: LootRange,
+	pub entries: Vec<LootEntry>,
+	#[serde(default)]
+	pub conditions: LootConditions,
+}
+
+/// Either an item or another table to roll, one of them needs to be set.
+#[derive(Clone, Debug, Deserialize, frogelua::FromLua)]
+pub struct LootEntry {
+	pub item: Option<Tag>,
+	pub table: Option<Tag>,
+	/// Defaults to 1.
+	pub weight: Option<u32>,
+	/// How many of the item drop, 1 if this is not set.
+	#[serde(default)]
+	pub amount: LootRange,
+	#[serde(default)]
+	pub conditions: LootConditions,
+}
+
+impl LootEntry {
+	pub fn weight(&self) -> u32 {
+		self.weight.unwrap_or(1)
+	}
+}
+
+/// An inclusive range where both ends default to 1.
+#[derive(Copy, Clone, PartialEq, Debug, Default, Deserialize, frogelua::FromLua)]
+#[use_default]
+pub struct LootRange {
+	pub min: Option<u32>,
+	pub max: Option<u32>,
+}
+
+impl LootRange {
+	pub fn roll(&self, rng: &mut impl Rng) -> u32 {
+		let min = self.min.unwrap_or(1);
+		let max = self.max.unwrap_or(min).max(min);
+		rng.gen_range(min..=max)
+	}
+}
+
+/// When a pool or an entry applies, everything that is not set always fits.
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, frogelua::FromLua)]
+#[use_default]
+pub struct LootConditions {
+	/// Surface or underground, a spot counts as underground if something solid is above it.
+	pub zones: Option<Vec<SpawnZone>>,
+	/// Which biomes this drops in, spots in no biome never fit if this is set.
+	pub biomes: Option<Vec<Tag>>,
+	/// The items that need to be used to get this, like a pickaxe for ores.
+	pub tools: Option<Vec<Tag>>,
+	/// How much luck is needed.
+	pub luck: Option<f32>,
+	/// The chance from 0 to 1 for this to happen at all, luck makes it more likely.
+	pub chance: Option<f32>,
+}
+
+impl LootConditions {
+	pub fn allows(&self, context: &LootContext, rng: &mut impl Rng) -> bool {
+		let zone_fits = match (&self.zones, context.zone) {
+			(Some(zones), Some(zone)) => zones.contains(&zone),
+			(Some(_), None) => false,
+			(None, _) => true,
+		};
+		let biome_fits = match (&self.biomes, &context.biome) {
+			(Some(biomes), Some(biome)) => biomes.contains(biome),
+			(Some(_), None) => false,
+			(None, _) => true,
+		};
+		let tool_fits = match (&self.tools, &context.tool) {
+			(Some(tools), Some(tool)) => tools.contains(tool),
+			(Some(_), None) => false,
+			(None, _) => true,
+		};
+		let luck_fits = self.luck.map_or(true, |luck| context.luck >= luck);
+
+		// Checked last so the rng only gets used if everything else fits.
+		zone_fits
+			&& biome_fits
+			&& tool_fits
+			&& luck_fits
+			&& self
+				.chance
+				.map_or(true, |chance| rng.gen::<f32>() < chance * (1.0 + context.luck.max(0.0)))
+	}
+}
+
+/// Where and how loot gets rolled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LootContext {
+	pub zone: Option<SpawnZone>,
+	/// The biome the loot drops in, found with [`crate::world::biome::biome_at`].
+	pub biome: Option<Tag>,
+	/// The item that got used, like the pickaxe that mined a tile.
+	pub tool: Option<Tag>,
+	pub luck: f32,
+}
+
+impl LootTablePrototype {
+	/// Rolls the table. The same rng state and context always give the same loot.
+	/// Stacks can go over `max_stack`, putting them somewhere splits them up.
+	pub fn roll(&self, carrier: &Carrier, context: &LootContext, rng: &mut impl Rng) -> Vec<ItemStack> {
+		let mut loot = Vec::new();
+		self.roll_into(carrier, context, rng, 0, &mut loot);
+		loot
+	}
+
+	fn roll_into(
+		&self,
+		carrier: &Carrier,
+		context: &LootContext,
+		rng: &mut impl Rng,
+		depth: u32,
+		loot: &mut Vec<ItemStack>,
+	) {
+		for pool in &self.pools {
+			if !pool.conditions.allows(context, rng) {
+				continue;
+			}
+
+			for _ in 0..pool.rolls.roll(rng) {
+				let entries: Vec<&LootEntry> = pool
+					.entries
+					.iter()
+					.filter(|entry| entry.conditions.allows(context, rng))
+					.collect();
+
+				if let Some(entry) = pick(&entries, rng) {
+					Self::roll_entry(entry, carrier, context, rng, depth, loot);
+				}
+			}
+		}
+	}
+
+	fn roll_entry(
+		entry: &LootEntry,
+		carrier: &Carrier,
+		context: &LootContext,
+		rng: &mut impl Rng,
+		depth: u32,
+		loot: &mut Vec<ItemStack>,
+	) {
+		if let Some(tag) = &entry.item {
+			match carrier.get::<ItemPrototype>().create_from_tag(tag) {
+				Ok(item) => {
+					let amount = entry.amount.roll(rng);
+					if amount > 0 {
+						loot.push(ItemStack::new(item, Some(amount)));
+					}
+				}
+				Err(_) => warn!(target: "tick@rustaria.loot", "Loot table drops unknown item {tag}"),
+			}
+		}
+
+		if let Some(tag) = &entry.table {
+			if depth >= MAX_LOOT_DEPTH {
+				warn!(target: "tick@rustaria.loot", "Loot table {tag} is nested too deep, it probably references itself");
+				return;
+			}
+
+			let table = match carrier.get::<LootTablePrototype>().create_from_tag(tag) {
+				Ok(table) => table,
+				Err(_) => {
+					warn!(target: "tick@rustaria.loot", "Loot table references unknown table {tag}");
+					return;
+				}
+			};
+			table.roll_into(carrier, context, rng, depth + 1, loot);
+		}
+	}
+}
+
+/// Picks an entry, the weight decides how likely it is to get picked.
+fn pick<'a>(entries: &[&'a LootEntry], rng: &mut impl Rng) -> Option<&'a LootEntry> {
+	let total: u32 = entries.iter().map(|entry| entry.weight()).sum();
+	if total == 0 {
+		return None;
+	}
+
+	let mut roll = rng.gen_range(0..total);
+	for entry in entries {
+		if roll < entry.weight() {
+			return Some(entry);
+		}
+		roll -= entry.weight();
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::rngs::SmallRng;
+	use rand::SeedableRng;
+
+	use rsa_core::api::carrier::Carrier;
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::logging::LevelFilter;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+	use rsa_item::item::ItemPrototype;
+
+	use crate::entity::spawn_rule::SpawnZone;
+	use crate::loot::{LootContext, LootTablePrototype};
+
+	fn carrier() -> Result<Carrier> {
+		rsa_core::initialize(LevelFilter::Off)?;
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["gel"] = { max_stack = 99 },
+				["gem"] = { max_stack = 99 },
+			}
+			reload.registry["loot_table"]:insert {
+				["slime"] = {
+					pools = {
+						{ rolls = { min = 2, max = 4 }, entries = { { item = "gel", amount = { min = 1, max = 3 } } } },
+						{ entries = { { table = "rare" } }, conditions = { zones = { "Underground" } } },
+					},
+				},
+				["rare"] = {
+					pools = { { entries = { { item = "gem", conditions = { luck = 1 } } } } },
+				},
+				["vines"] = {
+					pools = { { entries = { { item = "gem" } }, conditions = { biomes = { "jungle" } } } },
+				},
+				["loop"] = {
+					pools = { { entries = { { table = "loop" } } } },
+				},
+			}
+			"#,
+		);
+		reload!((ItemPrototype, LootTablePrototype) => api);
+		Ok(api.get_carrier())
+	}
+
+	fn roll(carrier: &Carrier, table: &str, context: &LootContext, seed: u64) -> Vec<(String, u32)> {
+		let table = carrier
+			.get::<LootTablePrototype>()
+			.create_from_tag(&Tag::new(format!("hello:{table}")).unwrap())
+			.unwrap();
+		let items = carrier.get::<ItemPrototype>();
+		table
+			.roll(carrier, context, &mut SmallRng::seed_from_u64(seed))
+			.iter()
+			.map(|stack| (items.tag_from_id(stack.item().ty).to_string(), stack.amount()))
+			.collect()
+	}
+
+	#[test]
+	fn same_seed_same_loot() -> Result<()> {
+		let carrier = carrier()?;
+		let context = LootContext::default();
+		for seed in 0..20 {
+			let loot = roll(&carrier, "slime", &context, seed);
+			assert_eq!(loot, roll(&carrier, "slime", &context, seed));
+			assert!((2..=4).contains(&loot.len()));
+			assert!(loot.iter().all(|(item, amount)| item == "hello:gel" && (1..=3).contains(amount)));
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn conditions_and_nesting() -> Result<()> {
+		let carrier = carrier()?;
+		let gems = |context: &LootContext| {
+			roll(&carrier, "slime", context, 0)
+				.iter()
+				.filter(|(item, _)| item == "hello:gem")
+				.count()
+		};
+
+		let underground = LootContext {
+			zone: Some(SpawnZone::Underground),
+			..LootContext::default()
+		};
+		assert_eq!(gems(&underground), 0);
+		assert_eq!(gems(&LootContext { luck: 1.0, ..LootContext::default() }), 0);
+		assert_eq!(gems(&LootContext { luck: 1.0, ..underground }), 1);
+
+		// Tables referencing themselves stop at some point.
+		assert!(roll(&carrier, "loop", &LootContext::default(), 0).is_empty());
+		Ok(())
+	}
+
+	#[test]
+	fn biomes() -> Result<()> {
+		let carrier = carrier()?;
+		let in_biome = |biome: Option<&str>| {
+			let context = LootContext {
+				biome: biome.map(|biome| Tag::new(biome).unwrap()),
+				..LootContext::default()
+			};
+			roll(&carrier, "vines", &context, 0)
+		};
+
+		assert_eq!(in_biome(Some("hello:jungle")), vec![("hello:gem".to_string(), 1)]);
+		assert!(in_biome(Some("hello:desert")).is_empty());
+		assert!(in_biome(None).is_empty());
+		Ok(())
+	}
+}
@@ -6,11 +6,13 @@ use rayon::ThreadPool;
 
 use rsa_core::api::{Api, Reloadable};
 use rsa_core::error::Result;
+use rsa_core::logging::{info, warn};
 use rsa_core::ty::ChunkPos;
 use rsa_network::Token;
 
 use crate::module::chunks::world_generation::WorldGeneration;
 use crate::packet::chunk::ClientChunkPacket;
+use crate::world::{World, DEFAULT_SPAWN_POINT};
 use crate::Server;
 
 mod world_generation;
@@ -55,6 +57,7 @@ impl ChunkModule {
 			server.world.chunks.put_chunk(pos, chunk);
 		});
 
+		this.dirty_chunks.extend(server.world.chunks.take_dirty());
 		for pos in this.dirty_chunks.drain() {
 			if let Some(chunk) = server.world.chunks.get_chunk(pos) {
 				server.network.send_chunk(None, pos, chunk.clone());
@@ -64,6 +67,22 @@ impl ChunkModule {
 		Ok(())
 	}
 
+	/// Finds the world spawn if the world does not have one yet.
+	/// If the generator can not find one, players use [`DEFAULT_SPAWN_POINT`] and the next reload tries again.
+	pub fn init_spawn_point(&self, world: &mut World) {
+		if world.spawn_point.is_none() {
+			match self.generator.find_spawn_point() {
+				Ok(spawn_point) => {
+					info!(target: "reload@rustaria.chunk", "World spawn is at {spawn_point:?}");
+					world.spawn_point = Some(spawn_point);
+				}
+				Err(error) => {
+					warn!(target: "reload@rustaria.chunk", "Could not find a world spawn, using {DEFAULT_SPAWN_POINT:?}: {error:#}");
+				}
+			}
+		}
+	}
+
 	pub fn packet(&mut self, from: Token, packet: ClientChunkPacket) -> Result<()> {
 		match packet {
 			ClientChunkPacket::Request(chunks) => {
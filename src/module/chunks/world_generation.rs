@@ -6,9 +6,10 @@ use rayon::ThreadPool;
 use rsa_core::api::carrier::Carrier;
 use rsa_core::api::{Api, Reloadable};
 
-use rsa_core::error::ContextCompat;
+use rsa_core::error::{bail, ContextCompat};
 use rsa_core::error::Result;
 use rsa_core::logging::error;
+use rsa_core::math::{vec2, Vector2D, WorldSpace};
 use rsa_core::settings::CHUNK_SIZE;
 use rsa_core::ty::{ChunkPos, ChunkSubPos, Tag};
 use crate::CarrierUnavailable;
@@ -17,6 +18,13 @@ use crate::chunk::layer::tile::TilePrototype;
 use crate::chunk::layer::ChunkLayer;
 use crate::chunk::Chunk;
 
+/// The column the world spawn gets searched in.
+const SPAWN_X: u32 = 3;
+/// How many chunks up we look for ground to spawn on.
+const SPAWN_SEARCH_CHUNKS: u32 = 16;
+/// How many air tiles a spawn point needs above the ground.
+const SPAWN_CLEARANCE: usize = 3;
+
 pub struct WorldGeneration {
 	carrier: Option<Carrier>,
 	thread_pool: Arc<ThreadPool>,
@@ -68,6 +76,27 @@ impl WorldGeneration {
 		}
 	}
 
+	/// Finds the lowest spot in the spawn column with solid ground below and room to stand above.
+	/// This asks the generator directly so it works before any chunk got requested.
+	pub fn find_spawn_point(&self) -> Result<Vector2D<f32, WorldSpace>> {
+		let carrier = self.carrier.as_ref().wrap_err(CarrierUnavailable)?;
+		let chunk_x = SPAWN_X / CHUNK_SIZE as u32;
+		let sub_x = (SPAWN_X % CHUNK_SIZE as u32) as u8;
+
+		let mut column = Vec::new();
+		for chunk_y in 0..SPAWN_SEARCH_CHUNKS {
+			let chunk = generate_chunk(carrier, ChunkPos { x: chunk_x, y: chunk_y })?;
+			column.extend((0..CHUNK_SIZE).map(|y| chunk.tiles[ChunkSubPos::new(sub_x, y as u8)].collision));
+
+			let end = column.len().saturating_sub(SPAWN_CLEARANCE);
+			if let Some(y) = (1..=end).find(|&y| column[y - 1] && column[y..y + SPAWN_CLEARANCE].iter().all(|solid| !solid)) {
+				return Ok(vec2(SPAWN_X as f32, y as f32));
+			}
+		}
+
+		bail!("Could not find ground to spawn on")
+	}
+
 	// we should prob convert chunks incase a new entry now exists.
 	// that needs world saving logic however sooooo
 	pub fn reload(&mut self, api: &Api) {
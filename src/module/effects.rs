@@ -0,0 +1,280 @@
+use mlua::Function;
+
+use rsa_core::api::carrier::Carrier;
+use rsa_core::api::lua::error::LuaError;
+use rsa_core::api::Api;
+use rsa_core::error::{ContextCompat, Result};
+use rsa_core::logging::warn;
+use rsa_core::registry::Registry;
+
+use crate::entity::behaviour::EntityLease;
+use crate::entity::component::effect::{ActiveEffect, EffectsComp};
+use crate::entity::component::stats::StatsComp;
+use crate::entity::packet::ServerEntityPacket;
+use crate::entity::status_effect::{EffectStacking, StatusEffectPrototype};
+use crate::entity::Entity;
+use crate::{CarrierUnavailable, Server, ServerPacket};
+
+/// The EffectModule ticks the status effects on entities, runs their hooks and keeps the stats of the entity up to date.
+pub struct EffectModule {
+	carrier: Option<Carrier>,
+}
+
+impl EffectModule {
+	pub fn new() -> EffectModule {
+		EffectModule { carrier: None }
+	}
+
+	#[macro_module::module(server.effect)]
+	pub fn tick(this: &mut EffectModule, server: &mut Server) -> Result<()> {
+		let carrier = this.carrier.as_ref().wrap_err(CarrierUnavailable)?;
+		let registry = carrier.get::<StatusEffectPrototype>();
+		let entities = &mut server.world.entities;
+
+		let mut affected: Vec<Entity> = entities
+			.query::<&EffectsComp>()
+			.iter()
+			.map(|(entity, _)| entity)
+			.collect();
+		affected.sort();
+
+		for entity in affected {
+			// Hooks can touch the entity, so they only run after we are done with the component.
+			let mut hooks = Vec::new();
+			let (changed, effects, modifiers) = {
+				let mut comp = match entities.get_mut::<EffectsComp>(entity) {
+					Ok(comp) => comp,
+					Err(_) => continue,
+				};
+
+				let mut changed = Self::resolve(&registry, &mut comp, &mut hooks);
+
+				let mut i = 0;
+				while i < comp.effects.len() {
+					let effect = &mut comp.effects[i];
+					let prototype = registry.prototype_from_id(effect.id);
+					effect.age += 1;
+					effect.remaining = effect.remaining.saturating_sub(1);
+
+					if let (Some(interval), Some(func)) = (prototype.tick_interval, &prototype.on_tick) {
+						if interval != 0 && effect.age % interval == 0 {
+							hooks.push(func.clone());
+						}
+					}
+
+					if effect.remaining == 0 {
+						hooks.extend(prototype.on_remove.clone());
+						comp.effects.remove(i);
+						changed = true;
+					} else {
+						i += 1;
+					}
+				}
+
+				(changed, comp.effects.clone(), comp.modifiers(&registry))
+			};
+
+			EntityLease::scope(entities, |lease| {
+				hooks
+					.into_iter()
+					.try_for_each(|func| func.call::<_, ()>(lease.handle(entity)))
+			})
+			.lua_err()?;
+
+			// Equipment modifiers are kept, only the effect ones change.
+			StatsComp::modify(carrier, entities, entity, |stats| stats.set_effects(modifiers));
+			if changed {
+				server
+					.network
+					.send_all(ServerPacket::Entity(ServerEntityPacket::Effects(entity, effects)))?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Applies and removes the effects which got requested during this tick, returns if anything changed.
+	fn resolve(
+		registry: &Registry<StatusEffectPrototype>,
+		comp: &mut EffectsComp,
+		hooks: &mut Vec<Function>,
+	) -> bool {
+		let mut changed = false;
+		for (tag, duration) in std::mem::take(&mut comp.pending) {
+			let id = match registry.id_from_tag(&tag) {
+				Ok(id) => id,
+				Err(error) => {
+					warn!(target: "tick@rustaria.effect", "Could not apply effect {error}");
+					continue;
+				}
+			};
+
+			let prototype = registry.prototype_from_id(id);
+			let duration = duration.unwrap_or(prototype.duration);
+			match comp.effects.iter_mut().find(|effect| effect.id == id) {
+				None => {
+					comp.effects.push(ActiveEffect {
+						id,
+						remaining: duration,
+						stacks: 1,
+						age: 0,
+					});
+					hooks.extend(prototype.on_apply.clone());
+				}
+				Some(effect) => match prototype.stacking {
+					EffectStacking::Refresh => effect.remaining = duration,
+					EffectStacking::Extend => effect.remaining = effect.remaining.saturating_add(duration),
+					EffectStacking::Stack => {
+						let max = prototype.max_stacks.unwrap_or(u32::MAX);
+						if effect.stacks < max {
+							effect.stacks += 1;
+							hooks.extend(prototype.on_apply.clone());
+						}
+						effect.remaining = duration;
+					}
+					EffectStacking::Ignore => continue,
+				},
+			}
+			changed = true;
+		}
+
+		for tag in std::mem::take(&mut comp.removing) {
+			let id = match registry.id_from_tag(&tag) {
+				Ok(id) => id,
+				Err(_) => continue,
+			};
+
+			if let Some(index) = comp.effects.iter().position(|effect| effect.id == id) {
+				comp.effects.remove(index);
+				hooks.extend(registry.prototype_from_id(id).on_remove.clone());
+				changed = true;
+			}
+		}
+
+		changed
+	}
+
+	pub fn reload(&mut self, api: &Api) {
+		self.carrier = Some(api.get_carrier());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::logging::LevelFilter;
+	use rsa_core::registry::Registry;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+
+	use crate::entity::component::effect::EffectsComp;
+	use crate::entity::status_effect::StatusEffectPrototype;
+	use crate::module::effects::EffectModule;
+
+	fn api() -> Result<Api> {
+		rsa_core::initialize(LevelFilter::Off)?;
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["status_effect"]:insert {
+				["refresh"] = { duration = 100, stacking = "Refresh" },
+				["extend"] = { duration = 100, stacking = "Extend" },
+				["stack"] = { duration = 100, stacking = "Stack", max_stacks = 2 },
+				["ignore"] = { duration = 100, stacking = "Ignore" },
+			}
+			"#,
+		);
+		reload!((StatusEffectPrototype) => api);
+		Ok(api)
+	}
+
+	fn apply(registry: &Registry<StatusEffectPrototype>, comp: &mut EffectsComp, name: &str, duration: Option<u32>) -> bool {
+		comp.pending.push((Tag::new(format!("hello:{name}")).unwrap(), duration));
+		EffectModule::resolve(registry, comp, &mut Vec::new())
+	}
+
+	/// (remaining, stacks) of the only effect.
+	fn state(comp: &EffectsComp) -> (u32, u32) {
+		assert_eq!(comp.effects.len(), 1);
+		(comp.effects[0].remaining, comp.effects[0].stacks)
+	}
+
+	#[test]
+	fn refresh_resets_the_time() -> Result<()> {
+		let api = api()?;
+		let carrier = api.get_carrier();
+		let registry = carrier.get::<StatusEffectPrototype>();
+		let mut comp = EffectsComp::default();
+
+		assert!(apply(&registry, &mut comp, "refresh", None));
+		comp.effects[0].remaining = 10;
+		assert!(apply(&registry, &mut comp, "refresh", None));
+		assert_eq!(state(&comp), (100, 1));
+		Ok(())
+	}
+
+	#[test]
+	fn extend_adds_up() -> Result<()> {
+		let api = api()?;
+		let carrier = api.get_carrier();
+		let registry = carrier.get::<StatusEffectPrototype>();
+		let mut comp = EffectsComp::default();
+
+		assert!(apply(&registry, &mut comp, "extend", Some(50)));
+		assert!(apply(&registry, &mut comp, "extend", None));
+		assert_eq!(state(&comp), (150, 1));
+
+		// Does not wrap around to almost nothing.
+		comp.effects[0].remaining = u32::MAX - 10;
+		assert!(apply(&registry, &mut comp, "extend", None));
+		assert_eq!(state(&comp), (u32::MAX, 1));
+		Ok(())
+	}
+
+	#[test]
+	fn stacks_up_to_the_limit() -> Result<()> {
+		let api = api()?;
+		let carrier = api.get_carrier();
+		let registry = carrier.get::<StatusEffectPrototype>();
+		let mut comp = EffectsComp::default();
+
+		for _ in 0..3 {
+			assert!(apply(&registry, &mut comp, "stack", Some(40)));
+			comp.effects[0].remaining = 5;
+		}
+		assert!(apply(&registry, &mut comp, "stack", None));
+		assert_eq!(state(&comp), (100, 2));
+		Ok(())
+	}
+
+	#[test]
+	fn ignore_keeps_the_effect() -> Result<()> {
+		let api = api()?;
+		let carrier = api.get_carrier();
+		let registry = carrier.get::<StatusEffectPrototype>();
+		let mut comp = EffectsComp::default();
+
+		assert!(apply(&registry, &mut comp, "ignore", Some(30)));
+		assert!(!apply(&registry, &mut comp, "ignore", None));
+		assert_eq!(state(&comp), (30, 1));
+		Ok(())
+	}
+
+	#[test]
+	fn unknown_and_removed_effects() -> Result<()> {
+		let api = api()?;
+		let carrier = api.get_carrier();
+		let registry = carrier.get::<StatusEffectPrototype>();
+		let mut comp = EffectsComp::default();
+
+		assert!(!apply(&registry, &mut comp, "gone", None));
+		assert!(comp.effects.is_empty());
+
+		assert!(apply(&registry, &mut comp, "refresh", None));
+		comp.removing.push(Tag::new("hello:refresh")?);
+		assert!(EffectModule::resolve(&registry, &mut comp, &mut Vec::new()));
+		assert!(comp.effects.is_empty());
+		Ok(())
+	}
+}
@@ -1,12 +1,28 @@
+use mlua::Function;
 use rsa_core::api::carrier::Carrier;
+use rsa_core::api::lua::error::LuaError;
 use rsa_core::api::{Api, Reloadable};
-use rsa_core::error::Result;
+use rsa_core::error::{ContextCompat, Result};
+use rsa_core::registry::Registry;
+use rsa_core::ty::Tag;
 use rsa_network::Token;
 use rsa_network::tunnel::MapTunnel;
 
-use crate::entity::packet::ClientEntityPacket;
+use crate::entity::behaviour::{Behaviour, EntityLease};
+use crate::entity::component::animation::AnimationComp;
+use crate::entity::component::custom::CustomComps;
+use crate::entity::component::effect::EffectsComp;
+use crate::entity::component::hitbox::HitboxComp;
+use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::pos::PositionComp;
+use crate::entity::component::prototype::PrototypeComp;
+use crate::entity::component::respawn::RespawnComp;
+use crate::entity::event::{EntityEvent, ProjectileTarget};
+use crate::entity::packet::{ClientEntityPacket, ServerEntityPacket};
+use crate::entity::prototype::EntityPrototype;
 use crate::entity::systems::server_network::ServerNetworkECSystem;
-use crate::Server;
+use crate::entity::{Entity, EntitySystem};
+use crate::{CarrierUnavailable, NetworkModule, Server, ServerPacket};
 
 pub struct EntityModule {
 	carrier: Option<Carrier>,
@@ -24,6 +40,152 @@ impl EntityModule {
 	#[macro_module::module(server.entity)]
 	pub fn tick(this: &mut EntityModule, server: &mut Server) -> Result<()> {
 		this.network.tick(&mut server.world.entities, &mut server.network.map())?;
+
+		let carrier = this.carrier.as_ref().wrap_err(CarrierUnavailable)?;
+		let prototypes = carrier.get::<EntityPrototype>();
+		let entities = &mut server.world.entities;
+
+		// Behaviours can spawn things, so dont hold on to the events.
+		for event in entities.events().to_vec() {
+			match event {
+				EntityEvent::Spawn(entity) => {
+					Self::behave(&prototypes, entities, entity, None, |b| b.spawn.as_ref())?
+				}
+				EntityEvent::Death(entity) => {
+					Self::behave(&prototypes, entities, entity, None, |b| b.death.as_ref())?;
+					// Respawning entities stick around, whoever owns them brings them back.
+					if entities.get::<RespawnComp>(entity).is_err() {
+						server
+							.network
+							.send_all(ServerPacket::Entity(ServerEntityPacket::Kill(entity)))?;
+					}
+				}
+				EntityEvent::ContactBegin(e1, e2) => EntityLease::scope(entities, |lease| {
+					server.api.invoke_hook(&Tag::rsa("entity_contact_begin"), || {
+						(lease.handle(e1), lease.handle(e2))
+					})
+				})?,
+				EntityEvent::ContactEnd(e1, e2) => EntityLease::scope(entities, |lease| {
+					server.api.invoke_hook(&Tag::rsa("entity_contact_end"), || {
+						(lease.handle(e1), lease.handle(e2))
+					})
+				})?,
+				EntityEvent::ProjectileHit(projectile, ProjectileTarget::Entity(target)) => {
+					Self::behave(&prototypes, entities, target, Some(projectile), |b| b.hit.as_ref())?;
+					EntityLease::scope(entities, |lease| {
+						server.api.invoke_hook(&Tag::rsa("projectile_hit_entity"), || {
+							(lease.handle(projectile), lease.handle(target))
+						})
+					})?
+				}
+				EntityEvent::ProjectileHit(projectile, ProjectileTarget::Tile(pos)) => {
+					EntityLease::scope(entities, |lease| {
+						server.api.invoke_hook(&Tag::rsa("projectile_hit_tile"), || {
+							(lease.handle(projectile), pos.x(), pos.y())
+						})
+					})?
+				}
+				// Picking items up is done by the ItemModule.
+				EntityEvent::ItemTouch(..) => {}
+			}
+		}
+
+		let mut ticking: Vec<Entity> = entities
+			.query::<&PrototypeComp>()
+			.iter()
+			.filter(|(_, prototype)| {
+				let behaviour = &prototypes.prototype_from_id(prototype.0).behaviour;
+				matches!(behaviour, Some(Behaviour { tick: Some(_), .. }))
+			})
+			.map(|(entity, _)| entity)
+			.collect();
+		ticking.sort();
+
+		for entity in ticking {
+			Self::behave(&prototypes, entities, entity, None, |b| b.tick.as_ref())?;
+		}
+
+		for (entity, (animation, physics, hitbox)) in entities.query_mut::<(
+			&mut AnimationComp,
+			Option<&PhysicsComp>,
+			Option<&HitboxComp>,
+		)>() {
+			if animation.select(physics, hitbox) {
+				server.network.send_all(ServerPacket::Entity(ServerEntityPacket::Animation(
+					entity,
+					animation.state.clone(),
+				)))?;
+			}
+		}
+
+		for (entity, comps) in entities.query_mut::<&mut CustomComps>() {
+			for name in std::mem::take(&mut comps.dirty) {
+				let value = comps.get(&name).cloned().unwrap_or_default();
+				server.network.send_all(ServerPacket::Entity(ServerEntityPacket::Component(
+					entity, name, value,
+				)))?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Tells a client that just joined about every entity and the state it is in.
+	pub fn send_world(entities: &EntitySystem, network: &NetworkModule, to: Token) -> Result<()> {
+		for (entity, (prototype, position, animation, comps, effects)) in entities
+			.query::<(
+				&PrototypeComp,
+				&PositionComp,
+				Option<&AnimationComp>,
+				Option<&CustomComps>,
+				Option<&EffectsComp>,
+			)>()
+			.iter()
+		{
+			network.send(to, ServerPacket::Entity(ServerEntityPacket::Spawn(0, entity, prototype.0)))?;
+			network.send(to, ServerPacket::Entity(ServerEntityPacket::Pos(0, entity, position.position)))?;
+			if let Some(animation) = animation {
+				network.send(to, ServerPacket::Entity(ServerEntityPacket::Animation(entity, animation.state.clone())))?;
+			}
+			if let Some(comps) = comps {
+				for (name, value) in comps.replicated() {
+					network.send(
+						to,
+						ServerPacket::Entity(ServerEntityPacket::Component(entity, name.clone(), value.clone())),
+					)?;
+				}
+			}
+			if let Some(effects) = effects.filter(|effects| !effects.effects.is_empty()) {
+				network.send(
+					to,
+					ServerPacket::Entity(ServerEntityPacket::Effects(entity, effects.effects.clone())),
+				)?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Runs a function of the behaviour of `entity` if its prototype has one.
+	fn behave(
+		prototypes: &Registry<EntityPrototype>,
+		entities: &mut EntitySystem,
+		entity: Entity,
+		other: Option<Entity>,
+		func: impl FnOnce(&Behaviour) -> Option<&Function>,
+	) -> Result<()> {
+		let id = match entities.get::<PrototypeComp>(entity) {
+			Ok(prototype) => prototype.0,
+			// Already gone.
+			Err(_) => return Ok(()),
+		};
+
+		if let Some(func) = prototypes.prototype_from_id(id).behaviour.as_ref().and_then(func) {
+			EntityLease::scope(entities, |lease| match other {
+				Some(other) => func.call::<_, ()>((lease.handle(entity), lease.handle(other))),
+				None => func.call::<_, ()>(lease.handle(entity)),
+			})
+			.lua_err()?;
+		}
+
 		Ok(())
 	}
 
@@ -38,6 +200,10 @@ impl EntityModule {
 			.packet(&server.player, &mut server.world.entities, &token, packet)
 	}
 
+	pub fn leave(&mut self, token: Token) {
+		self.network.leave(token);
+	}
+
 	pub fn reload(&mut self, api: &Api) {
 		self.carrier = Some(api.get_carrier());
 	}
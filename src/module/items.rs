@@ -0,0 +1,981 @@
+use std::collections::HashSet;
+
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use rsa_core::api::carrier::Carrier;
+use rsa_core::api::Api;
+use rsa_core::error::{ContextCompat, Result};
+use rsa_core::logging::warn;
+use rsa_core::math::{point2, size2, vec2, Rect, Vector2D, WorldSpace};
+use rsa_core::ty::{RawId, Tag, TilePos};
+use rsa_item::item::{ItemPrototype, UseStyle};
+use rsa_item::stack::ItemStack;
+
+use crate::chunk::layer::tile::TilePrototype;
+use crate::entity::component::contact::ContactComp;
+use crate::entity::component::effect::EffectsComp;
+use crate::entity::component::health::HealthComp;
+use crate::entity::component::item::ItemComp;
+use crate::entity::component::prototype::PrototypeComp;
+use crate::entity::component::respawn::RespawnComp;
+use crate::entity::component::stats::StatsComp;
+use crate::entity::event::EntityEvent;
+use crate::entity::spawn_rule::SpawnZone;
+use crate::entity::packet::ServerEntityPacket;
+use crate::entity::prototype::EntityPrototype;
+use crate::entity::Entity;
+use crate::loot::{LootContext, LootTablePrototype};
+use crate::world::biome::{biome_at, BiomePrototype};
+use crate::{CarrierUnavailable, NetworkModule, Server, ServerPacket, World};
+
+/// How close items of the same kind need to be to merge into one stack.
+const MERGE_RANGE: f32 = 1.5;
+/// How many ticks a dropped item waits before it can be picked up, so you dont instantly pick up what you threw away.
+pub const DROP_PICKUP_DELAY: f32 = 60.0;
+/// How far from the user items can be used, targets further away get pulled in.
+pub const USE_REACH: f32 = 6.0;
+/// How far around the target a swing hits.
+const SWING_RADIUS: f32 = 1.5;
+/// Thrusts hit a smaller area, but reach this much further.
+const THRUST_RADIUS: f32 = 0.75;
+const THRUST_REACH: f32 = 1.5;
+/// How far up loot looks for tiles to tell how deep underground it dropped.
+const ZONE_SCAN: i64 = 32;
+
+/// The ItemModule handles items lying around in the world, merging them and putting them into inventories.
+pub struct ItemModule {
+	carrier: Option<Carrier>,
+	item_entity: Option<RawId>,
+	air_tile: Option<RawId>,
+	rng: SmallRng,
+}
+
+impl ItemModule {
+	/// Loot rolls come from `seed`, which should be the world seed.
+	pub fn new(seed: u64) -> ItemModule {
+		ItemModule {
+			carrier: None,
+			item_entity: None,
+			air_tile: None,
+			rng: SmallRng::seed_from_u64(seed),
+		}
+	}
+
+	/// Spawns an item entity holding `stack` and tells the clients about it.
+	pub fn spawn_item(
+		&self,
+		world: &mut World,
+		network: &NetworkModule,
+		position: Vector2D<f32, WorldSpace>,
+		stack: ItemStack,
+		pickup_delay: f32,
+	) -> Result<Entity> {
+		let carrier = self.carrier.as_ref().wrap_err(CarrierUnavailable)?;
+		let id = self.item_entity.wrap_err(CarrierUnavailable)?;
+		let entity = world.entities.spawn_item(
+			position,
+			stack,
+			pickup_delay,
+			id,
+			carrier.get::<EntityPrototype>().prototype_from_id(id),
+		);
+
+		network.send_all(ServerPacket::Entity(ServerEntityPacket::Spawn(0, entity, id)))?;
+		network.send_all(ServerPacket::Entity(ServerEntityPacket::Pos(0, entity, position)))?;
+		Ok(entity)
+	}
+
+	/// Uses the stack in `slot` towards `target`, taking an item off it if the item gets used up.
+	/// Returns how many ticks the use takes, `None` if nothing could be done.
+	pub fn use_item(
+		&mut self,
+		world: &mut World,
+		network: &NetworkModule,
+		user: Entity,
+		slot: &mut Option<ItemStack>,
+		target: Vector2D<f32, WorldSpace>,
+	) -> Result<Option<u32>> {
+		let carrier = self.carrier.clone().wrap_err(CarrierUnavailable)?;
+		let (tag, prototype) = match slot {
+			Some(stack) => {
+				let registry = carrier.get::<ItemPrototype>();
+				let id = stack.item().ty;
+				(registry.tag_from_id(id).clone(), registry.prototype_from_id(id).clone())
+			}
+			None => return Ok(None),
+		};
+		let style = match prototype.use_style {
+			Some(style) => style,
+			None => return Ok(None),
+		};
+		let origin = match world.entities.spatial().get_bounds(user) {
+			Some(bounds) => bounds.center().to_vector(),
+			None => return Ok(None),
+		};
+
+		let reach = match style {
+			UseStyle::Thrust => USE_REACH * THRUST_REACH,
+			_ => USE_REACH,
+		};
+		let offset = target - origin;
+		let target = if offset.length() > reach {
+			origin + offset.normalize() * reach
+		} else {
+			target
+		};
+
+		let used = match style {
+			UseStyle::Swing | UseStyle::Thrust => {
+				let radius = if style == UseStyle::Swing { SWING_RADIUS } else { THRUST_RADIUS };
+				if let Some(damage) = prototype.damage {
+					Self::hit(world, user, target, radius, damage);
+				}
+				if let Some(power) = prototype.tool_power {
+					self.mine(&carrier, world, network, target, power, tag)?;
+				}
+				// Swinging at nothing still takes time.
+				true
+			}
+			UseStyle::Shoot => Self::shoot(&carrier, world, network, user, origin, offset, prototype.projectile.as_ref())?,
+			UseStyle::PlaceTile => self.place(&carrier, world, user, target, prototype.tile.as_ref())?,
+			UseStyle::Consume => Self::consume(world, user, prototype.effect.as_ref()),
+		};
+
+		if !used {
+			return Ok(None);
+		}
+
+		if prototype.consumes() {
+			if let Some(stack) = slot {
+				if stack.split(1).is_none() {
+					*slot = None;
+				}
+			}
+		}
+		Ok(Some(prototype.use_time()))
+	}
+
+	/// Damages everything within `radius` of `target` that the contact layers of `user` let it touch.
+	fn hit(world: &mut World, user: Entity, target: Vector2D<f32, WorldSpace>, radius: f32, damage: f32) {
+		let contact = world.entities.get::<ContactComp>(user).ok().map(|comp| (*comp).clone());
+		for entity in world.entities.query_radius(target, radius) {
+			let dead = world.entities.get::<RespawnComp>(entity).map_or(false, |comp| comp.dead);
+			let touches = ContactComp::may_interact(
+				contact.as_ref(),
+				world.entities.get::<ContactComp>(entity).ok().as_deref(),
+			);
+			if entity == user || dead || !touches {
+				continue;
+			}
+
+			let amount = match world.entities.get::<StatsComp>(entity) {
+				Ok(stats) => stats.modifiers.damage(damage),
+				Err(_) => damage,
+			};
+			if let Ok(mut health) = world.entities.get_mut::<HealthComp>(entity) {
+				health.damage(amount);
+			}
+		}
+	}
+
+	/// Breaks the tile at `target` if `power` is enough and drops its loot table, or its item if it has none.
+	fn mine(
+		&mut self,
+		carrier: &Carrier,
+		world: &mut World,
+		network: &NetworkModule,
+		target: Vector2D<f32, WorldSpace>,
+		power: u32,
+		tool: Tag,
+	) -> Result<()> {
+		let air = self.air_tile.wrap_err(CarrierUnavailable)?;
+		let pos = match tile_pos(target) {
+			Some(pos) => pos,
+			None => return Ok(()),
+		};
+		let id = match world.chunks.get_tile(pos) {
+			Some(tile) if tile.id != air => tile.id,
+			_ => return Ok(()),
+		};
+
+		let tiles = carrier.get::<TilePrototype>();
+		let prototype = tiles.prototype_from_id(id);
+		if prototype.hardness.map_or(false, |hardness| hardness > power) {
+			return Ok(());
+		}
+
+		world.chunks.set_tile(pos, tiles.create_from_id(air));
+		let center = vec2(pos.x() as f32 + 0.5, pos.y() as f32 + 0.5);
+		if let Some(table) = &prototype.loot_table {
+			let context = loot_context(carrier, world, center, Some(tool));
+			self.drop_loot(carrier, world, network, table, center, &context)?;
+		} else if let Some(tag) = &prototype.item {
+			match carrier.get::<ItemPrototype>().create_from_tag(tag) {
+				Ok(item) => {
+					self.spawn_item(world, network, center, ItemStack::new(item, None), 0.0)?;
+				}
+				Err(_) => warn!(target: "tick@rustaria.item", "Tile {} drops unknown item {tag}", tiles.tag_from_id(id)),
+			}
+		}
+		Ok(())
+	}
+
+	/// Rolls a loot table and drops whatever came out at `position`.
+	pub fn drop_loot(
+		&mut self,
+		carrier: &Carrier,
+		world: &mut World,
+		network: &NetworkModule,
+		table: &Tag,
+		position: Vector2D<f32, WorldSpace>,
+		context: &LootContext,
+	) -> Result<()> {
+		let table = match carrier.get::<LootTablePrototype>().create_from_tag(table) {
+			Ok(table) => table,
+			Err(_) => {
+				warn!(target: "tick@rustaria.item", "Unknown loot table {table}");
+				return Ok(());
+			}
+		};
+
+		for stack in table.roll(carrier, context, &mut self.rng) {
+			self.spawn_item(world, network, position, stack, 0.0)?;
+		}
+		Ok(())
+	}
+
+	fn shoot(
+		carrier: &Carrier,
+		world: &mut World,
+		network: &NetworkModule,
+		user: Entity,
+		origin: Vector2D<f32, WorldSpace>,
+		direction: Vector2D<f32, WorldSpace>,
+		projectile: Option<&Tag>,
+	) -> Result<bool> {
+		let tag = match projectile {
+			Some(tag) => tag,
+			None => return Ok(false),
+		};
+		let registry = carrier.get::<EntityPrototype>();
+		let id = match registry.id_from_tag(tag) {
+			Ok(id) => id,
+			Err(_) => {
+				warn!(target: "tick@rustaria.item", "Item shoots unknown entity {tag}");
+				return Ok(false);
+			}
+		};
+
+		let entity = match world.entities.spawn_projectile(origin, direction, Some(user), id, registry.prototype_from_id(id)) {
+			Some(entity) => entity,
+			// Either aimed at itself or the entity is not a projectile.
+			None => return Ok(false),
+		};
+		network.send_all(ServerPacket::Entity(ServerEntityPacket::Spawn(0, entity, id)))?;
+		network.send_all(ServerPacket::Entity(ServerEntityPacket::Pos(0, entity, origin)))?;
+		Ok(true)
+	}
+
+	/// Places `tile` at `target` if there is only air. Placing a bed moves the spawn point of `user` onto it.
+	fn place(
+		&self,
+		carrier: &Carrier,
+		world: &mut World,
+		user: Entity,
+		target: Vector2D<f32, WorldSpace>,
+		tile: Option<&Tag>,
+	) -> Result<bool> {
+		let air = self.air_tile.wrap_err(CarrierUnavailable)?;
+		let tag = match tile {
+			Some(tag) => tag,
+			None => return Ok(false),
+		};
+		let tiles = carrier.get::<TilePrototype>();
+		let (tile, bed) = match tiles.id_from_tag(tag) {
+			Ok(id) => (tiles.create_from_id(id), tiles.prototype_from_id(id).bed),
+			Err(_) => {
+				warn!(target: "tick@rustaria.item", "Item places unknown tile {tag}");
+				return Ok(false);
+			}
+		};
+
+		let pos = match tile_pos(target) {
+			Some(pos) => pos,
+			None => return Ok(false),
+		};
+		if world.chunks.get_tile(pos).map_or(true, |old| old.id != air) {
+			return Ok(false);
+		}
+
+		// Solid tiles would trap whatever is standing there.
+		if tile.collision {
+			let area = Rect::new(point2(pos.x() as f32, pos.y() as f32), size2(1.0, 1.0));
+			if !world.entities.query_area(area).is_empty() {
+				return Ok(false);
+			}
+		}
+
+		world.chunks.set_tile(pos, tile);
+		if bed {
+			if let Ok(mut comp) = world.entities.get_mut::<RespawnComp>(user) {
+				comp.spawn_point = Some(vec2(pos.x() as f32, pos.y() as f32));
+			}
+		}
+		Ok(true)
+	}
+
+	fn consume(world: &mut World, user: Entity, effect: Option<&Tag>) -> bool {
+		let effect = match effect {
+			Some(effect) => effect,
+			None => return false,
+		};
+
+		if world.entities.get::<EffectsComp>(user).is_err() {
+			world.entities.add(user, EffectsComp::default());
+		}
+		match world.entities.get_mut::<EffectsComp>(user) {
+			Ok(mut effects) => {
+				effects.pending.push((effect.clone(), None));
+				true
+			}
+			Err(_) => false,
+		}
+	}
+
+	#[macro_module::module(server.item)]
+	pub fn tick(this: &mut ItemModule, server: &mut Server) -> Result<()> {
+		let carrier = &this.carrier.clone().wrap_err(CarrierUnavailable)?;
+		this.drop_death_loot(carrier, &mut server.world, &server.network)?;
+
+		let mut removed = HashSet::new();
+		this.merge(carrier, &mut server.world, &mut removed);
+
+		let touches: Vec<(Entity, Entity)> = server
+			.world
+			.entities
+			.events()
+			.iter()
+			.filter_map(|event| match *event {
+				EntityEvent::ItemTouch(item, collector) => Some((item, collector)),
+				_ => None,
+			})
+			.collect();
+
+		for (item, collector) in touches {
+			if removed.contains(&item) {
+				continue;
+			}
+
+			let player = match server.player.get_player_by_entity_mut(collector) {
+				Some(player) => player,
+				// Only players have somewhere to put items.
+				None => continue,
+			};
+
+			let stack = match server.world.entities.get::<ItemComp>(item) {
+				Ok(comp) => comp.stack.clone(),
+				Err(_) => continue,
+			};
+
+			match player.inventory.give(carrier, stack) {
+				Some(leftover) => {
+					if let Ok(mut comp) = server.world.entities.get_mut::<ItemComp>(item) {
+						comp.stack = leftover;
+					}
+				}
+				None => {
+					removed.insert(item);
+				}
+			}
+		}
+
+		for item in removed {
+			server.world.entities.kill_later(item);
+			server
+				.network
+				.send_all(ServerPacket::Entity(ServerEntityPacket::Kill(item)))?;
+		}
+
+		Ok(())
+	}
+
+	/// Entities that died this tick drop their loot table, they are only removed at the start of the next tick.
+	fn drop_death_loot(&mut self, carrier: &Carrier, world: &mut World, network: &NetworkModule) -> Result<()> {
+		let deaths: Vec<Entity> = world
+			.entities
+			.events()
+			.iter()
+			.filter_map(|event| match *event {
+				EntityEvent::Death(entity) => Some(entity),
+				_ => None,
+			})
+			.collect();
+
+		for entity in deaths {
+			// Respawning entities keep what they have.
+			if world.entities.get::<RespawnComp>(entity).is_ok() {
+				continue;
+			}
+
+			let id = match world.entities.get::<PrototypeComp>(entity) {
+				Ok(comp) => comp.0,
+				Err(_) => continue,
+			};
+			let table = match &carrier.get::<EntityPrototype>().prototype_from_id(id).loot_table {
+				Some(table) => table.clone(),
+				None => continue,
+			};
+			let center = match world.entities.spatial().get_bounds(entity) {
+				Some(bounds) => bounds.center().to_vector(),
+				None => continue,
+			};
+
+			let context = loot_context(carrier, world, center, None);
+			self.drop_loot(carrier, world, network, &table, center, &context)?;
+		}
+		Ok(())
+	}
+
+	/// Merges stacks of the same item that are close to each other, the lower entity takes in the higher one.
+	fn merge(&self, carrier: &Carrier, world: &mut World, removed: &mut HashSet<Entity>) {
+		let mut items: Vec<Entity> = world
+			.entities
+			.query::<&ItemComp>()
+			.iter()
+			.map(|(entity, _)| entity)
+			.collect();
+		items.sort();
+
+		for item in items {
+			if removed.contains(&item) {
+				continue;
+			}
+
+			let center = match world.entities.spatial().get_bounds(item) {
+				Some(bounds) => bounds.center().to_vector(),
+				None => continue,
+			};
+
+			for other in world.entities.query_radius(center, MERGE_RANGE) {
+				if other <= item || removed.contains(&other) {
+					continue;
+				}
+
+				let other_stack = match world.entities.get::<ItemComp>(other) {
+					Ok(comp) => comp.stack.clone(),
+					Err(_) => continue,
+				};
+
+				let leftover = match world.entities.get_mut::<ItemComp>(item) {
+					Ok(mut comp) if comp.stack.stacks_with(&other_stack) => {
+						comp.stack.merge(carrier, other_stack)
+					}
+					_ => continue,
+				};
+
+				match leftover {
+					Some(leftover) => {
+						if let Ok(mut comp) = world.entities.get_mut::<ItemComp>(other) {
+							comp.stack = leftover;
+						}
+					}
+					None => {
+						removed.insert(other);
+					}
+				}
+			}
+		}
+	}
+
+	pub fn reload(&mut self, api: &Api) {
+		let carrier = api.get_carrier();
+		let items = carrier.get::<ItemPrototype>();
+		for (id, prototype) in items.iter_ids() {
+			if prototype.max_stack == 0 {
+				warn!(target: "reload@rustaria.item", "Item {} has a max_stack of 0, it can not be picked up", items.tag_from_id(id));
+			}
+		}
+		drop(items);
+
+		self.item_entity = carrier
+			.get::<EntityPrototype>()
+			.id_from_tag(&Tag::rsa("item"))
+			.ok();
+		self.air_tile = carrier
+			.get::<TilePrototype>()
+			.id_from_tag(&Tag::rsa("air"))
+			.ok();
+		self.carrier = Some(carrier);
+	}
+}
+
+fn tile_pos(position: Vector2D<f32, WorldSpace>) -> Option<TilePos> {
+	if position.x < 0.0 || position.y < 0.0 {
+		return None;
+	}
+	TilePos::try_from(position).ok()
+}
+
+/// Where loot at `position` drops, underground if there is something solid above it.
+fn loot_context(
+	carrier: &Carrier,
+	world: &World,
+	position: Vector2D<f32, WorldSpace>,
+	tool: Option<Tag>,
+) -> LootContext {
+	let (x, y) = (position.x.floor(), position.y.floor());
+	let mut depth = 0;
+	for above in 1..=ZONE_SCAN {
+		let tile = match tile_pos(vec2(x, y + above as f32)) {
+			Some(pos) => world.chunks.get_tile(pos),
+			None => None,
+		};
+		match tile {
+			Some(tile) if tile.collision => depth += 1,
+			Some(_) => {}
+			// Unloaded or out of the world counts as sky.
+			None => break,
+		}
+	}
+
+	let zone = if depth == 0 {
+		SpawnZone::Surface
+	} else {
+		SpawnZone::Underground
+	};
+	LootContext {
+		zone: Some(zone),
+		biome: biome_at(&carrier.get::<BiomePrototype>(), x, y, zone, depth),
+		tool,
+		luck: 0.0,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::logging::LevelFilter;
+	use rsa_core::math::{rect, vec2, Vector2D, WorldSpace};
+	use rsa_core::reload;
+	use rsa_core::settings::CHUNK_SIZE;
+	use rsa_core::ty::{ChunkPos, ChunkSubPos, Tag, TilePos};
+	use rsa_item::item::ItemPrototype;
+	use rsa_item::stack::ItemStack;
+
+	use crate::chunk::layer::tile::TilePrototype;
+	use crate::chunk::layer::ChunkLayer;
+	use crate::chunk::Chunk;
+	use crate::entity::component::collector::CollectorComp;
+	use crate::entity::component::contact::ContactComp;
+	use crate::entity::component::effect::EffectsComp;
+	use crate::entity::component::health::HealthComp;
+	use crate::entity::component::hitbox::HitboxComp;
+	use crate::entity::component::item::ItemComp;
+	use crate::entity::component::pos::PositionComp;
+	use crate::entity::component::projectile::ProjectileComp;
+	use crate::entity::event::EntityEvent;
+	use crate::entity::prototype::EntityPrototype;
+	use crate::entity::Entity;
+	use crate::loot::LootTablePrototype;
+	use crate::module::items::{ItemModule, DROP_PICKUP_DELAY};
+	use crate::world::biome::BiomePrototype;
+	use crate::{NetworkModule, ServerNetwork, World};
+
+	struct Setup {
+		items: ItemModule,
+		world: World,
+		network: NetworkModule,
+		user: Entity,
+	}
+
+	impl Setup {
+		fn new() -> Result<Setup> {
+			rsa_core::initialize(LevelFilter::Off)?;
+			let mut api = Api::new_test();
+			api.load_simple_plugin(
+				r#"
+				reload.registry["tile"]:insert {
+					["r:air"] = {},
+					["dirt"] = { collision = true, hardness = 2 },
+					["stone"] = { collision = true, hardness = 5 },
+					["clay"] = { collision = true, hardness = 1, loot_table = "clay" },
+				}
+				reload.registry["item"]:insert {
+					["sword"] = { max_stack = 1, use_style = "Swing", damage = 4 },
+					["spear"] = { max_stack = 1, use_style = "Thrust", damage = 4 },
+					["pickaxe"] = { max_stack = 1, use_style = "Swing", use_time = 15, tool_power = 3 },
+					["dirt"] = { max_stack = 99, use_style = "PlaceTile", tile = "dirt" },
+					["potion"] = { max_stack = 5, use_style = "Consume", effect = "swift" },
+					["bow"] = { max_stack = 1, use_style = "Shoot", projectile = "arrow" },
+					["stick"] = { max_stack = 99 },
+				}
+				reload.registry["entity"]:insert {
+					["r:item"] = {
+						hitbox = { x = 0, y = 0, width = 0.5, height = 0.5 },
+						velocity = { x = 0, y = 0 },
+					},
+					["arrow"] = {
+						hitbox = { x = 0, y = 0, width = 0.5, height = 0.5 },
+						velocity = { x = 0, y = 0 },
+						projectile = { speed = 40, lifetime = 300, pierce = 0, gravity = 0 },
+					},
+				}
+				reload.registry["loot_table"]:insert {
+					["clay"] = {
+						pools = {
+							{ entries = { { item = "stick" } } },
+							{ entries = { { item = "potion" } }, conditions = { biomes = { "plains" } } },
+						},
+					},
+				}
+				reload.registry["biome"]:insert {
+					["plains"] = { x = { max = 7 } },
+				}
+				"#,
+			);
+			reload!((TilePrototype, ItemPrototype, EntityPrototype, LootTablePrototype, BiomePrototype) => api);
+
+			// Dirt below y 4 which turns into stone at x 9.
+			let carrier = api.get_carrier();
+			let tiles = carrier.get::<TilePrototype>();
+			let mut chunk = Chunk {
+				tiles: ChunkLayer::new([[tiles.create_from_tag(&Tag::rsa("air"))?; CHUNK_SIZE]; CHUNK_SIZE]),
+			};
+			for y in 0..4 {
+				for x in 0..CHUNK_SIZE {
+					let tile = if x >= 9 { "hello:stone" } else { "hello:dirt" };
+					chunk.tiles[ChunkSubPos::new(x as u8, y as u8)] = tiles.create_from_tag(&Tag::new(tile)?)?;
+				}
+			}
+			drop(tiles);
+
+			let mut world = World::new();
+			world.chunks.put_chunk(ChunkPos { x: 0, y: 0 }, chunk);
+			let user = world.entities.push((
+				PositionComp {
+					position: vec2(5.0, 4.0),
+				},
+				HitboxComp {
+					hitbox: rect(0.0, 0.0, 1.0, 2.0),
+					touches_ground: true,
+					drop_through: false,
+				},
+			));
+
+			let mut items = ItemModule::new(world.seed);
+			items.reload(&api);
+			Ok(Setup {
+				items,
+				world,
+				network: NetworkModule::new(ServerNetwork {
+					integrated: None,
+					remote: None,
+				}),
+				user,
+			})
+		}
+
+		fn target(&mut self, x: f32, y: f32) -> Entity {
+			self.world.entities.push((
+				PositionComp { position: vec2(x, y) },
+				HitboxComp {
+					hitbox: rect(0.0, 0.0, 1.0, 1.0),
+					touches_ground: true,
+					drop_through: false,
+				},
+				HealthComp {
+					maximum: 10.0,
+					health: 10.0,
+				},
+			))
+		}
+
+		fn stack(&self, item: &str, amount: u32) -> Result<Option<ItemStack>> {
+			let carrier = self.items.carrier.clone().unwrap();
+			let item = carrier.get::<ItemPrototype>().create_from_tag(&Tag::new(format!("hello:{item}"))?)?;
+			Ok(Some(ItemStack::new(item, Some(amount))))
+		}
+
+		/// The spatial index only picks up new entities on a tick.
+		fn tick(&mut self) -> Result<()> {
+			self.world.tick()
+		}
+
+		fn use_item(&mut self, slot: &mut Option<ItemStack>, x: f32, y: f32) -> Result<Option<u32>> {
+			self.items
+				.use_item(&mut self.world, &self.network, self.user, slot, vec2(x, y))
+		}
+
+		fn health(&self, entity: Entity) -> f32 {
+			self.world.entities.get::<HealthComp>(entity).unwrap().health
+		}
+
+		fn tile(&self, x: i64, y: i64) -> String {
+			let pos: Vector2D<f32, WorldSpace> = vec2(x as f32, y as f32);
+			let pos = TilePos::try_from(pos).unwrap();
+			let tile = self.world.chunks.get_tile(pos).unwrap();
+			let carrier = self.items.carrier.clone().unwrap();
+			let tiles = carrier.get::<TilePrototype>();
+			tiles.tag_from_id(tile.id).to_string()
+		}
+
+		fn set_tile(&mut self, x: i64, y: i64, tile: &str) -> Result<()> {
+			let pos: Vector2D<f32, WorldSpace> = vec2(x as f32, y as f32);
+			let pos = TilePos::try_from(pos).unwrap();
+			let carrier = self.items.carrier.clone().unwrap();
+			let tile = carrier.get::<TilePrototype>().create_from_tag(&Tag::new(format!("hello:{tile}"))?)?;
+			self.world.chunks.set_tile(pos, tile);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn swing_reach_gets_clamped() -> Result<()> {
+		let mut setup = Setup::new()?;
+		let near = setup.target(11.0, 4.0);
+		let far = setup.target(14.0, 4.0);
+		setup.tick()?;
+
+		// Aimed at the far one, but the swing only reaches up to the near one.
+		let mut sword = setup.stack("sword", 1)?;
+		assert_eq!(
+			setup.use_item(&mut sword, 14.5, 5.0)?,
+			Some(ItemPrototype::DEFAULT_USE_TIME)
+		);
+		assert_eq!(setup.health(near), 6.0);
+		assert_eq!(setup.health(far), 10.0);
+		Ok(())
+	}
+
+	#[test]
+	fn thrust_reaches_further() -> Result<()> {
+		let mut setup = Setup::new()?;
+		let near = setup.target(11.0, 4.0);
+		let far = setup.target(14.0, 4.0);
+		setup.tick()?;
+
+		let mut spear = setup.stack("spear", 1)?;
+		setup.use_item(&mut spear, 14.5, 5.0)?;
+		assert_eq!(setup.health(near), 10.0);
+		assert_eq!(setup.health(far), 6.0);
+		// Weapons do not get used up.
+		assert!(spear.is_some());
+		Ok(())
+	}
+
+	#[test]
+	fn hits_respect_contact_layers() -> Result<()> {
+		let mut setup = Setup::new()?;
+		let friend = setup.target(7.0, 4.0);
+		let enemy = setup.target(7.0, 5.0);
+		let contact = |layers, mask| ContactComp {
+			layers,
+			mask,
+			solid: false,
+		};
+		setup.world.entities.add(setup.user, contact(0b01, 0b10));
+		setup.world.entities.add(friend, contact(0b01, 0b01));
+		setup.world.entities.add(enemy, contact(0b10, 0b01));
+		setup.tick()?;
+
+		let mut sword = setup.stack("sword", 1)?;
+		setup.use_item(&mut sword, 7.5, 5.0)?;
+		assert_eq!(setup.health(friend), 10.0);
+		assert_eq!(setup.health(enemy), 6.0);
+		Ok(())
+	}
+
+	#[test]
+	fn mining_needs_enough_power() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.tick()?;
+
+		let mut pickaxe = setup.stack("pickaxe", 1)?;
+		assert_eq!(setup.use_item(&mut pickaxe, 7.5, 3.5)?, Some(15));
+		assert_eq!(setup.tile(7, 3), "rustaria:air");
+
+		// Stone is too hard, but the swing still takes time.
+		assert_eq!(setup.use_item(&mut pickaxe, 9.5, 3.5)?, Some(15));
+		assert_eq!(setup.tile(9, 3), "hello:stone");
+		Ok(())
+	}
+
+	#[test]
+	fn mined_loot_depends_on_the_biome() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.set_tile(6, 3, "clay")?;
+		setup.set_tile(8, 3, "clay")?;
+		setup.tick()?;
+
+		let mut pickaxe = setup.stack("pickaxe", 1)?;
+		setup.use_item(&mut pickaxe, 6.5, 3.5)?;
+		setup.use_item(&mut pickaxe, 8.5, 3.5)?;
+
+		let carrier = setup.items.carrier.clone().unwrap();
+		let items = carrier.get::<ItemPrototype>();
+		let mut drops: Vec<(String, f32)> = setup
+			.world
+			.entities
+			.query::<(&ItemComp, &PositionComp)>()
+			.iter()
+			.map(|(_, (item, pos))| (items.tag_from_id(item.stack.item().ty).to_string(), pos.position.x))
+			.collect();
+		drops.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+		// Only the clay inside the plains drops a potion.
+		let names: Vec<&str> = drops.iter().map(|(name, _)| name.as_str()).collect();
+		assert_eq!(names, ["hello:potion", "hello:stick", "hello:stick"]);
+		assert!(drops[0].1 < 7.0);
+		Ok(())
+	}
+
+	#[test]
+	fn placing_uses_up_the_stack() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.tick()?;
+
+		let mut dirt = setup.stack("dirt", 2)?;
+		assert!(setup.use_item(&mut dirt, 7.5, 4.5)?.is_some());
+		assert_eq!(setup.tile(7, 4), "hello:dirt");
+		assert_eq!(dirt.as_ref().map(|stack| stack.amount()), Some(1));
+
+		// Something is already there.
+		assert_eq!(setup.use_item(&mut dirt, 7.5, 3.5)?, None);
+		assert_eq!(dirt.as_ref().map(|stack| stack.amount()), Some(1));
+
+		assert!(setup.use_item(&mut dirt, 8.5, 4.5)?.is_some());
+		assert!(dirt.is_none());
+		Ok(())
+	}
+
+	#[test]
+	fn placing_is_blocked_by_entities() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.target(7.0, 4.0);
+		setup.tick()?;
+
+		let mut dirt = setup.stack("dirt", 2)?;
+		assert_eq!(setup.use_item(&mut dirt, 7.5, 4.5)?, None);
+		assert_eq!(setup.tile(7, 4), "rustaria:air");
+		assert_eq!(dirt.as_ref().map(|stack| stack.amount()), Some(2));
+		Ok(())
+	}
+
+	#[test]
+	fn consuming_applies_the_effect() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.tick()?;
+
+		let mut potion = setup.stack("potion", 1)?;
+		assert!(setup.use_item(&mut potion, 0.0, 0.0)?.is_some());
+		assert!(potion.is_none());
+
+		let effects = setup.world.entities.get::<EffectsComp>(setup.user)?;
+		assert_eq!(effects.pending, vec![(Tag::new("hello:swift")?, None)]);
+		Ok(())
+	}
+
+	#[test]
+	fn shooting_spawns_a_projectile() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.tick()?;
+
+		let mut bow = setup.stack("bow", 1)?;
+		assert!(setup.use_item(&mut bow, 10.0, 5.0)?.is_some());
+		assert!(bow.is_some());
+
+		let mut query = setup.world.entities.query::<&ProjectileComp>();
+		let owners: Vec<Option<Entity>> = query.iter().map(|(_, projectile)| projectile.owner).collect();
+		assert_eq!(owners, vec![Some(setup.user)]);
+		Ok(())
+	}
+
+	#[test]
+	fn shooting_at_nothing_spawns_nothing() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.tick()?;
+
+		let mut bow = setup.stack("bow", 1)?;
+		for (x, y) in [(f32::NAN, 5.0), (f32::INFINITY, 5.0), (10.0, f32::NEG_INFINITY)] {
+			setup.use_item(&mut bow, x, y)?;
+		}
+		assert_eq!(setup.world.entities.query::<&ProjectileComp>().iter().count(), 0);
+		setup.tick()?;
+		Ok(())
+	}
+
+	fn drop_item(setup: &mut Setup, item: &str, amount: u32, x: f32) -> Result<Entity> {
+		let stack = setup.stack(item, amount)?.unwrap();
+		setup
+			.items
+			.spawn_item(&mut setup.world, &setup.network, vec2(x, 4.0), stack, 0.0)
+	}
+
+	fn amount(setup: &Setup, item: Entity) -> u32 {
+		setup.world.entities.get::<ItemComp>(item).unwrap().stack.amount()
+	}
+
+	#[test]
+	fn close_stacks_merge() -> Result<()> {
+		let mut setup = Setup::new()?;
+		let first = drop_item(&mut setup, "stick", 60, 2.0)?;
+		let second = drop_item(&mut setup, "stick", 50, 2.5)?;
+		let other = drop_item(&mut setup, "dirt", 5, 3.0)?;
+		let far = drop_item(&mut setup, "stick", 5, 10.0)?;
+		setup.tick()?;
+
+		let carrier = setup.items.carrier.clone().unwrap();
+		let mut removed = HashSet::new();
+		setup.items.merge(&carrier, &mut setup.world, &mut removed);
+
+		// The first one fills up to max_stack, the rest stays behind.
+		assert!(removed.is_empty());
+		assert_eq!(amount(&setup, first), 99);
+		assert_eq!(amount(&setup, second), 11);
+		assert_eq!(amount(&setup, other), 5);
+		assert_eq!(amount(&setup, far), 5);
+
+		setup.world.entities.get_mut::<ItemComp>(second)?.stack = setup.stack("stick", 1)?.unwrap();
+		setup.world.entities.get_mut::<ItemComp>(first)?.stack = setup.stack("stick", 10)?.unwrap();
+		setup.items.merge(&carrier, &mut setup.world, &mut removed);
+		assert_eq!(removed, HashSet::from([second]));
+		assert_eq!(amount(&setup, first), 11);
+		Ok(())
+	}
+
+	#[test]
+	fn dropped_items_wait() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.world.entities.add(setup.user, CollectorComp { range: 5.0 });
+		let stack = setup.stack("stick", 1)?.unwrap();
+		let item = setup
+			.items
+			.spawn_item(&mut setup.world, &setup.network, vec2(5.25, 4.5), stack, DROP_PICKUP_DELAY)?;
+
+		for _ in 0..DROP_PICKUP_DELAY as u32 - 1 {
+			setup.tick()?;
+			assert!(!setup.world.entities.events().contains(&EntityEvent::ItemTouch(item, setup.user)));
+		}
+		setup.tick()?;
+		assert!(setup.world.entities.events().contains(&EntityEvent::ItemTouch(item, setup.user)));
+		Ok(())
+	}
+
+	#[test]
+	fn unusable_items_do_nothing() -> Result<()> {
+		let mut setup = Setup::new()?;
+		setup.tick()?;
+
+		let mut stick = setup.stack("stick", 3)?;
+		assert_eq!(setup.use_item(&mut stick, 7.5, 4.5)?, None);
+		assert_eq!(stick.as_ref().map(|stack| stack.amount()), Some(3));
+
+		let mut nothing = None;
+		assert_eq!(setup.use_item(&mut nothing, 7.5, 4.5)?, None);
+		Ok(())
+	}
+}
@@ -1,4 +1,8 @@
 pub mod chunks;
+pub mod effects;
 pub mod entities;
+pub mod items;
 pub mod networking;
 pub mod players;
+pub mod spawning;
+pub mod validation;
@@ -1,6 +1,7 @@
-use rsa_core::logging::info;
+use rsa_core::logging::{info, warn};
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 
 use rsa_core::error::Result;
 use rsa_core::ty::ChunkPos;
@@ -8,7 +9,9 @@ use rsa_network::packet::compress::Compress;
 use rsa_network::Token;
 
 use crate::chunk::Chunk;
+use crate::entity::systems::server_network::NetworkError;
 use crate::packet::chunk::{ChunkBundlePacket, ServerChunkPacket};
+use crate::packet::player::ServerPlayerPacket;
 use crate::packet::ServerPacket;
 use crate::{ClientPacket, EntityModule, Server, ServerNetwork};
 
@@ -32,6 +35,24 @@ impl NetworkModule {
 		self.chunk_buffer.get_mut(&to).unwrap().insert(pos, chunk);
 	}
 
+	/// Tells the player why and then closes their connection.
+	pub fn disconnect(&mut self, token: Token, reason: String) -> Result<()> {
+		info!(target: "misc@rustaria.network", "Disconnecting {token}, {reason}");
+		self.internal
+			.send(token, ServerPacket::Player(ServerPlayerPacket::Disconnect { reason }))?;
+		self.internal.kick(token);
+		Ok(())
+	}
+
+	/// Disconnects the player like `disconnect` and keeps them out for `duration`.
+	pub fn ban(&mut self, token: Token, reason: String, duration: Duration) -> Result<()> {
+		info!(target: "misc@rustaria.network", "Banning {token} for {duration:?}, {reason}");
+		self.internal
+			.send(token, ServerPacket::Player(ServerPlayerPacket::Disconnect { reason }))?;
+		self.internal.ban(token, duration);
+		Ok(())
+	}
+
 	#[macro_module::module(server.network)]
 	pub fn tick(this: &mut NetworkModule, server: &mut Server) -> Result<()> {
 		for (to, chunks) in this.chunk_buffer.drain() {
@@ -55,19 +76,37 @@ impl NetworkModule {
 		}
 
 		for (from, packet) in data.received {
-			match packet {
-				ClientPacket::Chunk(packet) => server.chunk.packet(from, packet)?,
+			if !server.validation.receive(from) {
+				continue;
+			}
+
+			let result = match packet {
+				ClientPacket::Chunk(packet) => server.chunk.packet(from, packet),
 				ClientPacket::Player(packet) => {
 					server
 						.player
-						.packet(from, packet, &mut server.world, &server.network)?
+						.packet(from, packet, &mut server.world, &server.network, &mut server.item)
+				}
+				ClientPacket::Entity(packet) => EntityModule::packet(server, from, packet),
+			};
+
+			// Players misbehaving is their problem, not a reason to take the server down.
+			// Other failures only lose this packet, the rest and the players leaving still need handling.
+			if let Err(error) = result {
+				match error.downcast_ref::<NetworkError>() {
+					Some(NetworkError::Hacking(infraction)) => server.validation.report(from, *infraction),
+					_ => warn!(target: "tick@rustaria.network", "Packet from {from} failed: {error:#}"),
 				}
-				ClientPacket::Entity(packet) => EntityModule::packet(server, from, packet)?,
 			}
 		}
 
 		for token in data.to_disconnect {
 			info!("{} disconnected", token);
+			server.validation.leave(token);
+			server.entity.leave(token);
+			server
+				.player
+				.leave(token, &mut server.world, &server.network)?;
 		}
 
 		Ok(())
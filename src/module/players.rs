@@ -1,22 +1,50 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 
+use rsa_core::api::carrier::Carrier;
 use rsa_core::api::Api;
-use rsa_core::error::{ContextCompat, Result};
+use rsa_core::error::{bail, ContextCompat, Report, Result, WrapErr};
 use rsa_core::logging::{info, warn};
-use rsa_core::math::vec2;
-use rsa_core::ty::{RawId, Tag};
+use rsa_core::math::{vec2, Vector2D, WorldSpace};
+use rsa_core::settings::UPS;
+use rsa_core::ty::{RawId, Tag, TilePos};
+use rsa_item::recipe::RecipePrototype;
 use rsa_network::Token;
 
+use crate::chunk::layer::tile::TilePrototype;
+use crate::entity::component::custom::CustomComps;
+use crate::entity::component::effect::EffectsComp;
+use crate::entity::component::health::HealthComp;
+use crate::entity::component::humanoid::HumanoidComp;
+use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::pos::PositionComp;
+use crate::entity::component::respawn::RespawnComp;
+use crate::entity::component::stats::StatsComp;
 use crate::entity::prototype::EntityPrototype;
+use crate::entity::{Entity, EntitySystem};
+use crate::module::entities::EntityModule;
+use crate::module::items::{ItemModule, DROP_PICKUP_DELAY};
 use crate::packet::player::{ClientPlayerPacket, ServerPlayerPacket};
-use crate::player::Player;
-use crate::{CarrierUnavailable, NetworkModule, ServerPacket, World};
+use crate::entity::systems::server_network::{HackingInfraction, NetworkError};
+use crate::player::inventory::{InventoryError, InventorySlot};
+use crate::player::profile::{PlayerProfile, ProfileStore, PROFILE_DIR};
+use crate::player::{Player, RespawnStep};
+use crate::world::DEFAULT_SPAWN_POINT;
+use crate::{CarrierUnavailable, NetworkModule, Server, ServerPacket, World};
 use crate::entity::packet::ServerEntityPacket;
 
+/// How long players stay dead.
+pub const RESPAWN_DELAY: u32 = UPS as u32 * 5;
+/// How many tiles away from a player a crafting station still counts.
+pub const STATION_RANGE: i64 = 4;
+
 pub struct PlayerModule {
 	api: Api,
 	player_entity: Option<RawId>,
 	players: HashMap<Token, Player>,
+	/// Connections that may not join, they get disconnected on the next tick.
+	rejected: Vec<(Token, String)>,
+	pub profiles: ProfileStore,
 }
 
 impl PlayerModule {
@@ -25,6 +53,8 @@ impl PlayerModule {
 			api: api.clone(),
 			player_entity: None,
 			players: Default::default(),
+			rejected: vec![],
+			profiles: ProfileStore::new(PathBuf::from(PROFILE_DIR)),
 		}
 	}
 
@@ -32,44 +62,422 @@ impl PlayerModule {
 		self.players.get(token)
 	}
 
+	pub fn players(&self) -> impl Iterator<Item = &Player> {
+		self.players.values()
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&Token, &Player)> {
+		self.players.iter()
+	}
+
+	pub fn get_player_by_entity_mut(&mut self, entity: Entity) -> Option<&mut Player> {
+		self.players
+			.values_mut()
+			.find(|player| player.entity == Some(entity))
+	}
+
 	pub fn join(&mut self, token: Token) {
-		info!("Player joined {}", token);
+		info!(target: "misc@rustaria.player", "Player joined {}", token);
 		self.players.insert(token, Player::new(token.to_string()));
 	}
 
+	/// Removes the entity of the player, forgets about them and saves their profile.
+	/// Saving can fail without taking the server down, that only gets logged.
+	pub fn leave(&mut self, token: Token, world: &mut World, network: &NetworkModule) -> Result<()> {
+		let player = match self.players.remove(&token) {
+			Some(player) => player,
+			None => return Ok(()),
+		};
+
+		info!(target: "misc@rustaria.player", "Player left {}", player.name);
+		let mut position = None;
+		let mut health = None;
+		let mut effects = Vec::new();
+		let mut spawn_point = None;
+		let mut components = BTreeMap::new();
+		if let Some(entity) = player.entity {
+			if let Ok(comp) = world.entities.get::<PositionComp>(entity) {
+				position = Some(comp.position);
+			}
+			if let Ok(comp) = world.entities.get::<HealthComp>(entity) {
+				health = Some(comp.health);
+			}
+			if let Ok(comp) = world.entities.get::<EffectsComp>(entity) {
+				effects = comp.effects.clone();
+			}
+			if let Ok(comp) = world.entities.get::<RespawnComp>(entity) {
+				spawn_point = comp.spawn_point;
+			}
+			if let Ok(comps) = world.entities.get::<CustomComps>(entity) {
+				components = comps.values();
+			}
+
+			world.entities.kill(entity);
+			network.send_all(ServerPacket::Entity(ServerEntityPacket::Kill(entity)))?;
+		}
+
+		// Never joined, nothing to save.
+		let id = match player.id {
+			Some(id) => id,
+			None => return Ok(()),
+		};
+
+		let carrier = self.api.get_carrier();
+		let mut profile = match self.profiles.load(id) {
+			Ok(profile) => profile.unwrap_or_else(|| PlayerProfile::new(id, player.name.clone())),
+			// Everything that matters gets set below anyway.
+			Err(error) => {
+				warn!(target: "misc@rustaria.player", "Could not load the profile of {}, starting a new one: {error:#}", player.name);
+				PlayerProfile::new(id, player.name.clone())
+			}
+		};
+		profile.name = player.name.clone();
+		profile.save_inventory(&carrier, &player.inventory);
+		if player.entity.is_some() {
+			profile.spawn_point = spawn_point;
+			profile.components = components;
+			profile.position = position;
+			profile.health = health;
+			profile.save_effects(&carrier, &effects);
+		}
+
+		if let Err(error) = self.profiles.save(&profile) {
+			warn!(target: "misc@rustaria.player", "Could not save the profile of {}: {error:#}", player.name);
+		}
+		Ok(())
+	}
+
 	pub fn packet(
 		&mut self,
 		from: Token,
 		packet: ClientPlayerPacket,
 		world: &mut World,
 		network: &NetworkModule,
+		items: &mut ItemModule,
 	) -> Result<()> {
+		// The id is whatever the client says, so two connections with the same one would share a profile.
+		if let ClientPlayerPacket::Join { id, .. } = &packet {
+			let online = self
+				.players
+				.iter()
+				.any(|(token, player)| *token != from && player.id == Some(*id));
+			if online {
+				warn!(target: "misc@rustaria.player", "{from} tried to join as player {id} who is already online");
+				self.rejected.push((from, "A player with your id is already online.".to_string()));
+				return Ok(());
+			}
+		}
+
 		if let Some(player) = self.players.get_mut(&from) {
 			match packet {
-				// this should prob be world spawn later on
-				ClientPlayerPacket::Join { .. } => {
+				ClientPlayerPacket::Join { name, id: player_id } => {
+					if player.id.is_some() {
+						warn!(target: "misc@rustaria.player", "Player {} tried to join twice.", player.name);
+						return Ok(());
+					}
+
+					let profile = match self.profiles.load(player_id) {
+						Ok(Some(profile)) => {
+							info!(target: "misc@rustaria.player", "Welcome back {name}");
+							profile
+						}
+						Ok(None) => {
+							info!(target: "misc@rustaria.player", "{name} joined for the first time");
+							PlayerProfile::new(player_id, name.clone())
+						}
+						// Starting over would overwrite their profile on leave, so they can not join until it is fixed.
+						Err(error) => {
+							warn!(target: "misc@rustaria.player", "Could not load the profile of {name}: {error:#}");
+							self.rejected.push((from, "Your profile could not be loaded.".to_string()));
+							return Ok(());
+						}
+					};
+
+					let carrier = self.api.get_carrier();
+					player.id = Some(player_id);
+					player.name = name;
+					player.inventory = profile.load_inventory(&carrier);
+
 					let id = self.player_entity.wrap_err(CarrierUnavailable)?;
-					let pos = vec2(3.0, 50.0);
+					// Everything that already exists, before our own entity so it does not get sent twice.
+					EntityModule::send_world(&world.entities, network, from)?;
+
+					// Players who left while dead start over at their spawn point.
+					let alive = profile.health.map_or(true, |health| health > 0.0);
+					let pos = match profile.position {
+						Some(position) if alive => position,
+						_ => Self::respawn_point(profile.spawn_point, world),
+					};
 					let entity = world.entities.spawn(
 						pos,
 						id,
-						self.api
-							.get_carrier()
-							.get::<EntityPrototype>()
-							.prototype_from_id(id),
+						carrier.get::<EntityPrototype>().prototype_from_id(id),
 					);
 
-					network.send(from, ServerPacket::Entity(ServerEntityPacket::Spawn(0, entity, id)))?;
-					network.send(from, ServerPacket::Entity(ServerEntityPacket::Pos(0, entity, pos)))?;
+					world.entities.add(entity, RespawnComp {
+						dead: false,
+						spawn_point: profile.spawn_point,
+					});
+					// Before the health gets restored, so it can go over the base maximum.
+					Self::equip(&carrier, player, entity, &mut world.entities);
+					if let (Some(health), Ok(mut comp)) = (profile.health, world.entities.get_mut::<HealthComp>(entity)) {
+						if alive {
+							comp.health = health.min(comp.maximum);
+						}
+					}
+					if let Ok(mut comps) = world.entities.get_mut::<CustomComps>(entity) {
+						for name in comps.load(profile.components.clone()) {
+							warn!(target: "misc@rustaria.player", "Component {name} of player {} does not exist anymore", player.name);
+						}
+					}
+					let effects = profile.load_effects(&carrier);
+					if alive && !effects.is_empty() {
+						world.entities.add(entity, EffectsComp {
+							effects: effects.clone(),
+							..EffectsComp::default()
+						});
+					}
+
+					network.send_all(ServerPacket::Entity(ServerEntityPacket::Spawn(0, entity, id)))?;
+					network.send_all(ServerPacket::Entity(ServerEntityPacket::Pos(0, entity, pos)))?;
 					network.send(from, ServerPacket::Player(ServerPlayerPacket::Attach { entity }), )?;
+					if alive && !effects.is_empty() {
+						network.send_all(ServerPacket::Entity(ServerEntityPacket::Effects(entity, effects)))?;
+					}
 
 					player.entity = Some(entity);
 				}
+				ClientPlayerPacket::DropItem { slot } => {
+					let entity = match player.entity {
+						Some(entity) => entity,
+						None => {
+							warn!(target: "misc@rustaria.player", "Player {from} tried to drop an item without a player entity.");
+							return Ok(());
+						}
+					};
+
+					if let Some(stack) = player.inventory.take(slot).map_err(Self::invalid_slot)? {
+						let pos = world.entities.get::<PositionComp>(entity)?.position;
+						items.spawn_item(world, network, pos, stack, DROP_PICKUP_DELAY)?;
+					}
+				}
+				ClientPlayerPacket::ClickSlot { slot, action } => {
+					let carrier = self.api.get_carrier();
+					player
+						.inventory
+						.click(&carrier, slot, action)
+						.map_err(Self::invalid_slot)?;
+				}
+				ClientPlayerPacket::UseItem { slot, target } => {
+					if !target.x.is_finite() || !target.y.is_finite() {
+						// The client always aims somewhere on screen, anything else would poison the positions.
+						return Err(Report::new(NetworkError::Hacking(HackingInfraction::Imposter)));
+					}
+					let entity = match player.entity {
+						Some(entity) => entity,
+						None => return Ok(()),
+					};
+					let dead = world.entities.get::<RespawnComp>(entity).map_or(false, |comp| comp.dead);
+					// Clicking faster than the item allows is not worth a report, the client might just be ahead.
+					if dead || player.use_cooldown > 0 {
+						return Ok(());
+					}
+
+					let stack = player
+						.inventory
+						.hotbar
+						.slot_mut(slot)
+						.ok_or(InventoryError::InvalidSlot(InventorySlot::Hotbar(slot)))
+						.map_err(Self::invalid_slot)?;
+					if let Some(use_time) = items.use_item(world, network, entity, stack, target)? {
+						player.use_cooldown = use_time;
+					}
+				}
+				ClientPlayerPacket::Craft { recipe } => {
+					let carrier = self.api.get_carrier();
+					let registry = carrier.get::<RecipePrototype>();
+					let prototype = registry
+						.get(recipe)
+						.ok_or(NetworkError::Hacking(HackingInfraction::Imposter))?;
+
+					// The client might just be behind, so this is not worth a report.
+					if let Err(error) = Self::craft_for(&carrier, player, world, prototype) {
+						warn!(target: "misc@rustaria.player", "Player {} could not craft: {error:#}", player.name);
+					}
+				}
 			};
 		} else {
-			warn!("Player {from} does not exist.");
+			warn!(target: "misc@rustaria.player", "Player {from} does not exist.");
+		}
+
+		Ok(())
+	}
+
+	/// The client only ever shows real slots, so anything else is made up.
+	fn invalid_slot(error: InventoryError) -> Report {
+		Report::new(error).wrap_err(NetworkError::Hacking(HackingInfraction::Imposter))
+	}
+
+	/// The recipes a player can craft right now with what they carry and the stations around them.
+	pub fn craftable(&self, token: Token, world: &World) -> Vec<RawId> {
+		let player = match self.players.get(&token) {
+			Some(player) => player,
+			None => return vec![],
+		};
+		let carrier = self.api.get_carrier();
+		let stations = match Self::stations_near(&carrier, player, world) {
+			Some(stations) => stations,
+			None => return vec![],
+		};
+
+		let inventory = &player.inventory;
+		carrier
+			.get::<RecipePrototype>()
+			.iter_ids()
+			.filter(|(_, recipe)| {
+				recipe.stations().iter().all(|station| stations.contains(station))
+					&& recipe.has_ingredients(&carrier, &[&inventory.hotbar, &inventory.main])
+			})
+			.map(|(id, _)| id)
+			.collect()
+	}
+
+	/// Crafts `recipe` once for the player. Nothing changes if they can not craft it.
+	pub fn craft(&mut self, token: Token, world: &World, recipe: RawId) -> Result<()> {
+		let carrier = self.api.get_carrier();
+		let player = self.players.get_mut(&token).wrap_err("Player does not exist")?;
+		let registry = carrier.get::<RecipePrototype>();
+		let prototype = registry.get(recipe).wrap_err("Recipe does not exist")?;
+		Self::craft_for(&carrier, player, world, prototype)
+	}
+
+	fn craft_for(carrier: &Carrier, player: &mut Player, world: &World, recipe: &RecipePrototype) -> Result<()> {
+		let stations = Self::stations_near(carrier, player, world).wrap_err("Player is not in the world")?;
+		if let Some(station) = recipe.stations().iter().find(|station| !stations.contains(station)) {
+			bail!("Needs to be close to {station}");
 		}
 
+		let inventory = &mut player.inventory;
+		recipe.craft(carrier, &mut inventory.hotbar, &mut inventory.main)
+	}
+
+	/// Every kind of tile within `STATION_RANGE` of the player, `None` if they have no entity.
+	fn stations_near(carrier: &Carrier, player: &Player, world: &World) -> Option<HashSet<Tag>> {
+		let position = world.entities.get::<PositionComp>(player.entity?).ok()?.position;
+		let (x, y) = (position.x.floor() as i64, position.y.floor() as i64);
+
+		let tiles = carrier.get::<TilePrototype>();
+		let mut stations = HashSet::new();
+		for y in y - STATION_RANGE..=y + STATION_RANGE {
+			for x in x - STATION_RANGE..=x + STATION_RANGE {
+				if x < 0 || y < 0 {
+					continue;
+				}
+
+				let pos = match TilePos::try_from(vec2::<f32, WorldSpace>(x as f32, y as f32)) {
+					Ok(pos) => pos,
+					Err(_) => continue,
+				};
+				if let Some(tile) = world.chunks.get_tile(pos) {
+					stations.insert(tiles.tag_from_id(tile.id).clone());
+				}
+			}
+		}
+		Some(stations)
+	}
+
+	fn respawn_point(spawn_point: Option<Vector2D<f32, WorldSpace>>, world: &World) -> Vector2D<f32, WorldSpace> {
+		spawn_point.or(world.spawn_point).unwrap_or(DEFAULT_SPAWN_POINT)
+	}
+
+	/// Counts down the respawn timers of dead players and brings them back.
+	/// Also keeps the stats of players in line with what they have equipped.
+	#[macro_module::module(server.player)]
+	pub fn tick(this: &mut PlayerModule, server: &mut Server) -> Result<()> {
+		for (token, reason) in this.rejected.drain(..) {
+			server.network.disconnect(token, reason)?;
+		}
+
+		let carrier = this.api.get_carrier();
+		for (token, player) in &mut this.players {
+			let equipment_changed = player.inventory.equipment != player.synced_inventory.equipment;
+			if let (true, Some(entity)) = (equipment_changed, player.entity) {
+				Self::equip(&carrier, player, entity, &mut server.world.entities);
+			}
+
+			Self::sync_inventory(*token, player, &server.network)?;
+			player.use_cooldown = player.use_cooldown.saturating_sub(1);
+
+			let entity = match player.entity {
+				Some(entity) => entity,
+				None => continue,
+			};
+
+			let entities = &mut server.world.entities;
+			let dead = entities.get::<RespawnComp>(entity).map_or(false, |comp| comp.dead);
+			if !dead {
+				continue;
+			}
+
+			match player.tick_respawn() {
+				RespawnStep::Died => {
+					info!(target: "tick@rustaria.player", "{} died", player.name);
+					if let Ok(mut comp) = entities.get_mut::<HumanoidComp>(entity) {
+						comp.dir = vec2(0.0, 0.0);
+						comp.jumping = false;
+					}
+					server.network.send(
+						*token,
+						ServerPacket::Player(ServerPlayerPacket::Died { respawn_in: RESPAWN_DELAY }),
+					)?;
+				}
+				RespawnStep::Respawn => {
+					let spawn_point = entities.get::<RespawnComp>(entity).ok().and_then(|comp| comp.spawn_point);
+					let position = Self::respawn_point(spawn_point, &server.world);
+					let entities = &mut server.world.entities;
+					entities.teleport(entity, position);
+					if let Ok(mut comp) = entities.get_mut::<PhysicsComp>(entity) {
+						comp.velocity = vec2(0.0, 0.0);
+					}
+					if let Ok(mut comp) = entities.get_mut::<HealthComp>(entity) {
+						comp.health = comp.maximum;
+					}
+					if let Ok(mut comp) = entities.get_mut::<RespawnComp>(entity) {
+						comp.dead = false;
+					}
+
+					server.network.send_all(ServerPacket::Entity(ServerEntityPacket::Pos(0, entity, position)))?;
+					server.network.send(
+						*token,
+						ServerPacket::Player(ServerPlayerPacket::Respawn { entity, position }),
+					)?;
+				}
+				RespawnStep::Waiting => {}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Puts the modifiers of the equipped items on the player entity.
+	fn equip(carrier: &Carrier, player: &Player, entity: Entity, entities: &mut EntitySystem) {
+		let modifiers = player.inventory.equipment_modifiers(carrier);
+		StatsComp::modify(carrier, entities, entity, |stats| stats.set_equipment(modifiers));
+	}
+
+	/// Tells the client about every slot that changed since last time.
+	fn sync_inventory(token: Token, player: &mut Player, network: &NetworkModule) -> Result<()> {
+		let changed: Vec<_> = player
+			.inventory
+			.slots()
+			.filter(|slot| player.inventory.get(*slot) != player.synced_inventory.get(*slot))
+			.map(|slot| (slot, player.inventory.get(slot).cloned()))
+			.collect();
+
+		if !changed.is_empty() {
+			player.synced_inventory = player.inventory.clone();
+			network.send(token, ServerPacket::Player(ServerPlayerPacket::Slots(changed)))?;
+		}
 		Ok(())
 	}
 
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use rsa_core::api::carrier::Carrier;
+use rsa_core::api::Api;
+use rsa_core::error::{ContextCompat, Result};
+use rsa_core::logging::warn;
+use rsa_core::math::{vec2, Vector2D, WorldSpace};
+use rsa_core::registry::Registry;
+use rsa_core::settings::UPS;
+use rsa_core::ty::{RawId, TilePos};
+
+use crate::chunk::layer::tile::Tile;
+use crate::chunk::ChunkSystem;
+use crate::entity::component::pos::PositionComp;
+use crate::entity::component::spawned::SpawnedComp;
+use crate::entity::packet::ServerEntityPacket;
+use crate::entity::prototype::EntityPrototype;
+use crate::entity::spawn_rule::{SpawnRulePrototype, SpawnSpot, SpawnZone};
+use crate::world::biome::{biome_at, BiomePrototype};
+use crate::{CarrierUnavailable, Server, ServerPacket, World};
+
+/// How many ticks between every spawn and despawn pass.
+const SPAWN_INTERVAL: u64 = UPS as u64;
+/// How many spots get tried per player every pass.
+const SPAWN_ATTEMPTS: u32 = 4;
+/// How far from a player things can spawn.
+const SPAWN_DISTANCE: f32 = 80.0;
+/// Half of the area a player can see, nothing spawns in here so things dont pop in out of thin air.
+const VIEW_WIDTH: f32 = 48.0;
+const VIEW_HEIGHT: f32 = 28.0;
+/// Spawned entities this far from every player get removed.
+const DESPAWN_DISTANCE: f32 = 128.0;
+/// How far down we look for a floor.
+const FLOOR_SCAN: i64 = 32;
+/// How far up we look for tiles covering the spot.
+const DEPTH_SCAN: i64 = 64;
+/// How many opaque tiles it takes to block out all light.
+const LIGHT_FALLOFF: f32 = 10.0;
+
+/// The SpawnModule naturally spawns entities around players using the spawn rules, and cleans them up when nobody is around.
+pub struct SpawnModule {
+	carrier: Option<Carrier>,
+	rng: SmallRng,
+	ticks: u64,
+}
+
+impl SpawnModule {
+	pub fn new() -> SpawnModule {
+		SpawnModule {
+			carrier: None,
+			rng: SmallRng::from_entropy(),
+			ticks: 0,
+		}
+	}
+
+	#[macro_module::module(server.spawn)]
+	pub fn tick(this: &mut SpawnModule, server: &mut Server) -> Result<()> {
+		this.ticks += 1;
+		if this.ticks % SPAWN_INTERVAL != 0 {
+			return Ok(());
+		}
+
+		let carrier = this.carrier.as_ref().wrap_err(CarrierUnavailable)?;
+		let players: Vec<Vector2D<f32, WorldSpace>> = server
+			.player
+			.players()
+			.filter_map(|player| player.entity)
+			.filter_map(|entity| server.world.entities.get::<PositionComp>(entity).ok())
+			.map(|comp| comp.position)
+			.collect();
+
+		// Despawn
+		let mut despawned = Vec::new();
+		for (entity, (_, pos)) in server
+			.world
+			.entities
+			.query::<(&SpawnedComp, &PositionComp)>()
+			.iter()
+		{
+			let near = players
+				.iter()
+				.any(|player| (*player - pos.position).length() < DESPAWN_DISTANCE);
+			if !near {
+				despawned.push(entity);
+			}
+		}
+
+		for entity in despawned {
+			server.world.entities.kill_later(entity);
+			server
+				.network
+				.send_all(ServerPacket::Entity(ServerEntityPacket::Kill(entity)))?;
+		}
+
+		// Spawn
+		let rules = carrier.get::<SpawnRulePrototype>();
+		let entities = carrier.get::<EntityPrototype>();
+		let biomes = carrier.get::<BiomePrototype>();
+		for &player in &players {
+			let counts = Self::count_spawned(&server.world, player);
+			for _ in 0..SPAWN_ATTEMPTS {
+				let (position, spot) = match Self::find_spot(&mut this.rng, &server.world.chunks, &biomes, player, &players) {
+					Some(spot) => spot,
+					None => continue,
+				};
+
+				let day = server.world.is_day();
+				let candidates: Vec<(RawId, &SpawnRulePrototype)> = rules
+					.iter_ids()
+					.filter(|(id, rule)| {
+						// Rules with an unknown entity got reported on reload.
+						entities.id_from_tag(&rule.entity).is_ok()
+							&& rule.allows(&spot, day)
+							&& rule.max_per_player.map_or(true, |max| {
+								counts.get(&id.index()).copied().unwrap_or(0) < max
+							})
+					})
+					.collect();
+
+				let (rule_id, rule) = match Self::pick(&mut this.rng, &candidates) {
+					Some(rule) => rule,
+					None => continue,
+				};
+
+				let id = match entities.id_from_tag(&rule.entity) {
+					Ok(id) => id,
+					Err(_) => continue,
+				};
+				let prototype = entities.prototype_from_id(id);
+
+				// Stand the entity on the floor.
+				let position = match &prototype.hitbox {
+					Some(hitbox) => position - vec2(0.0, hitbox.hitbox.min_y()),
+					None => position,
+				};
+
+				let entity = server.world.entities.spawn(position, id, prototype);
+				server.world.entities.add(entity, SpawnedComp { rule: rule_id });
+				server
+					.network
+					.send_all(ServerPacket::Entity(ServerEntityPacket::Spawn(0, entity, id)))?;
+				server
+					.network
+					.send_all(ServerPacket::Entity(ServerEntityPacket::Pos(0, entity, position)))?;
+				// Only one spawn per pass, else we get swarmed.
+				break;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Counts the naturally spawned entities around a player for every rule.
+	fn count_spawned(world: &World, player: Vector2D<f32, WorldSpace>) -> HashMap<usize, u32> {
+		let mut counts = HashMap::new();
+		for entity in world.entities.query_radius(player, DESPAWN_DISTANCE) {
+			if let Ok(comp) = world.entities.get::<SpawnedComp>(entity) {
+				*counts.entry(comp.rule.index()).or_insert(0) += 1;
+			}
+		}
+		counts
+	}
+
+	/// Picks a random floor tile around the player which is outside of the view of every player.
+	fn find_spot(
+		rng: &mut SmallRng,
+		chunks: &ChunkSystem,
+		biomes: &Registry<BiomePrototype>,
+		player: Vector2D<f32, WorldSpace>,
+		players: &[Vector2D<f32, WorldSpace>],
+	) -> Option<(Vector2D<f32, WorldSpace>, SpawnSpot)> {
+		let x = rng.gen_range(-SPAWN_DISTANCE..SPAWN_DISTANCE);
+		let y = rng.gen_range(-SPAWN_DISTANCE..SPAWN_DISTANCE);
+		let x = (player.x + x).floor() as i64;
+		let start = (player.y + y).floor() as i64;
+
+		// Look for a solid tile with air on top.
+		let mut floor = None;
+		for y in (start - FLOOR_SCAN..start).rev() {
+			let below = tile(chunks, x, y)?;
+			let above = tile(chunks, x, y + 1)?;
+			if below.collision && !above.collision {
+				floor = Some(y);
+				break;
+			}
+		}
+		let floor = floor?;
+
+		// The floor scan moves the spot, so the view check has to happen on where it ended up.
+		let position = vec2(x as f32 + 0.5, (floor + 1) as f32);
+		if players
+			.iter()
+			.any(|p| (position.x - p.x).abs() < VIEW_WIDTH && (position.y - p.y).abs() < VIEW_HEIGHT)
+		{
+			return None;
+		}
+
+		let mut depth = 0;
+		let mut opaque = 0;
+		for y in floor + 2..floor + 2 + DEPTH_SCAN {
+			match tile(chunks, x, y) {
+				Some(tile) => {
+					if tile.collision {
+						depth += 1;
+					}
+					if tile.opaque {
+						opaque += 1;
+					}
+				}
+				// Unloaded or out of the world counts as sky.
+				None => break,
+			}
+		}
+
+		let zone = if depth == 0 {
+			SpawnZone::Surface
+		} else {
+			SpawnZone::Underground
+		};
+		let spot = SpawnSpot {
+			zone,
+			biome: biome_at(biomes, x as f32, (floor + 1) as f32, zone, depth),
+			depth,
+			light: (1.0 - opaque as f32 / LIGHT_FALLOFF).clamp(0.0, 1.0),
+		};
+
+		Some((position, spot))
+	}
+
+	/// Picks a rule, the weight decides how likely it is to get picked.
+	fn pick<'a>(
+		rng: &mut SmallRng,
+		rules: &[(RawId, &'a SpawnRulePrototype)],
+	) -> Option<(RawId, &'a SpawnRulePrototype)> {
+		let total: u32 = rules.iter().map(|(_, rule)| rule.weight).sum();
+		if total == 0 {
+			return None;
+		}
+
+		let mut roll = rng.gen_range(0..total);
+		for (id, rule) in rules {
+			if roll < rule.weight {
+				return Some((*id, rule));
+			}
+			roll -= rule.weight;
+		}
+		None
+	}
+
+	pub fn reload(&mut self, api: &Api) {
+		let carrier = api.get_carrier();
+		{
+			let entities = carrier.get::<EntityPrototype>();
+			let rules = carrier.get::<SpawnRulePrototype>();
+			for (id, rule) in rules.iter_ids() {
+				if entities.id_from_tag(&rule.entity).is_err() {
+					warn!(target: "reload@rustaria.spawn", "Spawn rule {} spawns unknown entity {}, it gets skipped", rules.tag_from_id(id), rule.entity);
+				}
+			}
+		}
+		self.carrier = Some(carrier);
+	}
+}
+
+fn tile(chunks: &ChunkSystem, x: i64, y: i64) -> Option<&Tile> {
+	if x < 0 || y < 0 {
+		return None;
+	}
+
+	let pos = TilePos::try_from(vec2::<f32, WorldSpace>(x as f32, y as f32)).ok()?;
+	chunks.get_tile(pos)
+}
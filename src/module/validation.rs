@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rsa_core::error::Result;
+use rsa_core::logging::warn;
+use rsa_core::math::{vec2, Vector2D, WorldSpace};
+use rsa_core::settings::UPS;
+use rsa_network::Token;
+
+use crate::entity::component::humanoid::HumanoidComp;
+use crate::entity::component::physics::PhysicsComp;
+use crate::entity::component::pos::PositionComp;
+use crate::entity::component::stats::StatsComp;
+use crate::entity::packet::ServerEntityPacket;
+use crate::entity::systems::server_network::HackingInfraction;
+use crate::{Server, ServerPacket};
+
+/// How the server reacts to players doing things they should not be able to do.
+/// Every infraction adds to the score of the player, which slowly wears off again.
+#[derive(Clone, Debug)]
+pub struct ValidationPolicy {
+	pub sus_score: f32,
+	pub imposter_score: f32,
+	/// How much score wears off every tick.
+	pub decay: f32,
+	/// From this score on impossible movement gets reverted, below it gets let through.
+	pub correct_score: f32,
+	/// From this score on every packet of the player gets dropped for `throttle_ticks`.
+	pub throttle_score: f32,
+	pub throttle_ticks: u64,
+	/// From this score on the player gets kicked. `Emergency` infractions always kick.
+	pub kick_score: f32,
+	/// How long a kicked player can not connect again.
+	pub kick_cooldown: Duration,
+	/// How many packets a player can send every second.
+	pub max_packets_per_second: u32,
+	/// How much faster than their settings players can move before it counts, lag makes movement lumpy.
+	pub speed_tolerance: f32,
+	/// Tiles of movement per tick that are always allowed, for things like stepping up blocks and getting pushed.
+	pub movement_slack: f32,
+}
+
+impl Default for ValidationPolicy {
+	fn default() -> Self {
+		ValidationPolicy {
+			sus_score: 1.0,
+			imposter_score: 10.0,
+			decay: 1.0 / UPS as f32,
+			correct_score: 0.0,
+			throttle_score: 20.0,
+			throttle_ticks: UPS as u64,
+			kick_score: 50.0,
+			kick_cooldown: Duration::from_secs(60 * 5),
+			max_packets_per_second: UPS as u32 * 4,
+			speed_tolerance: 1.5,
+			movement_slack: 0.6,
+		}
+	}
+}
+
+#[derive(Default)]
+struct Record {
+	score: f32,
+	packets: u32,
+	throttled_until: u64,
+	last_position: Option<Vector2D<f32, WorldSpace>>,
+}
+
+/// The ValidationModule keeps track of how trustworthy every player is and acts on it.
+pub struct ValidationModule {
+	pub policy: ValidationPolicy,
+	records: HashMap<Token, Record>,
+	to_kick: Vec<Token>,
+	ticks: u64,
+}
+
+impl ValidationModule {
+	pub fn new(policy: ValidationPolicy) -> ValidationModule {
+		ValidationModule {
+			policy,
+			records: Default::default(),
+			to_kick: vec![],
+			ticks: 0,
+		}
+	}
+
+	/// Counts an incoming packet, returns false if it should be dropped.
+	pub fn receive(&mut self, from: Token) -> bool {
+		let record = self.records.entry(from).or_default();
+		if record.throttled_until > self.ticks {
+			return false;
+		}
+
+		record.packets += 1;
+		if record.packets > self.policy.max_packets_per_second {
+			// Only count it once every second, else a single flood instantly kicks.
+			if record.packets == self.policy.max_packets_per_second + 1 {
+				self.report(from, HackingInfraction::Imposter);
+			}
+			return false;
+		}
+		true
+	}
+
+	pub fn report(&mut self, from: Token, infraction: HackingInfraction) {
+		let record = self.records.entry(from).or_default();
+		record.score += match infraction {
+			HackingInfraction::Sus => self.policy.sus_score,
+			HackingInfraction::Imposter => self.policy.imposter_score,
+			HackingInfraction::Emergency => self.policy.kick_score,
+		};
+		warn!(target: "misc@rustaria.validation", "Player {from} committed an infraction, {infraction}. Score {}", record.score);
+
+		if record.score >= self.policy.kick_score {
+			if !self.to_kick.contains(&from) {
+				self.to_kick.push(from);
+			}
+		} else if record.score >= self.policy.throttle_score {
+			record.throttled_until = self.ticks + self.policy.throttle_ticks;
+		}
+	}
+
+	pub fn leave(&mut self, token: Token) {
+		self.records.remove(&token);
+	}
+
+	/// Lets the score of every player wear off a bit and starts a new second for the packet limit.
+	fn decay(&mut self) {
+		self.ticks += 1;
+		let new_second = self.ticks % UPS as u64 == 0;
+		for record in self.records.values_mut() {
+			record.score = (record.score - self.policy.decay).max(0.0);
+			if new_second {
+				record.packets = 0;
+			}
+		}
+	}
+
+	#[macro_module::module(server.validation)]
+	pub fn tick(this: &mut ValidationModule, server: &mut Server) -> Result<()> {
+		this.decay();
+
+		// The server moved these on purpose, so the jump in position is fine.
+		let teleported = server.world.entities.take_teleported();
+		let players: Vec<_> = server
+			.player
+			.iter()
+			.filter_map(|(token, player)| Some((*token, player.entity?)))
+			.collect();
+
+		for (token, entity) in players {
+			let entities = &mut server.world.entities;
+			let position = match entities.get::<PositionComp>(entity) {
+				Ok(comp) => comp.position,
+				Err(_) => continue,
+			};
+
+			let limit = match entities.get::<HumanoidComp>(entity) {
+				Ok(humanoid) => {
+					let settings = match entities.get::<StatsComp>(entity) {
+						Ok(stats) => humanoid.settings.modified(&stats.modifiers),
+						Err(_) => humanoid.settings.clone(),
+					};
+					// Velocity is capped per tick and then moves the position per tick.
+					let per_tick = (UPS * UPS) as f32;
+					vec2(
+						settings.run_max_speed.abs() / per_tick,
+						settings.jump_speed.abs() / per_tick,
+					) * this.policy.speed_tolerance
+						+ vec2(this.policy.movement_slack, this.policy.movement_slack)
+				}
+				Err(_) => continue,
+			};
+
+			let record = this.records.entry(token).or_default();
+			if teleported.contains(&entity) {
+				record.last_position = Some(position);
+				continue;
+			}
+			let last = match record.last_position.replace(position) {
+				Some(last) => last,
+				None => continue,
+			};
+
+			let moved = position - last;
+			// Falling has no speed limit.
+			if moved.x.abs() <= limit.x && moved.y <= limit.y {
+				continue;
+			}
+
+			this.report(token, HackingInfraction::Sus);
+			let record = this.records.entry(token).or_default();
+			if record.score >= this.policy.correct_score {
+				record.last_position = Some(last);
+				if let Ok(mut comp) = entities.get_mut::<PositionComp>(entity) {
+					comp.position = last;
+				}
+				if let Ok(mut comp) = entities.get_mut::<PhysicsComp>(entity) {
+					comp.velocity = vec2(0.0, 0.0);
+				}
+				server
+					.network
+					.send(token, ServerPacket::Entity(ServerEntityPacket::Pos(0, entity, last)))?;
+			}
+		}
+
+		for token in this.to_kick.drain(..) {
+			server.network.ban(
+				token,
+				"Kicked for breaking the rules too often.".to_string(),
+				this.policy.kick_cooldown,
+			)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_network::Token;
+
+	use crate::entity::systems::server_network::HackingInfraction;
+	use crate::module::validation::{ValidationModule, ValidationPolicy};
+
+	fn validation() -> ValidationModule {
+		ValidationModule::new(ValidationPolicy {
+			sus_score: 1.0,
+			imposter_score: 10.0,
+			decay: 0.5,
+			correct_score: 0.0,
+			throttle_score: 20.0,
+			throttle_ticks: 5,
+			kick_score: 50.0,
+			max_packets_per_second: 3,
+			..ValidationPolicy::default()
+		})
+	}
+
+	#[test]
+	fn infractions_add_up() {
+		let mut validation = validation();
+		let token = Token::new_v4();
+		validation.report(token, HackingInfraction::Sus);
+		validation.report(token, HackingInfraction::Imposter);
+		assert_eq!(validation.records[&token].score, 11.0);
+		assert!(validation.to_kick.is_empty());
+	}
+
+	#[test]
+	fn score_decays() {
+		let mut validation = validation();
+		let token = Token::new_v4();
+		validation.report(token, HackingInfraction::Sus);
+		validation.decay();
+		assert_eq!(validation.records[&token].score, 0.5);
+		validation.decay();
+		validation.decay();
+		assert_eq!(validation.records[&token].score, 0.0);
+	}
+
+	#[test]
+	fn throttles_then_lets_through() {
+		let mut validation = validation();
+		let token = Token::new_v4();
+		validation.report(token, HackingInfraction::Imposter);
+		validation.report(token, HackingInfraction::Imposter);
+		assert!(!validation.receive(token));
+
+		for _ in 0..5 {
+			validation.decay();
+		}
+		assert!(validation.receive(token));
+	}
+
+	#[test]
+	fn floods_get_reported_once() {
+		let mut validation = validation();
+		let token = Token::new_v4();
+		for _ in 0..3 {
+			assert!(validation.receive(token));
+		}
+		for _ in 0..10 {
+			assert!(!validation.receive(token));
+		}
+		assert_eq!(validation.records[&token].score, 10.0);
+	}
+
+	#[test]
+	fn kicks_once() {
+		let mut validation = validation();
+		let token = Token::new_v4();
+		for _ in 0..6 {
+			validation.report(token, HackingInfraction::Imposter);
+		}
+		assert_eq!(validation.to_kick, vec![token]);
+
+		let other = Token::new_v4();
+		validation.report(other, HackingInfraction::Emergency);
+		assert_eq!(validation.to_kick, vec![token, other]);
+	}
+}
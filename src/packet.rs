@@ -1,4 +1,4 @@
-use rsa_network::packet::{Packet, PacketDesc};
+use rsa_network::packet::{OrderKind, Packet, PacketDesc, ReliableKind};
 use serde::{Deserialize, Serialize};
 use crate::entity::packet::{ClientEntityPacket, ServerEntityPacket};
 use crate::packet::chunk::{ClientChunkPacket, ServerChunkPacket};
@@ -21,13 +21,21 @@ pub enum ClientPacket {
 	Player(ClientPlayerPacket),
 }
 
+// Everything relies on packets arriving in the order they got sent, like a spawn before the position of
+// the entity, so remote connections send all of them reliably on one stream.
 impl Packet for ServerPacket {
 	fn get_desc(&self) -> PacketDesc {
-		todo!()
+		PacketDesc {
+			reliable: ReliableKind::Reliable,
+			ordered: OrderKind::Ordered(None),
+		}
 	}
 }
 impl Packet for ClientPacket {
 	fn get_desc(&self) -> PacketDesc {
-		todo!()
+		PacketDesc {
+			reliable: ReliableKind::Reliable,
+			ordered: OrderKind::Ordered(None),
+		}
 	}
 }
@@ -1,17 +1,59 @@
 use hecs::Entity;
 use serde::{Deserialize, Serialize};
 
+use rsa_core::math::{Vector2D, WorldSpace};
+use rsa_item::stack::ItemStack;
+
+use crate::player::inventory::{InventorySlot, SlotAction};
+use crate::player::PlayerId;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum ServerPlayerPacket {
 	/// Responds to the player what entity to attach to and create.
 	Attach {
 		entity: Entity,
 	},
-
+	/// The player died and comes back in `respawn_in` ticks.
+	Died {
+		respawn_in: u32,
+	},
+	/// The player came back at `position`, anything predicted from before is wrong now.
+	Respawn {
+		entity: Entity,
+		position: Vector2D<f32, WorldSpace>,
+	},
+	/// What is in the inventory slots that changed, as the item id and amount.
+	/// This is the only way the client inventory changes.
+	Slots(Vec<(InventorySlot, Option<ItemStack>)>),
+	/// Tells the player why they are getting disconnected, the connection closes right after.
+	Disconnect {
+		reason: String,
+	},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum ClientPlayerPacket {
-	/// Creates a Player Entity
-	Join(),
+	/// Identifies the player and creates their Player Entity
+	Join {
+		name: String,
+		id: PlayerId,
+	},
+	/// Throws the stack in an inventory slot out into the world.
+	DropItem {
+		slot: InventorySlot,
+	},
+	/// Clicks an inventory slot, the server answers with the slots that changed.
+	ClickSlot {
+		slot: InventorySlot,
+		action: SlotAction,
+	},
+	/// Uses the item in a hotbar slot towards `target`.
+	UseItem {
+		slot: u32,
+		target: Vector2D<f32, WorldSpace>,
+	},
+	/// Crafts a recipe once, the result ends up in the inventory.
+	Craft {
+		recipe: RawId,
+	},
 }
\ No newline at end of file
@@ -1,9 +1,38 @@
 use hecs::Entity;
+use rsa_core::ty::Uuid;
+
+use crate::module::players::RESPAWN_DELAY;
+use crate::player::inventory::PlayerInventory;
+
+pub mod inventory;
+pub mod profile;
+
+/// What happened to a dead player on this tick.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RespawnStep {
+	/// The player just died and the timer started.
+	Died,
+	Waiting,
+	/// The timer ran out and the player should come back.
+	Respawn,
+}
+
+/// The id a player picked once and uses on every server, unlike the connection `Token` it stays the same.
+pub type PlayerId = Uuid;
 
 #[derive(Clone)]
 pub struct Player {
+	/// Set once the player sent their join packet.
+	pub id: Option<PlayerId>,
 	pub name: String,
 	pub entity: Option<Entity>,
+	pub inventory: PlayerInventory,
+	/// What the client last got told the inventory looks like.
+	pub(crate) synced_inventory: PlayerInventory,
+	/// Ticks until the player respawns, only set while dead.
+	pub respawn_timer: Option<u32>,
+	/// Ticks until the player can use an item again.
+	pub use_cooldown: u32,
 
 	// Jump
 	pub jump_frames: u32,
@@ -17,8 +46,13 @@ pub struct Player {
 impl Player {
 	pub fn new(name: String) -> Player {
 		Player {
+			id: None,
 			name,
 			entity: None,
+			inventory: PlayerInventory::new(),
+			synced_inventory: PlayerInventory::new(),
+			respawn_timer: None,
+			use_cooldown: 0,
 			jump_frames: 15,
 			jump_speed: 20.0,
 			run_acceleration: 4.8,
@@ -26,4 +60,50 @@ impl Player {
 			run_max_speed: 12.0,
 		}
 	}
+
+	/// Counts down the respawn timer, this only gets called while the player is dead.
+	pub fn tick_respawn(&mut self) -> RespawnStep {
+		match self.respawn_timer {
+			None => {
+				self.respawn_timer = Some(RESPAWN_DELAY);
+				RespawnStep::Died
+			}
+			Some(0) => {
+				self.respawn_timer = None;
+				RespawnStep::Respawn
+			}
+			Some(ticks) => {
+				self.respawn_timer = Some(ticks - 1);
+				RespawnStep::Waiting
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::module::players::RESPAWN_DELAY;
+	use crate::player::{Player, RespawnStep};
+
+	#[test]
+	fn respawns_after_delay() {
+		let mut player = Player::new("Test".to_string());
+		assert_eq!(player.tick_respawn(), RespawnStep::Died);
+		for _ in 0..RESPAWN_DELAY {
+			assert_eq!(player.tick_respawn(), RespawnStep::Waiting);
+		}
+		assert_eq!(player.tick_respawn(), RespawnStep::Respawn);
+		assert_eq!(player.respawn_timer, None);
+	}
+
+	#[test]
+	fn dying_again_restarts_timer() {
+		let mut player = Player::new("Test".to_string());
+		player.tick_respawn();
+		player.tick_respawn();
+		while player.tick_respawn() != RespawnStep::Respawn {}
+
+		assert_eq!(player.tick_respawn(), RespawnStep::Died);
+		assert_eq!(player.respawn_timer, Some(RESPAWN_DELAY));
+	}
 }
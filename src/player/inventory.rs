@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+
+use rsa_core::api::carrier::Carrier;
+use rsa_item::item::{EquipmentSlot, ItemPrototype};
+use rsa_item::stack::ItemStack;
+use rsa_item::stats::StatModifiers;
+use rsa_item::storage::ItemStorage;
+
+/// How many slots the main inventory has.
+pub const INVENTORY_SIZE: u32 = 40;
+/// How many slots the hotbar has.
+pub const HOTBAR_SIZE: u32 = 10;
+/// What kind of item goes in each equipment slot.
+pub const EQUIPMENT_SLOTS: [EquipmentSlot; 8] = [
+	EquipmentSlot::Head,
+	EquipmentSlot::Chest,
+	EquipmentSlot::Legs,
+	EquipmentSlot::Accessory,
+	EquipmentSlot::Accessory,
+	EquipmentSlot::Accessory,
+	EquipmentSlot::Accessory,
+	EquipmentSlot::Accessory,
+];
+
+/// A slot somewhere in the inventory of a player.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InventorySlot {
+	Main(u32),
+	Hotbar(u32),
+	/// One of the [`EQUIPMENT_SLOTS`], these only ever hold a single item that fits them.
+	Equipment(u32),
+	/// The stack the player is holding with their mouse.
+	Cursor,
+}
+
+/// What a player does when clicking a slot, everything goes through the cursor.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SlotAction {
+	/// Takes the slot into an empty cursor, puts the cursor down into an empty slot,
+	/// tops up a slot of the same item or swaps them.
+	PickUp,
+	/// Puts a single item from the cursor down.
+	Place,
+	/// Takes half of the slot into an empty cursor.
+	Split,
+	/// Moves the slot over between the hotbar and the main inventory, equipment goes to the main inventory.
+	QuickMove,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum InventoryError {
+	#[error("Slot {0:?} does not exist")]
+	InvalidSlot(InventorySlot),
+}
+
+/// Everything a player carries. Only the server changes this, the client gets told what the slots contain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerInventory {
+	pub main: ItemStorage,
+	pub hotbar: ItemStorage,
+	pub equipment: ItemStorage,
+	pub cursor: Option<ItemStack>,
+}
+
+impl PlayerInventory {
+	pub fn new() -> PlayerInventory {
+		PlayerInventory {
+			main: ItemStorage::new(INVENTORY_SIZE),
+			hotbar: ItemStorage::new(HOTBAR_SIZE),
+			equipment: ItemStorage::new(EQUIPMENT_SLOTS.len() as u32),
+			cursor: None,
+		}
+	}
+
+	pub fn get(&self, slot: InventorySlot) -> Option<&ItemStack> {
+		match slot {
+			InventorySlot::Main(pos) => self.main.get(pos),
+			InventorySlot::Hotbar(pos) => self.hotbar.get(pos),
+			InventorySlot::Equipment(pos) => self.equipment.get(pos),
+			InventorySlot::Cursor => self.cursor.as_ref(),
+		}
+	}
+
+	pub fn slot_mut(&mut self, slot: InventorySlot) -> Result<&mut Option<ItemStack>, InventoryError> {
+		match slot {
+			InventorySlot::Main(pos) => self.main.slot_mut(pos),
+			InventorySlot::Hotbar(pos) => self.hotbar.slot_mut(pos),
+			InventorySlot::Equipment(pos) => self.equipment.slot_mut(pos),
+			InventorySlot::Cursor => Some(&mut self.cursor),
+		}
+		.ok_or(InventoryError::InvalidSlot(slot))
+	}
+
+	/// Every slot, the cursor comes last.
+	pub fn slots(&self) -> impl Iterator<Item = InventorySlot> {
+		(0..self.main.len())
+			.map(InventorySlot::Main)
+			.chain((0..self.hotbar.len()).map(InventorySlot::Hotbar))
+			.chain((0..self.equipment.len()).map(InventorySlot::Equipment))
+			.chain(std::iter::once(InventorySlot::Cursor))
+	}
+
+	/// If `stack` can go into equipment slot `pos`.
+	pub fn can_equip(carrier: &Carrier, pos: u32, stack: &ItemStack) -> bool {
+		match EQUIPMENT_SLOTS.get(pos as usize) {
+			Some(kind) => carrier.get::<ItemPrototype>().prototype_from_id(stack.item().ty).equipment == Some(*kind),
+			None => false,
+		}
+	}
+
+	/// The modifiers of everything equipped, combined.
+	pub fn equipment_modifiers(&self, carrier: &Carrier) -> StatModifiers {
+		let registry = carrier.get::<ItemPrototype>();
+		let mut modifiers = StatModifiers::default();
+		for pos in 0..self.equipment.len() {
+			if let Some(stack) = self.equipment.get(pos) {
+				modifiers.combine(&registry.prototype_from_id(stack.item().ty).modifiers);
+			}
+		}
+		modifiers
+	}
+
+	/// Picks up a stack, filling the hotbar first. Returns whatever did not fit.
+	pub fn give(&mut self, carrier: &Carrier, stack: ItemStack) -> Option<ItemStack> {
+		let leftover = self.hotbar.insert(carrier, stack)?;
+		self.main.insert(carrier, leftover)
+	}
+
+	/// Takes the whole stack out of a slot.
+	pub fn take(&mut self, slot: InventorySlot) -> Result<Option<ItemStack>, InventoryError> {
+		Ok(self.slot_mut(slot)?.take())
+	}
+
+	/// Runs a click on a slot. Items only ever move, so nothing gets made or lost here.
+	pub fn click(
+		&mut self,
+		carrier: &Carrier,
+		slot: InventorySlot,
+		action: SlotAction,
+	) -> Result<(), InventoryError> {
+		// You click with the cursor, not on it. This is just a mis-click, not worth a report.
+		if slot == InventorySlot::Cursor {
+			return Ok(());
+		}
+
+		if action == SlotAction::QuickMove {
+			let stack = self.take(slot)?;
+
+			if let Some(stack) = stack {
+				let other = match slot {
+					InventorySlot::Main(_) => &mut self.hotbar,
+					_ => &mut self.main,
+				};
+				let leftover = other.insert(carrier, stack);
+				*self.slot_mut(slot)? = leftover;
+			}
+			return Ok(());
+		}
+
+		let (target, cursor) = match slot {
+			InventorySlot::Main(pos) => (self.main.slot_mut(pos), &mut self.cursor),
+			InventorySlot::Hotbar(pos) => (self.hotbar.slot_mut(pos), &mut self.cursor),
+			InventorySlot::Equipment(pos) => return self.click_equipment(carrier, pos, action),
+			InventorySlot::Cursor => return Ok(()),
+		};
+		let target = target.ok_or(InventoryError::InvalidSlot(slot))?;
+
+		match action {
+			SlotAction::PickUp => match (target.take(), cursor.take()) {
+				(Some(mut stack), Some(held)) if stack.stacks_with(&held) => {
+					*cursor = stack.merge(carrier, held);
+					*target = Some(stack);
+				}
+				// Swaps, or moves if one of them is empty.
+				(stack, held) => {
+					*target = held;
+					*cursor = stack;
+				}
+			},
+			SlotAction::Place => {
+				let held = match cursor.as_mut() {
+					Some(held) => held,
+					None => return Ok(()),
+				};
+
+				let fits = match target.as_ref() {
+					None => true,
+					Some(stack) => {
+						let max_stack = carrier
+							.get::<ItemPrototype>()
+							.prototype_from_id(stack.item().ty)
+							.max_stack;
+						stack.stacks_with(held) && stack.amount() < max_stack
+					}
+				};
+
+				if fits {
+					let one = match held.split(1) {
+						Some(one) => one,
+						// The last one.
+						None => cursor.take().unwrap(),
+					};
+
+					match target.as_mut() {
+						None => *target = Some(one),
+						Some(stack) => {
+							stack.merge(carrier, one);
+						}
+					}
+				}
+			}
+			SlotAction::Split => {
+				if cursor.is_none() {
+					let half = match target.as_mut() {
+						Some(stack) => stack.split((stack.amount() + 1) / 2),
+						None => return Ok(()),
+					};
+					// A single item just gets picked up.
+					*cursor = half.or_else(|| target.take());
+				}
+			}
+			SlotAction::QuickMove => unreachable!("handled above"),
+		}
+
+		Ok(())
+	}
+
+	/// Equipment slots only take a single item of the right kind, so they never stack or split.
+	fn click_equipment(&mut self, carrier: &Carrier, pos: u32, action: SlotAction) -> Result<(), InventoryError> {
+		let target = self
+			.equipment
+			.slot_mut(pos)
+			.ok_or(InventoryError::InvalidSlot(InventorySlot::Equipment(pos)))?;
+		let cursor = &mut self.cursor;
+
+		let held = match cursor.as_mut() {
+			Some(held) => held,
+			None => {
+				if matches!(action, SlotAction::PickUp | SlotAction::Split) {
+					*cursor = target.take();
+				}
+				return Ok(());
+			}
+		};
+
+		if !Self::can_equip(carrier, pos, held) {
+			return Ok(());
+		}
+
+		match action {
+			// Swapping only works with a single item, otherwise one gets put down.
+			SlotAction::PickUp if held.amount() == 1 => std::mem::swap(target, cursor),
+			SlotAction::PickUp | SlotAction::Place if target.is_none() => {
+				*target = match held.split(1) {
+					Some(one) => Some(one),
+					// The last one.
+					None => cursor.take(),
+				};
+			}
+			_ => {}
+		}
+		Ok(())
+	}
+}
+
+impl Default for PlayerInventory {
+	fn default() -> Self {
+		PlayerInventory::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use proptest::prelude::*;
+	use rsa_core::api::carrier::Carrier;
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+	use rsa_item::item::{Item, ItemPrototype};
+	use rsa_item::stack::ItemStack;
+
+	use crate::player::inventory::{
+		InventorySlot, PlayerInventory, SlotAction, EQUIPMENT_SLOTS, HOTBAR_SIZE, INVENTORY_SIZE,
+	};
+
+	fn carrier() -> Result<Carrier> {
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["item"]:insert {
+				["stick"] = { max_stack = 10 },
+				["stone"] = { max_stack = 99 },
+				["helmet"] = { max_stack = 5, equipment = "Head" },
+			}
+			"#,
+		);
+		reload!((ItemPrototype) => api);
+		Ok(api.get_carrier())
+	}
+
+	fn items(carrier: &Carrier) -> [Item; 3] {
+		let registry = carrier.get::<ItemPrototype>();
+		["stick", "stone", "helmet"].map(|name| {
+			registry
+				.create_from_tag(&Tag::new(format!("hello:{name}")).unwrap())
+				.unwrap()
+		})
+	}
+
+	/// Sticks, stones and helmets spread over the first few hotbar slots.
+	fn inventory(carrier: &Carrier, items: &[Item; 3]) -> PlayerInventory {
+		let mut inventory = PlayerInventory::new();
+		for (item, amount) in items.iter().zip([25, 150, 3]) {
+			assert!(inventory
+				.give(carrier, ItemStack::new(item.clone(), Some(amount)))
+				.is_none());
+		}
+		inventory
+	}
+
+	fn count(inventory: &PlayerInventory, item: &Item) -> u32 {
+		inventory
+			.slots()
+			.filter_map(|slot| inventory.get(slot))
+			.filter(|stack| stack.item() == item)
+			.map(|stack| stack.amount())
+			.sum()
+	}
+
+	fn check(carrier: &Carrier, inventory: &PlayerInventory) {
+		for slot in inventory.slots() {
+			if let Some(stack) = inventory.get(slot) {
+				let prototype = carrier.get::<ItemPrototype>().prototype_from_id(stack.item().ty).clone();
+				assert!(stack.amount() > 0, "Empty stack in {slot:?}");
+				assert!(stack.amount() <= prototype.max_stack, "{slot:?} is over max_stack");
+				if let InventorySlot::Equipment(pos) = slot {
+					assert_eq!(stack.amount(), 1, "{slot:?} holds more than one item");
+					assert_eq!(prototype.equipment, Some(EQUIPMENT_SLOTS[pos as usize]));
+				}
+			}
+		}
+	}
+
+	fn slot() -> impl Strategy<Value = InventorySlot> {
+		// Mostly the slots that have something in them, and sometimes one past the end.
+		prop_oneof![
+			(0..4u32).prop_map(InventorySlot::Main),
+			Just(InventorySlot::Main(INVENTORY_SIZE)),
+			(0..6u32).prop_map(InventorySlot::Hotbar),
+			Just(InventorySlot::Hotbar(HOTBAR_SIZE)),
+			(0..2u32).prop_map(InventorySlot::Equipment),
+			Just(InventorySlot::Equipment(EQUIPMENT_SLOTS.len() as u32)),
+			Just(InventorySlot::Cursor),
+		]
+	}
+
+	fn action() -> impl Strategy<Value = SlotAction> {
+		prop_oneof![
+			Just(SlotAction::PickUp),
+			Just(SlotAction::Place),
+			Just(SlotAction::Split),
+			Just(SlotAction::QuickMove),
+		]
+	}
+
+	#[test]
+	fn clicking_the_cursor_is_not_an_error() -> Result<()> {
+		let carrier = carrier()?;
+		let items = items(&carrier);
+		let mut inventory = inventory(&carrier, &items);
+		inventory.click(&carrier, InventorySlot::Hotbar(0), SlotAction::Split)?;
+
+		let before = inventory.clone();
+		for action in [SlotAction::PickUp, SlotAction::Place, SlotAction::Split, SlotAction::QuickMove] {
+			inventory.click(&carrier, InventorySlot::Cursor, action)?;
+			assert_eq!(inventory, before);
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn equipment_only_takes_what_fits() -> Result<()> {
+		let carrier = carrier()?;
+		let items = items(&carrier);
+		let mut inventory = inventory(&carrier, &items);
+
+		// Sticks are not equipment.
+		inventory.click(&carrier, InventorySlot::Hotbar(0), SlotAction::PickUp)?;
+		let before = inventory.clone();
+		inventory.click(&carrier, InventorySlot::Equipment(0), SlotAction::Place)?;
+		assert_eq!(inventory, before);
+		inventory.click(&carrier, InventorySlot::Hotbar(0), SlotAction::PickUp)?;
+
+		// Helmets only go on the head, one at a time.
+		inventory.click(&carrier, InventorySlot::Hotbar(5), SlotAction::PickUp)?;
+		let before = inventory.clone();
+		inventory.click(&carrier, InventorySlot::Equipment(1), SlotAction::Place)?;
+		assert_eq!(inventory, before);
+
+		inventory.click(&carrier, InventorySlot::Equipment(0), SlotAction::PickUp)?;
+		assert_eq!(inventory.equipment.get(0).map(|stack| stack.amount()), Some(1));
+		assert_eq!(inventory.cursor.as_ref().map(|stack| stack.amount()), Some(2));
+		check(&carrier, &inventory);
+		Ok(())
+	}
+
+	proptest! {
+		#[test]
+		fn clicks_conserve_items(clicks in prop::collection::vec((slot(), action()), 1..64)) {
+			let carrier = carrier().unwrap();
+			let items = items(&carrier);
+			let mut inventory = inventory(&carrier, &items);
+			let expected: Vec<u32> = items.iter().map(|item| count(&inventory, item)).collect();
+
+			for (slot, action) in clicks {
+				let before = inventory.clone();
+				if inventory.click(&carrier, slot, action).is_err() {
+					// Only slots that do not exist fail, and those do not touch anything.
+					prop_assert!(inventory.slot_mut(slot).is_err());
+					prop_assert_eq!(&inventory, &before);
+				}
+
+				check(&carrier, &inventory);
+				let counts: Vec<u32> = items.iter().map(|item| count(&inventory, item)).collect();
+				prop_assert_eq!(&counts, &expected);
+			}
+		}
+	}
+}
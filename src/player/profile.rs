@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use rsa_core::api::carrier::Carrier;
+use rsa_core::error::{Result, WrapErr};
+use rsa_core::logging::warn;
+use rsa_core::math::{Vector2D, WorldSpace};
+use rsa_core::ty::Tag;
+use rsa_item::save::{SavedStack, SavedStorage};
+
+use crate::api::data::LuaData;
+use crate::entity::component::effect::ActiveEffect;
+use crate::entity::status_effect::StatusEffectPrototype;
+use crate::player::inventory::{PlayerInventory, EQUIPMENT_SLOTS, HOTBAR_SIZE, INVENTORY_SIZE};
+use crate::player::PlayerId;
+
+/// Where profiles get saved if nothing else is set.
+pub const PROFILE_DIR: &str = "profiles";
+
+/// Everything the server remembers about a player between sessions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerProfile {
+	pub id: PlayerId,
+	pub name: String,
+	/// Where the player was when they left, they continue from here.
+	pub position: Option<Vector2D<f32, WorldSpace>>,
+	/// Where the player comes back to, the world spawn if unset.
+	pub spawn_point: Option<Vector2D<f32, WorldSpace>>,
+	pub health: Option<f32>,
+	/// The status effects the player had, they keep running once the player is back.
+	#[serde(default)]
+	pub effects: Vec<SavedEffect>,
+	/// The custom components of the player entity.
+	#[serde(default)]
+	pub components: BTreeMap<String, LuaData>,
+	/// The inventory slots, items are stored by tag so they survive plugins changing.
+	pub inventory: SavedStorage,
+	#[serde(default)]
+	pub hotbar: SavedStorage,
+	#[serde(default)]
+	pub equipment: SavedStorage,
+	#[serde(default)]
+	pub cursor: Option<SavedStack>,
+}
+
+impl PlayerProfile {
+	pub fn new(id: PlayerId, name: String) -> PlayerProfile {
+		PlayerProfile {
+			id,
+			name,
+			position: None,
+			spawn_point: None,
+			health: None,
+			effects: vec![],
+			components: BTreeMap::new(),
+			inventory: SavedStorage::default(),
+			hotbar: SavedStorage::default(),
+			equipment: SavedStorage::default(),
+			cursor: None,
+		}
+	}
+
+	/// Builds the inventory back up, whatever had to be fixed on the way gets logged.
+	pub fn load_inventory(&self, carrier: &Carrier) -> PlayerInventory {
+		let mut issues = Vec::new();
+		let mut inventory = PlayerInventory {
+			main: self.inventory.load(carrier, INVENTORY_SIZE, &mut issues),
+			hotbar: self.hotbar.load(carrier, HOTBAR_SIZE, &mut issues),
+			equipment: self.equipment.load(carrier, EQUIPMENT_SLOTS.len() as u32, &mut issues),
+			cursor: self.cursor.as_ref().and_then(|cursor| cursor.load(carrier, &mut issues)),
+		};
+
+		for issue in issues {
+			warn!(target: "misc@rustaria.player", "Inventory of player {}: {issue}", self.name);
+		}
+
+		// Items can stop being equipment when plugins change, those go back into the inventory if they fit.
+		for pos in 0..inventory.equipment.len() {
+			let fits = match inventory.equipment.get(pos) {
+				Some(stack) => PlayerInventory::can_equip(carrier, pos, stack),
+				None => continue,
+			};
+			if !fits {
+				let stack = inventory.equipment.take(pos).unwrap();
+				warn!(target: "misc@rustaria.player", "Inventory of player {}: Equipment slot {pos} held something that is not equipment", self.name);
+				if let Some(leftover) = inventory.give(carrier, stack) {
+					inventory.equipment.set(pos, Some(leftover)).unwrap();
+				}
+			}
+		}
+		inventory
+	}
+
+	/// The saved effects which still exist, the others get logged and dropped.
+	pub fn load_effects(&self, carrier: &Carrier) -> Vec<ActiveEffect> {
+		let registry = carrier.get::<StatusEffectPrototype>();
+		self.effects
+			.iter()
+			.filter_map(|saved| match registry.id_from_tag(&saved.effect) {
+				Ok(id) => Some(ActiveEffect {
+					id,
+					remaining: saved.remaining,
+					stacks: saved.stacks,
+					age: 0,
+				}),
+				Err(_) => {
+					warn!(target: "misc@rustaria.player", "Effect {} of player {} does not exist anymore", saved.effect, self.name);
+					None
+				}
+			})
+			.collect()
+	}
+
+	pub fn save_effects(&mut self, carrier: &Carrier, effects: &[ActiveEffect]) {
+		let registry = carrier.get::<StatusEffectPrototype>();
+		self.effects = effects
+			.iter()
+			.map(|effect| SavedEffect {
+				effect: registry.tag_from_id(effect.id).clone(),
+				remaining: effect.remaining,
+				stacks: effect.stacks,
+			})
+			.collect();
+	}
+
+	pub fn save_inventory(&mut self, carrier: &Carrier, inventory: &PlayerInventory) {
+		self.inventory = SavedStorage::save(carrier, &inventory.main);
+		self.hotbar = SavedStorage::save(carrier, &inventory.hotbar);
+		self.equipment = SavedStorage::save(carrier, &inventory.equipment);
+		self.cursor = inventory.cursor.as_ref().map(|stack| SavedStack::save(carrier, stack));
+	}
+}
+
+/// An [`ActiveEffect`] stored by tag, for the same reason items are.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedEffect {
+	pub effect: Tag,
+	pub remaining: u32,
+	pub stacks: u32,
+}
+
+/// Reads and writes profiles as a json file per player.
+pub struct ProfileStore {
+	dir: PathBuf,
+}
+
+impl ProfileStore {
+	pub fn new(dir: PathBuf) -> ProfileStore {
+		ProfileStore { dir }
+	}
+
+	fn path(&self, id: PlayerId) -> PathBuf {
+		self.dir.join(format!("{id}.json"))
+	}
+
+	/// Loads the profile of a player, `None` if they never played here before.
+	pub fn load(&self, id: PlayerId) -> Result<Option<PlayerProfile>> {
+		let path = self.path(id);
+		if !path.exists() {
+			return Ok(None);
+		}
+
+		let data = fs::read(&path).wrap_err_with(|| format!("Could not read profile {path:?}"))?;
+		let profile = serde_json::from_slice(&data).wrap_err_with(|| format!("Corrupt profile {path:?}"))?;
+		Ok(Some(profile))
+	}
+
+	pub fn save(&self, profile: &PlayerProfile) -> Result<()> {
+		fs::create_dir_all(&self.dir)?;
+		let path = self.path(profile.id);
+		fs::write(&path, serde_json::to_vec_pretty(profile)?)
+			.wrap_err_with(|| format!("Could not write profile {path:?}"))
+	}
+}
@@ -1,23 +1,47 @@
 use rsa_core::error::Result;
+use rsa_core::math::{Vector2D, WorldSpace};
+use rsa_core::settings::UPS;
 
 use crate::chunk::ChunkSystem;
 use crate::entity::EntitySystem;
 
+pub mod biome;
+
+/// How many ticks a full day and night takes.
+pub const DAY_LENGTH: u64 = UPS as u64 * 60 * 20;
+/// Where players go when the generator could not find a world spawn.
+pub const DEFAULT_SPAWN_POINT: Vector2D<f32, WorldSpace> = Vector2D::new(3.0, 50.0);
+
 pub struct World {
 	pub entities: EntitySystem,
 	pub chunks: ChunkSystem,
+	/// Ticks since the world started.
+	pub time: u64,
+	/// Everything random in the world that is not the terrain rolls from this, like loot.
+	pub seed: u64,
+	/// Where players show up if they have no spawn point of their own, found when the server reloads.
+	pub spawn_point: Option<Vector2D<f32, WorldSpace>>,
 }
 
 impl World {
 	pub fn new() -> World {
 		World {
 			entities: EntitySystem::new(),
-			chunks: ChunkSystem::new()
+			chunks: ChunkSystem::new(),
+			time: 0,
+			seed: rand::random(),
+			spawn_point: None,
 		}
 	}
 
+	/// The first half of every day is day, the rest is night.
+	pub fn is_day(&self) -> bool {
+		self.time % DAY_LENGTH < DAY_LENGTH / 2
+	}
+
 	pub fn tick(&mut self) -> Result<()> {
-		self.entities.tick(&self.chunks, 1.0)?;
+		self.time += 1;
+		self.entities.tick(&self.chunks)?;
 		// nothing to tick on chunks yet
 
 		Ok(())
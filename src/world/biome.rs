@@ -0,0 +1,101 @@
+use serde::Deserialize;
+
+use rsa_core::registry::Registry;
+use rsa_core::ty::{Prototype, RawId, Tag};
+
+use crate::entity::spawn_rule::{SpawnRange, SpawnZone};
+
+/// A part of the world which spawn rules and loot tables can be limited to.
+/// If more than one biome fits a spot the one with the highest priority wins.
+/// ```lua
+/// reload.registry.biome:insert {
+///     ["caves"] = { zones = { "Underground" } },
+///     ["depths"] = { zones = { "Underground" }, depth = { min = 16 }, priority = 1 },
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize, frogelua::FromLua)]
+pub struct BiomePrototype {
+	/// Every zone fits if this is not set.
+	pub zones: Option<Vec<SpawnZone>>,
+	/// The tile columns this covers.
+	pub x: SpawnRange,
+	/// The tile rows this covers.
+	pub y: SpawnRange,
+	/// How many solid tiles can be above the spot.
+	pub depth: SpawnRange,
+	pub priority: Option<i32>,
+}
+
+impl Prototype for BiomePrototype {
+	type Item = BiomePrototype;
+
+	fn create(&self, _: RawId) -> Self::Item {
+		self.clone()
+	}
+
+	fn lua_registry_name() -> &'static str {
+		"biome"
+	}
+}
+
+impl BiomePrototype {
+	pub fn contains(&self, x: f32, y: f32, zone: SpawnZone, depth: u32) -> bool {
+		let zone_fits = match &self.zones {
+			Some(zones) => zones.contains(&zone),
+			None => true,
+		};
+
+		zone_fits && self.x.contains(x) && self.y.contains(y) && self.depth.contains(depth as f32)
+	}
+}
+
+/// Finds the biome of a spot, `None` if no biome fits it.
+pub fn biome_at(biomes: &Registry<BiomePrototype>, x: f32, y: f32, zone: SpawnZone, depth: u32) -> Option<Tag> {
+	let mut best: Option<(RawId, i32)> = None;
+	for (id, biome) in biomes.iter_ids() {
+		let priority = biome.priority.unwrap_or(0);
+		// Ties go to the first biome so every machine picks the same one.
+		if biome.contains(x, y, zone, depth) && best.map_or(true, |(_, best)| priority > best) {
+			best = Some((id, priority));
+		}
+	}
+
+	best.map(|(id, _)| biomes.tag_from_id(id).clone())
+}
+
+#[cfg(test)]
+mod tests {
+	use rsa_core::api::Api;
+	use rsa_core::error::Result;
+	use rsa_core::logging::LevelFilter;
+	use rsa_core::reload;
+	use rsa_core::ty::Tag;
+
+	use crate::entity::spawn_rule::SpawnZone;
+	use crate::world::biome::{biome_at, BiomePrototype};
+
+	#[test]
+	fn highest_priority_wins() -> Result<()> {
+		rsa_core::initialize(LevelFilter::Off)?;
+		let mut api = Api::new_test();
+		api.load_simple_plugin(
+			r#"
+			reload.registry["biome"]:insert {
+				["forest"] = { zones = { "Surface" }, x = { max = 50 } },
+				["caves"] = { zones = { "Underground" } },
+				["depths"] = { zones = { "Underground" }, depth = { min = 16 }, priority = 1 },
+			}
+			"#,
+		);
+		reload!((BiomePrototype) => api);
+		let carrier = api.get_carrier();
+		let biomes = carrier.get::<BiomePrototype>();
+
+		let biome = |x, zone, depth| biome_at(&biomes, x, 10.0, zone, depth);
+		assert_eq!(biome(10.0, SpawnZone::Surface, 0), Some(Tag::new("hello:forest").unwrap()));
+		assert_eq!(biome(60.0, SpawnZone::Surface, 0), None);
+		assert_eq!(biome(60.0, SpawnZone::Underground, 4), Some(Tag::new("hello:caves").unwrap()));
+		assert_eq!(biome(60.0, SpawnZone::Underground, 20), Some(Tag::new("hello:depths").unwrap()));
+		Ok(())
+	}
+}